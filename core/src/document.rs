@@ -0,0 +1,651 @@
+//! Parsing the Markdown the editor understands: headings, bold and italic text, escapes,
+//! wiki-style `[[links]]` and fenced code blocks.
+
+use std::fmt::{self, Display, Formatter};
+use std::ops::{Deref, DerefMut, Range};
+
+use nom::branch::alt;
+use nom::bytes::complete::{is_not, tag};
+use nom::character::complete::{char, newline, one_of};
+use nom::combinator::{all_consuming, eof, map, map_res, opt, recognize, rest};
+use nom::multi::{many0, many1, many1_count, many_till};
+use nom::sequence::{delimited, pair, preceded, separated_pair, terminated, tuple};
+use nom::IResult;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Text(String),
+    Heading(usize, Vec<Self>),
+    Emphasis(Emphasis, Vec<Self>),
+    Escaped(char),
+    /// A wiki-style `[[link]]` to another document by its name.
+    Link(String),
+    /// A code block fenced by lines starting with three backticks, kept verbatim with its fences.
+    CodeBlock(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Emphasis {
+    Bold,
+    Italic,
+}
+
+impl Emphasis {
+    /// The characters around emphasized text.
+    pub const fn delimiter(self) -> &'static str {
+        match self {
+            Self::Bold => "**",
+            Self::Italic => "*",
+        }
+    }
+
+    const fn other(self) -> Self {
+        match self {
+            Self::Bold => Self::Italic,
+            Self::Italic => Self::Bold,
+        }
+    }
+}
+
+/// The deepest level of heading Markdown has.
+const MAX_HEADING_DEPTH: usize = 6;
+
+impl Document {
+    /// Parses a document, keeping every character of the input in some segment so the ranges of
+    /// segments line up with it.
+    pub fn parse(input: &str) -> IResult<&str, Self> {
+        let mut segments = Vec::new();
+        let mut remaining = input;
+        // Headings and code blocks only start at the start of a line.
+        let mut line_start = true;
+        while !remaining.is_empty() {
+            let parsed = if line_start {
+                alt((Segment::code_block, Segment::heading, Segment::parse))(remaining)
+            } else {
+                Segment::parse(remaining)
+            };
+            match parsed {
+                Ok((rest, segment)) => {
+                    line_start = remaining[..remaining.len() - rest.len()].ends_with('\n');
+                    segments.push(segment);
+                    remaining = rest;
+                }
+                Err(nom::Err::Error(_)) => break,
+                Err(error) => return Err(error),
+            }
+        }
+        Ok((remaining, Self { segments }))
+    }
+
+    /// Moves every heading some levels deeper, or shallower if the shift is negative, keeping
+    /// them between the first and the last level.
+    pub fn shift_headings(&mut self, shift: isize) {
+        for segment in &mut self.segments {
+            if let Segment::Heading(depth, _) = segment {
+                *depth = depth
+                    .saturating_add_signed(shift)
+                    .clamp(1, MAX_HEADING_DEPTH);
+            }
+        }
+    }
+}
+
+/// Writes the document back out as Markdown.
+impl Display for Document {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.segments.iter().try_for_each(|segment| segment.fmt(f))
+    }
+}
+
+impl Display for Segment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Text(text) => f.write_str(text),
+            Self::Heading(depth, segments) => {
+                write!(f, "{} ", "#".repeat(*depth))?;
+                segments.iter().try_for_each(|segment| segment.fmt(f))
+            }
+            Self::Emphasis(emphasis, segments) => {
+                f.write_str(emphasis.delimiter())?;
+                segments.iter().try_for_each(|segment| segment.fmt(f))?;
+                f.write_str(emphasis.delimiter())
+            }
+            Self::Escaped(char) => write!(f, "\\{char}"),
+            Self::Link(name) => write!(f, "[[{name}]]"),
+            Self::CodeBlock(code) => f.write_str(code),
+        }
+    }
+}
+
+/// Calls a function with every segment of some segments and the byte offset it starts at,
+/// including the segments nested in headings and emphasis right after their parent.
+fn walk(segments: &[Segment], offset: &mut usize, visit: &mut impl FnMut(&Segment, usize)) {
+    for segment in segments {
+        visit(segment, *offset);
+        match segment {
+            Segment::Text(text) => *offset += text.len(),
+            Segment::Heading(depth, segments) => {
+                *offset += depth + 1;
+                walk(segments, offset, visit);
+            }
+            Segment::Emphasis(emphasis, segments) => {
+                *offset += emphasis.delimiter().len();
+                walk(segments, offset, visit);
+                *offset += emphasis.delimiter().len();
+            }
+            Segment::Escaped(char) => *offset += 1 + char.len_utf8(),
+            Segment::Link(name) => *offset += name.len() + 4,
+            Segment::CodeBlock(code) => *offset += code.len(),
+        }
+    }
+}
+
+/// Finds the byte ranges of the plain text in some input, skipping markup like heading hashes,
+/// emphasis delimiters and escapes. Anything that doesn't parse counts as plain text.
+pub fn text_ranges(input: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let remaining = match Document::parse(input) {
+        Ok((remaining, document)) => {
+            walk(&document, &mut 0, &mut |segment, offset| match segment {
+                Segment::Text(text) => ranges.push(offset..offset + text.len()),
+                Segment::Link(name) => ranges.push(offset + 2..offset + 2 + name.len()),
+                _ => {}
+            });
+            remaining
+        }
+        Err(_) => input,
+    };
+    ranges.push(input.len() - remaining.len()..input.len());
+    ranges.retain(|range| !range.is_empty());
+    ranges
+}
+
+/// Finds the wiki-style links in some input, with the byte ranges they span including their
+/// brackets.
+pub fn links(input: &str) -> Vec<(Range<usize>, String)> {
+    let mut links = Vec::new();
+    if let Ok((_, document)) = Document::parse(input) {
+        walk(&document, &mut 0, &mut |segment, offset| {
+            if let Segment::Link(name) = segment {
+                links.push((offset..offset + name.len() + 4, name.clone()));
+            }
+        });
+    }
+    links
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heading {
+    pub depth: usize,
+    pub title: String,
+    /// The byte offset of the start of the heading's line.
+    pub offset: usize,
+}
+
+/// Finds every heading in some text the way the document parser does, so the outline and search
+/// see the headings shown in the editor.
+pub fn headings(text: &str) -> Vec<Heading> {
+    let Ok((_, document)) = Document::parse(text) else {
+        return Vec::new();
+    };
+    let mut offset = 0;
+    let mut headings = Vec::new();
+    for segment in document.iter() {
+        if let Segment::Heading(depth, segments) = segment {
+            let title = segments.iter().map(ToString::to_string).collect::<String>();
+            let title = title.trim();
+            if !title.is_empty() {
+                headings.push(Heading {
+                    depth: *depth,
+                    title: title.to_string(),
+                    offset,
+                });
+            }
+        }
+        offset += segment.to_string().len();
+    }
+    headings
+}
+
+/// Normalizes the name of a note in a link, dropping any heading or alias after `#` or `|`, so
+/// links can be compared with file names.
+pub fn note_name(link: &str) -> String {
+    link.split(['#', '|'])
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase()
+}
+
+/// Joins runs of plain text into one segment.
+fn merge_text(segments: Vec<Segment>) -> Vec<Segment> {
+    let mut merged = Vec::<Segment>::new();
+    for segment in segments {
+        match (merged.last_mut(), segment) {
+            (Some(Segment::Text(text)), Segment::Text(next)) => text.push_str(&next),
+            (_, segment) => merged.push(segment),
+        }
+    }
+    merged
+}
+
+impl Deref for Document {
+    type Target = Vec<Segment>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.segments
+    }
+}
+
+impl DerefMut for Document {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.segments
+    }
+}
+
+impl IntoIterator for Document {
+    type Item = Segment;
+    type IntoIter = std::vec::IntoIter<Segment>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.segments.into_iter()
+    }
+}
+
+impl Segment {
+    /// Parses a segment that isn't a heading. Markup characters that don't start any markup are
+    /// text on their own.
+    fn parse(input: &str) -> IResult<&str, Self> {
+        alt((
+            Self::escaped,
+            Self::link,
+            Self::text,
+            Self::bold,
+            Self::italic,
+            Self::literal("*#\\"),
+        ))(input)
+    }
+
+    /// Parses a heading up to the end of its line, along with the line break if there is one.
+    /// Everything after the hashes is kept, as plain text if it isn't markup.
+    fn heading(input: &str) -> IResult<&str, Self> {
+        map(
+            tuple((
+                separated_pair(
+                    many1_count(tag("#")),
+                    tag(" "),
+                    map_res(is_not("\n"), |line| {
+                        all_consuming(many0(alt((
+                            Self::escaped,
+                            Self::bold,
+                            Self::italic,
+                            Self::link,
+                            Self::text,
+                            Self::literal("*#\\"),
+                        ))))(line)
+                        .map(|(_, segments)| merge_text(segments))
+                    }),
+                ),
+                opt(newline),
+            )),
+            |((depth, mut segments), newline)| {
+                if newline.is_some() {
+                    segments.push(Self::Text("\n".to_string()));
+                }
+                Self::Heading(depth, segments)
+            },
+        )(input)
+    }
+
+    fn bold(input: &str) -> IResult<&str, Self> {
+        Self::emphasis(Emphasis::Bold)(input)
+    }
+
+    fn italic(input: &str) -> IResult<&str, Self> {
+        Self::emphasis(Emphasis::Italic)(input)
+    }
+
+    /// Parses plain text, up to any markup or the end of the line, so what starts the next line
+    /// can be told apart. A bracket that doesn't start a link is text on its own.
+    fn text(input: &str) -> IResult<&str, Self> {
+        map(
+            alt((
+                recognize(terminated(is_not("*#\\[\n"), opt(newline))),
+                tag("\n"),
+                tag("["),
+            )),
+            |text: &str| Self::Text(text.to_string()),
+        )(input)
+    }
+
+    /// Parses a code block from its opening fence up to and including its closing one, or to the
+    /// end of the input if it isn't closed. Like in `CommonMark`, an opening fence can't have
+    /// backticks after it.
+    fn code_block(input: &str) -> IResult<&str, Self> {
+        let fence = || tuple((tag("```"), opt(is_not("\n")), opt(newline)));
+        map(
+            recognize(pair(
+                terminated(
+                    pair(tag("```"), opt(is_not("`\n"))),
+                    alt((recognize(newline), eof)),
+                ),
+                alt((
+                    recognize(many_till(terminated(opt(is_not("\n")), newline), fence())),
+                    rest,
+                )),
+            )),
+            |code: &str| Self::CodeBlock(code.to_string()),
+        )(input)
+    }
+
+    fn link(input: &str) -> IResult<&str, Self> {
+        map(
+            delimited(tag("[["), is_not("[]\n"), tag("]]")),
+            |name: &str| Self::Link(name.to_string()),
+        )(input)
+    }
+
+    fn emphasis(emphasis: Emphasis) -> impl Fn(&str) -> IResult<&str, Self> {
+        let delimiter = emphasis.delimiter();
+        move |input: &str| {
+            map(
+                delimited(
+                    tag(delimiter),
+                    many1(alt((
+                        Self::escaped,
+                        Self::link,
+                        Self::text,
+                        |input| Self::emphasis(emphasis.other())(input),
+                        Self::literal("#\\"),
+                    ))),
+                    tag(delimiter),
+                ),
+                |segments| Self::Emphasis(emphasis, segments),
+            )(input)
+        }
+    }
+
+    /// Parses one of some markup characters as plain text.
+    fn literal(chars: &'static str) -> impl Fn(&str) -> IResult<&str, Self> {
+        move |input: &str| map(one_of(chars), |char: char| Self::Text(char.to_string()))(input)
+    }
+
+    fn escaped(input: &str) -> IResult<&str, Self> {
+        map(preceded(char('\\'), one_of("*#\\")), |char| {
+            Self::Escaped(char)
+        })(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn heading(depth: usize, title: &str, offset: usize) -> Heading {
+        Heading {
+            depth,
+            title: title.to_string(),
+            offset,
+        }
+    }
+
+    #[test]
+    fn parsing_document_works() {
+        let inputs = [
+            "",
+            "# Hello",
+            "This is a test",
+            "## Subheading",
+            "This is **bold**",
+            "This is *italic*",
+            "This is ***bold italic***",
+            "## **Bold subheading**",
+            "## *Italic subheading*",
+            "## ***Bold italic subheading***",
+            "This is a # symbol",
+        ];
+
+        for input in inputs {
+            dbg!(input, all_consuming(Document::parse)(input).unwrap().1);
+        }
+    }
+
+    #[test]
+    fn parsing_headings_works() {
+        assert_eq!(
+            all_consuming(Segment::heading)("# Hello").unwrap().1,
+            Segment::Heading(1, vec![Segment::Text("Hello".to_string())])
+        );
+        assert_eq!(
+            all_consuming(Segment::heading)("## Subheading").unwrap().1,
+            Segment::Heading(2, vec![Segment::Text("Subheading".to_string())])
+        );
+        assert_eq!(
+            all_consuming(Segment::heading)("### Subsubheading")
+                .unwrap()
+                .1,
+            Segment::Heading(3, vec![Segment::Text("Subsubheading".to_string())])
+        );
+        assert_eq!(
+            all_consuming(Segment::heading)("# # Hash heading")
+                .unwrap()
+                .1,
+            Segment::Heading(1, vec![Segment::Text("# Hash heading".to_string())])
+        );
+        assert!(Segment::heading("Not a heading").is_err());
+        assert_eq!(
+            all_consuming(Segment::heading)("# Learning C# \\* and * stray\n")
+                .unwrap()
+                .1,
+            Segment::Heading(
+                1,
+                vec![
+                    Segment::Text("Learning C# ".to_string()),
+                    Segment::Escaped('*'),
+                    Segment::Text(" and * stray".to_string()),
+                    Segment::Text("\n".to_string()),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn parsing_keeps_every_character() {
+        let inputs = [
+            "# Learning C# basics\ntext\n## Escapes \\* here",
+            "# a \\* é\nééé abc",
+            "# a # b\nhello",
+            "# stray * star\n**bold** \\ and *unclosed",
+            "a # not a heading\n# but this is",
+            "```\r\n# code\r\n```\r\n# after",
+            "text ```inline``` more\n````\n# four\n```rust\nunclosed *",
+        ];
+        for input in inputs {
+            let (remaining, document) = Document::parse(input).unwrap();
+            assert_eq!(remaining, "");
+            assert_eq!(document.to_string(), input);
+        }
+        let (_, document) = Document::parse("a # b\n# c").unwrap();
+        assert!(matches!(document[..], [.., Segment::Heading(1, _)]));
+        assert_eq!(
+            document
+                .iter()
+                .filter(|segment| matches!(segment, Segment::Heading(..)))
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn parsing_code_blocks_works() {
+        let input = "Text\n```md\n# Not a heading\n*not italic\n```\n# Heading\n```\nopen # \n";
+        let (remaining, document) = Document::parse(input).unwrap();
+        assert_eq!(remaining, "");
+        assert_eq!(
+            document.segments,
+            [
+                Segment::Text("Text\n".to_string()),
+                Segment::CodeBlock("```md\n# Not a heading\n*not italic\n```\n".to_string()),
+                Segment::Heading(
+                    1,
+                    vec![
+                        Segment::Text("Heading".to_string()),
+                        Segment::Text("\n".to_string())
+                    ]
+                ),
+                Segment::CodeBlock("```\nopen # \n".to_string()),
+            ]
+        );
+        assert_eq!(headings(input), [heading(1, "Heading", 43)]);
+        assert_eq!(text_ranges(input), [0..5, 45..52, 52..53]);
+        // Backticks after an opening fence make it plain text, like inline code.
+        let (_, document) = Document::parse("```a`\n# Heading").unwrap();
+        assert!(matches!(document[..], [.., Segment::Heading(1, _)]));
+    }
+
+    #[test]
+    fn shifting_headings_works() {
+        let (_, mut document) = Document::parse("# One\ntext\n###### Six\n").unwrap();
+        document.shift_headings(1);
+        assert_eq!(document.to_string(), "## One\ntext\n###### Six\n");
+        document.shift_headings(-3);
+        assert_eq!(document.to_string(), "# One\ntext\n### Six\n");
+    }
+
+    #[test]
+    fn finding_text_ranges_works() {
+        let input = "# Head **bold**\nplain \\* *it*";
+        let texts = text_ranges(input)
+            .into_iter()
+            .map(|range| &input[range])
+            .collect::<Vec<_>>();
+        assert_eq!(texts, ["Head ", "bold", "\n", "plain ", " ", "it"]);
+        assert_eq!(text_ranges("# End"), vec![2..5]);
+        let input = "# a # b\nhello";
+        let texts = text_ranges(input)
+            .into_iter()
+            .map(|range| &input[range])
+            .collect::<Vec<_>>();
+        assert_eq!(texts, ["a # b", "\n", "hello"]);
+        let input = "# a \\* é\nééé * x";
+        let texts = text_ranges(input)
+            .into_iter()
+            .map(|range| &input[range])
+            .collect::<Vec<_>>();
+        assert_eq!(texts, ["a ", " é", "\n", "ééé ", "*", " x"]);
+        assert!(text_ranges("").is_empty());
+    }
+
+    #[test]
+    fn writing_documents_works() {
+        let input = "# Title\nSome **bold *and italic*** text \\* here\n## Part\n";
+        let (_, document) = all_consuming(Document::parse)(input).unwrap();
+        assert_eq!(document.to_string(), input);
+    }
+
+    #[test]
+    fn finding_headings_works() {
+        let text = "# One\ntext #not\n## Two **bold**\n#nospace\n\n### Three";
+        assert_eq!(
+            headings(text),
+            [
+                heading(1, "One", 0),
+                heading(2, "Two **bold**", 16),
+                heading(3, "Three", 42),
+            ]
+        );
+        assert!(headings("").is_empty());
+        assert!(headings("#\n# \n").is_empty());
+        let text = "a # not\n# Learning C# \\* now\n";
+        assert_eq!(headings(text), [heading(1, "Learning C# \\* now", 8)]);
+    }
+
+    #[test]
+    fn parsing_links_works() {
+        assert_eq!(
+            all_consuming(Document::parse)("See [[Note Name]] and [this].")
+                .unwrap()
+                .1
+                .segments,
+            [
+                Segment::Text("See ".to_string()),
+                Segment::Link("Note Name".to_string()),
+                Segment::Text(" and ".to_string()),
+                Segment::Text("[".to_string()),
+                Segment::Text("this].".to_string()),
+            ]
+        );
+        assert_eq!(
+            all_consuming(Segment::italic)("*[[Note]]*").unwrap().1,
+            Segment::Emphasis(Emphasis::Italic, vec![Segment::Link("Note".to_string())])
+        );
+        let input = "# [[Head]]\nA **[[b]]** [[c";
+        assert_eq!(
+            links(input),
+            [(2..10, "Head".to_string()), (15..20, "b".to_string())]
+        );
+        assert_eq!(text_ranges("a [[b]]"), [0..2, 4..5]);
+    }
+
+    #[test]
+    fn normalizing_note_names_works() {
+        assert_eq!(note_name(" Note Name "), "note name");
+        assert_eq!(note_name("Note#Heading"), "note");
+        assert_eq!(note_name("Note|shown text"), "note");
+    }
+
+    #[test]
+    fn parsing_emphasis_works() {
+        assert_eq!(
+            all_consuming(Segment::bold)("**bold**").unwrap().1,
+            Segment::Emphasis(Emphasis::Bold, vec![Segment::Text("bold".to_string())])
+        );
+        assert_eq!(
+            all_consuming(Segment::italic)("*italic*").unwrap().1,
+            Segment::Emphasis(Emphasis::Italic, vec![Segment::Text("italic".to_string())])
+        );
+        assert_eq!(
+            all_consuming(Segment::bold)("**nested *italic* bold**")
+                .unwrap()
+                .1,
+            Segment::Emphasis(
+                Emphasis::Bold,
+                vec![
+                    Segment::Text("nested ".to_string()),
+                    Segment::Emphasis(Emphasis::Italic, vec![Segment::Text("italic".to_string())]),
+                    Segment::Text(" bold".to_string())
+                ]
+            )
+        );
+        assert_eq!(
+            all_consuming(Segment::italic)("*nested **bold** italic*")
+                .unwrap()
+                .1,
+            Segment::Emphasis(
+                Emphasis::Italic,
+                vec![
+                    Segment::Text("nested ".to_string()),
+                    Segment::Emphasis(Emphasis::Bold, vec![Segment::Text("bold".to_string())]),
+                    Segment::Text(" italic".to_string())
+                ]
+            )
+        );
+        assert_eq!(
+            all_consuming(Segment::bold)("*** bold italic ***")
+                .unwrap()
+                .1,
+            Segment::Emphasis(
+                Emphasis::Bold,
+                vec![Segment::Emphasis(
+                    Emphasis::Italic,
+                    vec![Segment::Text(" bold italic ".to_string())]
+                )]
+            )
+        );
+    }
+}
@@ -0,0 +1,270 @@
+//! Matching the queries of the editor's find and the folder search, which both find the same
+//! things.
+
+use std::ops::Range;
+
+use regex::{escape, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Query {
+    pub pattern: String,
+    pub regex: bool,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+}
+
+/// Whether a byte range of some text is a whole word, with no letter or digit right before or
+/// after it. Unlike `\b`, this works for words that start or end with punctuation, like `$5`.
+fn is_whole_word(text: &str, range: &Range<usize>) -> bool {
+    let bounded = |char: Option<char>| char.is_none_or(|char| !char.is_alphanumeric());
+    bounded(text[..range.start].chars().next_back()) && bounded(text[range.end..].chars().next())
+}
+
+impl Query {
+    /// Builds the regex the query matches with.
+    pub fn build(&self) -> Result<Regex, regex::Error> {
+        let pattern = if self.regex {
+            self.pattern.clone()
+        } else {
+            escape(&self.pattern)
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!self.case_sensitive)
+            .multi_line(true)
+            .build()
+    }
+
+    /// Finds every non-empty match of the query in some text.
+    ///
+    /// Returns the byte ranges of the matches in order, or an error if the query is a regex that
+    /// failed to compile. An empty pattern matches nothing.
+    pub fn find(&self, text: &str) -> Result<Vec<Range<usize>>, regex::Error> {
+        if self.pattern.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(self.matches(&self.build()?, text))
+    }
+
+    /// Finds every non-empty match of the query's regex in some text that's a whole word if the
+    /// query asks for one. A match that isn't doesn't hide one starting inside it.
+    pub fn matches(&self, regex: &Regex, text: &str) -> Vec<Range<usize>> {
+        let mut matches = Vec::new();
+        let mut start = 0;
+        while let Some(found) = regex.find_at(text, start) {
+            let range = found.range();
+            if !range.is_empty() && (!self.whole_word || is_whole_word(text, &range)) {
+                start = range.end;
+                matches.push(range);
+                continue;
+            }
+            let Some(char) = text[range.start..].chars().next() else {
+                break;
+            };
+            start = range.start + char.len_utf8();
+        }
+        matches
+    }
+
+    /// Replaces the match at `range` (as returned by [`Query::find`]) with a replacement.
+    ///
+    /// In regex mode, `$1`, `$name` and friends in the replacement expand to the match's capture
+    /// groups. Returns [`None`] if the range is no longer a match of the query.
+    pub fn replace(&self, text: &str, range: Range<usize>, replacement: &str) -> Option<String> {
+        let regex = self.build().ok()?;
+        let captures = regex.captures_at(text, range.start)?;
+        let found = captures.get(0)?;
+        if found.range() != range || (self.whole_word && !is_whole_word(text, &range)) {
+            return None;
+        }
+        let mut replaced = text[..range.start].to_string();
+        if self.regex {
+            captures.expand(replacement, &mut replaced);
+        } else {
+            replaced.push_str(replacement);
+        }
+        replaced.push_str(&text[range.end..]);
+        Some(replaced)
+    }
+
+    /// Replaces every match of the query in some text, returning the new text and the number of
+    /// replacements made.
+    pub fn replace_all(
+        &self,
+        text: &str,
+        replacement: &str,
+    ) -> Result<(String, usize), regex::Error> {
+        if self.pattern.is_empty() {
+            return Ok((text.to_string(), 0));
+        }
+        let regex = self.build()?;
+        let matches = self.matches(&regex, text);
+        let mut replaced = String::new();
+        let mut end = 0;
+        for range in &matches {
+            replaced.push_str(&text[end..range.start]);
+            match regex.captures_at(text, range.start) {
+                Some(captures) if self.regex => captures.expand(replacement, &mut replaced),
+                _ => replaced.push_str(replacement),
+            }
+            end = range.end;
+        }
+        replaced.push_str(&text[end..]);
+        Ok((replaced, matches.len()))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)]
+mod tests {
+    use super::*;
+
+    /// Patterns, texts and the whole words they find.
+    const WHOLE_WORD_CASES: [(&str, &str, &[Range<usize>]); 5] = [
+        ("$5", "costs $5, not $50", &[6..8]),
+        ("c++", "c++ and c++11 and (c++)", &[0..3, 19..22]),
+        ("cat", "cat concat cat's", &[0..3, 11..14]),
+        ("-", "a - b-c", &[2..3]),
+        ("aa", "aaa aa", &[4..6]),
+    ];
+
+    fn query(pattern: &str) -> Query {
+        Query {
+            pattern: pattern.to_string(),
+            ..Query::default()
+        }
+    }
+
+    #[test]
+    fn finding_literals_works() {
+        assert_eq!(query("a.b").find("a.b axb A.B").unwrap(), [0..3, 8..11]);
+        assert!(query("").find("anything").unwrap().is_empty());
+        assert_eq!(
+            Query {
+                case_sensitive: true,
+                ..query("a.b")
+            }
+            .find("a.b A.B")
+            .unwrap(),
+            [0..3]
+        );
+    }
+
+    #[test]
+    fn finding_regexes_works() {
+        let regex = Query {
+            regex: true,
+            ..query("a.b")
+        };
+        assert_eq!(regex.find("a.b axb").unwrap(), [0..3, 4..7]);
+        assert_eq!(
+            Query {
+                regex: true,
+                ..query("^#+")
+            }
+            .find("# One\ntext\n## Two")
+            .unwrap(),
+            [0..1, 11..13]
+        );
+        assert!(Query {
+            regex: true,
+            ..query("x*")
+        }
+        .find("abc")
+        .unwrap()
+        .is_empty());
+        assert!(Query {
+            regex: true,
+            ..query("(")
+        }
+        .find("(")
+        .is_err());
+    }
+
+    #[test]
+    fn finding_whole_words_works() {
+        let whole_word = Query {
+            whole_word: true,
+            ..query("cat")
+        };
+        assert_eq!(
+            whole_word.find("cat concat cat's café").unwrap(),
+            [0..3, 11..14]
+        );
+        assert_eq!(
+            Query {
+                whole_word: true,
+                ..query("café")
+            }
+            .find("café cafés")
+            .unwrap(),
+            [0..5]
+        );
+        for (pattern, text, found) in WHOLE_WORD_CASES {
+            assert_eq!(
+                Query {
+                    whole_word: true,
+                    ..query(pattern)
+                }
+                .find(text)
+                .unwrap(),
+                found,
+                "{pattern:?} in {text:?}"
+            );
+        }
+        let whole_word = Query {
+            whole_word: true,
+            ..query("c++")
+        };
+        assert_eq!(
+            whole_word.replace_all("c++ c++11 c++.", "rust").unwrap(),
+            ("rust c++11 rust.".to_string(), 2)
+        );
+        assert_eq!(whole_word.replace("c++11", 0..3, "rust"), None);
+    }
+
+    #[test]
+    fn replacing_works() {
+        assert_eq!(
+            query("cat").replace("a cat, a cat", 9..12, "dog").unwrap(),
+            "a cat, a dog"
+        );
+        assert_eq!(query("cat").replace("a cat", 0..3, "dog"), None);
+        assert_eq!(
+            Query {
+                regex: true,
+                ..query(r"(\w+)@(\w+)")
+            }
+            .replace("me@home", 0..7, "$2@$1")
+            .unwrap(),
+            "home@me"
+        );
+        assert_eq!(query(r"(\w+)").replace(r"(\w+)", 0..5, "$1").unwrap(), "$1");
+    }
+
+    #[test]
+    fn replacing_all_works() {
+        assert_eq!(
+            query("é").replace_all("éte été", "e").unwrap(),
+            ("ete ete".to_string(), 3)
+        );
+        assert_eq!(
+            query("$").replace_all("1$ 2$", "€").unwrap(),
+            ("1€ 2€".to_string(), 2)
+        );
+        assert_eq!(
+            Query {
+                regex: true,
+                ..query(r"(\d)\$")
+            }
+            .replace_all("1$ 2$", "$$$1")
+            .unwrap(),
+            ("$1 $2".to_string(), 2)
+        );
+        assert_eq!(
+            query("none").replace_all("text", "x").unwrap(),
+            ("text".to_string(), 0)
+        );
+    }
+}
@@ -0,0 +1,7 @@
+//! What the UI and the backend both need to agree on, like how queries match text.
+
+#![warn(clippy::pedantic, clippy::nursery)]
+#![allow(clippy::missing_errors_doc, clippy::must_use_candidate)]
+
+pub mod document;
+pub mod find;
@@ -0,0 +1,327 @@
+use std::fs::canonicalize;
+use std::path::{Path, PathBuf};
+
+use git2::{Commit, DiffOptions, ErrorCode, Repository, Status};
+use serde::Serialize;
+use tauri::command;
+
+/// The most commits listed in a file's log.
+const MAX_LOG: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileStatus {
+    Unmodified,
+    Modified,
+    /// Added to the index but not committed yet.
+    Added,
+    Untracked,
+    Ignored,
+}
+
+/// The state of the repository a file is in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GitStatus {
+    /// The name of the checked out branch, or the short id of the commit if none is.
+    branch: String,
+    status: FileStatus,
+    /// The contents of the file in the commit checked out, if it's in it.
+    head: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GitCommit {
+    /// The short id of the commit.
+    id: String,
+    summary: String,
+    author: String,
+    /// When the commit was made, in seconds since the epoch.
+    time: i64,
+}
+
+/// Opens the repository a file is in, if it's in one, along with the path of the file relative
+/// to the repository's working directory.
+fn open(path: &Path) -> Result<Option<(Repository, PathBuf)>, String> {
+    let path = canonicalize(path).map_err(|error| format!("{}: {error}", path.display()))?;
+    let repository = match Repository::discover(path.parent().unwrap_or(&path)) {
+        Ok(repository) => repository,
+        Err(error) if error.code() == ErrorCode::NotFound => return Ok(None),
+        Err(error) => return Err(error.message().to_string()),
+    };
+    let Some(workdir) = repository.workdir() else {
+        return Ok(None);
+    };
+    let workdir = canonicalize(workdir).map_err(|error| error.to_string())?;
+    let Ok(relative) = path.strip_prefix(&workdir).map(Path::to_path_buf) else {
+        return Ok(None);
+    };
+    Ok(Some((repository, relative)))
+}
+
+fn short_id(commit: &Commit) -> String {
+    commit
+        .as_object()
+        .short_id()
+        .ok()
+        .and_then(|id| id.as_str().map(ToString::to_string))
+        .unwrap_or_else(|| commit.id().to_string())
+}
+
+fn status(repository: &Repository, relative: &Path) -> Result<GitStatus, git2::Error> {
+    let head = match repository.head() {
+        Ok(head) => Some(head),
+        // A new repository has no commits yet.
+        Err(error) if error.code() == ErrorCode::UnbornBranch => None,
+        Err(error) => return Err(error),
+    };
+    let branch = match &head {
+        Some(head) if head.is_branch() => head.shorthand().unwrap_or_default().to_string(),
+        Some(head) => short_id(&head.peel_to_commit()?),
+        None => String::new(),
+    };
+    let contents = head
+        .map(|head| head.peel_to_tree())
+        .transpose()?
+        .and_then(|tree| tree.get_path(relative).ok())
+        .and_then(|entry| entry.to_object(repository).ok()?.into_blob().ok())
+        .map(|blob| String::from_utf8_lossy(blob.content()).to_string());
+    let flags = repository.status_file(relative)?;
+    let status = if flags.contains(Status::IGNORED) {
+        FileStatus::Ignored
+    } else if flags.contains(Status::WT_NEW) {
+        FileStatus::Untracked
+    } else if flags.contains(Status::INDEX_NEW) && !flags.contains(Status::WT_MODIFIED) {
+        FileStatus::Added
+    } else if flags.is_empty() {
+        FileStatus::Unmodified
+    } else {
+        FileStatus::Modified
+    };
+    Ok(GitStatus {
+        branch,
+        status,
+        head: contents,
+    })
+}
+
+/// Gets the branch of the repository a file is in, the file's status in it, and its contents in
+/// the commit checked out.
+///
+/// Returns the status, which is [`None`] if the file isn't in a repository, and a description of
+/// what went wrong reading the repository.
+#[command]
+pub fn git_status(path: PathBuf) -> (Option<GitStatus>, Option<String>) {
+    match open(&path) {
+        Ok(Some((repository, relative))) => match status(&repository, &relative) {
+            Ok(status) => (Some(status), None),
+            Err(error) => (None, Some(error.message().to_string())),
+        },
+        Ok(None) => (None, None),
+        Err(error) => (None, Some(error)),
+    }
+}
+
+fn commit(repository: &Repository, relative: &Path, message: &str) -> Result<(), git2::Error> {
+    let mut index = repository.index()?;
+    index.add_path(relative)?;
+    index.write()?;
+    let tree = repository.find_tree(index.write_tree()?)?;
+    let signature = repository.signature()?;
+    let parent = match repository.head() {
+        Ok(head) => Some(head.peel_to_commit()?),
+        Err(error) if error.code() == ErrorCode::UnbornBranch => None,
+        Err(error) => return Err(error),
+    };
+    repository.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parent.iter().collect::<Vec<_>>(),
+    )?;
+    Ok(())
+}
+
+/// Commits a file as it is saved, along with anything else already staged, with a message.
+///
+/// Returns a description of what went wrong, like the file not being in a repository or the
+/// author not being configured.
+#[command]
+pub fn git_commit(path: PathBuf, message: String) -> Option<String> {
+    if message.trim().is_empty() {
+        return Some("the commit message is empty".to_string());
+    }
+    match open(&path) {
+        Ok(Some((repository, relative))) => commit(&repository, &relative, &message)
+            .err()
+            .map(|error| error.message().to_string()),
+        Ok(None) => Some(format!("{} isn't in a git repository", path.display())),
+        Err(error) => Some(error),
+    }
+}
+
+fn log(repository: &Repository, relative: &Path) -> Result<Vec<GitCommit>, git2::Error> {
+    let mut walk = repository.revwalk()?;
+    match walk.push_head() {
+        Ok(()) => {}
+        Err(error) if error.code() == ErrorCode::UnbornBranch => return Ok(Vec::new()),
+        Err(error) => return Err(error),
+    }
+    let mut options = DiffOptions::new();
+    options.pathspec(relative);
+    let mut commits = Vec::new();
+    for id in walk {
+        let commit = repository.find_commit(id?)?;
+        let parent_tree = match commit.parents().next() {
+            Some(parent) => Some(parent.tree()?),
+            None => None,
+        };
+        let diff = repository.diff_tree_to_tree(
+            parent_tree.as_ref(),
+            Some(&commit.tree()?),
+            Some(&mut options),
+        )?;
+        if diff.deltas().len() == 0 {
+            continue;
+        }
+        commits.push(GitCommit {
+            id: short_id(&commit),
+            summary: commit.summary().unwrap_or_default().to_string(),
+            author: commit.author().name().unwrap_or_default().to_string(),
+            time: commit.time().seconds(),
+        });
+        if commits.len() == MAX_LOG {
+            break;
+        }
+    }
+    Ok(commits)
+}
+
+/// Lists the commits that changed a file, newest first.
+///
+/// Returns the commits and a description of what went wrong reading them.
+#[command]
+pub fn git_log(path: PathBuf) -> (Vec<GitCommit>, Option<String>) {
+    match open(&path) {
+        Ok(Some((repository, relative))) => match log(&repository, &relative) {
+            Ok(commits) => (commits, None),
+            Err(error) => (Vec::new(), Some(error.message().to_string())),
+        },
+        Ok(None) => (Vec::new(), None),
+        Err(error) => (Vec::new(), Some(error)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+    use std::fs::{create_dir_all, remove_dir_all, write};
+    use std::process;
+
+    use git2::RepositoryInitOptions;
+
+    use super::*;
+
+    /// Makes an empty repository on the `main` branch in a new temporary folder.
+    fn repository(name: &str) -> (Repository, PathBuf) {
+        let folder = temp_dir().join(format!("git-{name}-{}", process::id()));
+        let _ = remove_dir_all(&folder);
+        create_dir_all(&folder).unwrap();
+        let repository =
+            Repository::init_opts(&folder, RepositoryInitOptions::new().initial_head("main"))
+                .unwrap();
+        let mut config = repository.config().unwrap();
+        config.set_str("user.name", "Ada").unwrap();
+        config.set_str("user.email", "ada@example.com").unwrap();
+        (repository, folder)
+    }
+
+    fn file_status(path: &Path) -> GitStatus {
+        let (repository, relative) = open(path).unwrap().unwrap();
+        status(&repository, &relative).unwrap()
+    }
+
+    #[test]
+    fn getting_statuses_works() {
+        let (_repository, folder) = repository("status");
+        let note = folder.join("note.md");
+        write(&note, "one\ntwo\n").unwrap();
+        write(folder.join(".gitignore"), "draft.md\n").unwrap();
+        write(folder.join("draft.md"), "").unwrap();
+        assert_eq!(
+            file_status(&note),
+            GitStatus {
+                branch: String::new(),
+                status: FileStatus::Untracked,
+                head: None,
+            }
+        );
+        assert_eq!(
+            file_status(&folder.join("draft.md")).status,
+            FileStatus::Ignored
+        );
+
+        assert_eq!(
+            git_commit(note.clone(), " ".to_string()),
+            Some("the commit message is empty".to_string())
+        );
+        assert_eq!(git_commit(note.clone(), "Add a note".to_string()), None);
+        assert_eq!(
+            file_status(&note),
+            GitStatus {
+                branch: "main".to_string(),
+                status: FileStatus::Unmodified,
+                head: Some("one\ntwo\n".to_string()),
+            }
+        );
+
+        // The head keeps the committed contents the changed lines are found from.
+        write(&note, "one\n2\nthree\n").unwrap();
+        let status = file_status(&note);
+        assert_eq!(status.status, FileStatus::Modified);
+        assert_eq!(status.head.as_deref(), Some("one\ntwo\n"));
+
+        let other = folder.join("other.md");
+        write(&other, "").unwrap();
+        let (repository, relative) = open(&other).unwrap().unwrap();
+        let mut index = repository.index().unwrap();
+        index.add_path(&relative).unwrap();
+        index.write().unwrap();
+        assert_eq!(file_status(&other).status, FileStatus::Added);
+        remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn listing_commits_works() {
+        let (repository, folder) = repository("log");
+        let note = folder.join("note.md");
+        let other = folder.join("other.md");
+        write(&note, "one").unwrap();
+        commit(&repository, Path::new("note.md"), "First").unwrap();
+        write(&other, "other").unwrap();
+        commit(&repository, Path::new("other.md"), "Other").unwrap();
+        write(&note, "two").unwrap();
+        commit(&repository, Path::new("note.md"), "Second\n\nMore.").unwrap();
+        let summaries = |path: &Path| {
+            let (commits, error) = git_log(path.to_path_buf());
+            assert_eq!(error, None);
+            commits
+                .into_iter()
+                .map(|commit| (commit.summary, commit.author))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            summaries(&note),
+            [
+                ("Second".to_string(), "Ada".to_string()),
+                ("First".to_string(), "Ada".to_string()),
+            ]
+        );
+        assert_eq!(
+            summaries(&other),
+            [("Other".to_string(), "Ada".to_string())]
+        );
+        remove_dir_all(&folder).unwrap();
+    }
+}
@@ -0,0 +1,38 @@
+use std::collections::BTreeMap;
+use std::fs::read_to_string;
+use std::io::ErrorKind;
+
+use tauri::{command, AppHandle, Manager};
+
+/// The name of the file in the app config directory that keyboard shortcuts are overridden in.
+const KEYMAP_FILE: &str = "keymap.json";
+
+/// Loads the user's keyboard shortcut overrides, a JSON object mapping action ids to bindings
+/// like `"save_as": "c-sh-S"`.
+///
+/// Returns the overrides, which are empty if there is no keymap file, and a description of what
+/// went wrong if the file couldn't be read or parsed.
+#[command]
+pub fn load_keymap(app_handle: AppHandle) -> (BTreeMap<String, String>, Option<String>) {
+    let path = match app_handle.path().app_config_dir() {
+        Ok(directory) => directory.join(KEYMAP_FILE),
+        Err(error) => return (BTreeMap::new(), Some(error.to_string())),
+    };
+    let data = match read_to_string(&path) {
+        Ok(data) => data,
+        Err(error) if error.kind() == ErrorKind::NotFound => return (BTreeMap::new(), None),
+        Err(error) => {
+            return (
+                BTreeMap::new(),
+                Some(format!("{}: {error}", path.display())),
+            )
+        }
+    };
+    match serde_json::from_str(&data) {
+        Ok(overrides) => (overrides, None),
+        Err(error) => (
+            BTreeMap::new(),
+            Some(format!("{}: {error}", path.display())),
+        ),
+    }
+}
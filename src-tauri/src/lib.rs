@@ -1,3 +1,15 @@
+mod git;
+mod keymap;
+mod links;
+mod manuscript;
+mod project;
+mod search;
+mod search_index;
+mod settings;
+mod snapshots;
+mod spelling;
+mod writing;
+
 use std::{
     fs::{read_to_string, write},
     path::{self, PathBuf},
@@ -5,31 +17,57 @@ use std::{
 };
 
 use rfd::FileDialog;
-use tauri::{command, generate_context, generate_handler, AppHandle, Builder, Manager};
+use tauri::{
+    command, generate_context, generate_handler, AppHandle, Builder, Emitter, Manager, State,
+    WindowEvent,
+};
 use tauri_plugin_decorum::WebviewWindowExt;
 
+use links::LinkIndex;
+use search::Searches;
+use search_index::SearchIndexStore;
+use settings::SettingsStore;
+use spelling::Spelling;
+use writing::WritingLogStore;
+
+/// Saves some data to a path, prompting the user for it if it is [`None`], takes a snapshot of it
+/// and updates it in the link and search indexes.
+///
+/// Returns the path, which is [`None`] if the user cancelled the dialog or the data couldn't be
+/// written, and a description of what went wrong writing it or taking the snapshot.
 #[command]
-fn save_file(data: String, path: Option<PathBuf>) -> Option<PathBuf> {
+fn save_file(
+    app_handle: AppHandle,
+    link_index: State<LinkIndex>,
+    search_index: State<SearchIndexStore>,
+    data: String,
+    path: Option<PathBuf>,
+) -> (Option<PathBuf>, Option<String>) {
     let path = match path {
         Some(path) => path,
-        None => FileDialog::new()
+        None => match FileDialog::new()
             .set_can_create_directories(true)
-            .save_file()?,
+            .save_file()
+        {
+            Some(path) => path,
+            None => return (None, None),
+        },
     };
-    write(&path, data).unwrap();
-    Some(path)
+    if let Err(error) = write(&path, &data) {
+        return (None, Some(format!("{}: {error}", path.display())));
+    }
+    links::update(&link_index, &path, &data);
+    search_index::update(&app_handle, &search_index, &path, &data);
+    let error = snapshots::record(&app_handle, &path, &data).err();
+    (Some(path), error)
 }
 
+/// Loads the data in a file.
+///
+/// Returns the data, or [`None`] if the file couldn't be read.
 #[command]
-fn load_file(path: Option<PathBuf>) -> (Option<String>, Option<PathBuf>) {
-    let path = match path {
-        Some(path) => Some(path),
-        None => FileDialog::new().pick_file(),
-    };
-    match path {
-        Some(path) => (read_to_string(&path).ok(), Some(path)),
-        None => (None, None),
-    }
+fn load_file(path: PathBuf) -> Option<String> {
+    read_to_string(path).ok()
 }
 
 #[command]
@@ -42,6 +80,12 @@ pub fn run() {
     Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_decorum::init())
+        .manage(LinkIndex::default())
+        .manage(Searches::default())
+        .manage(SearchIndexStore::default())
+        .manage(SettingsStore::default())
+        .manage(Spelling::default())
+        .manage(WritingLogStore::default())
         .setup(|app| {
             #[cfg(target_os = "macos")]
             {
@@ -50,7 +94,47 @@ pub fn run() {
             }
             Ok(())
         })
-        .invoke_handler(generate_handler![save_file, load_file, quit])
+        .on_window_event(|window, event| {
+            // The UI logs the words written since the last tick before it quits.
+            if let WindowEvent::CloseRequested { api, .. } = event {
+                if window.emit("close-requested", ()).is_ok() {
+                    api.prevent_close();
+                }
+            }
+        })
+        .invoke_handler(generate_handler![
+            save_file,
+            load_file,
+            quit,
+            git::git_status,
+            git::git_commit,
+            git::git_log,
+            keymap::load_keymap,
+            links::index_links,
+            links::backlinks,
+            manuscript::get_manuscript,
+            manuscript::set_manuscript,
+            manuscript::write_manuscript,
+            project::pick_project,
+            project::list_project,
+            project::create_file,
+            project::create_folder,
+            project::rename_path,
+            project::delete_path,
+            search::search_folder,
+            search_index::open_search_index,
+            search_index::query_search_index,
+            settings::get_settings,
+            settings::set_settings,
+            snapshots::list_snapshots,
+            snapshots::load_snapshot,
+            spelling::check_words,
+            spelling::suggest_words,
+            spelling::add_to_dictionary,
+            writing::get_writing_log,
+            writing::log_words,
+            writing::set_document_goal
+        ])
         .run(generate_context!())
         .expect("error while running tauri application");
 }
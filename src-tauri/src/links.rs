@@ -0,0 +1,183 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use ignore::WalkBuilder;
+use tauri::{command, State};
+use typewriter_core::document::{links, note_name};
+
+use crate::manuscript;
+
+/// The files of the open project and the notes each of them links to.
+#[derive(Debug, Default)]
+struct Index {
+    root: PathBuf,
+    /// The names of the notes each Markdown file links to, normalized with [`note_name`].
+    links: BTreeMap<PathBuf, BTreeSet<String>>,
+}
+
+/// The link index of the open project, if one has been indexed.
+#[derive(Default)]
+pub struct LinkIndex(Mutex<Option<Index>>);
+
+/// Finds the names of the notes a text links to with `[[Note Name]]`, the way the editor parses
+/// them.
+fn scan(data: &str) -> BTreeSet<String> {
+    links(data)
+        .into_iter()
+        .map(|(_, name)| note_name(&name))
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+fn is_markdown(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "md")
+}
+
+impl Index {
+    /// Indexes the links between the Markdown files in a project, skipping anything ignored by
+    /// `.gitignore` files and the compiled manuscript, with descriptions of what went wrong reading
+    /// them.
+    fn build(root: PathBuf) -> (Self, Vec<String>) {
+        let mut problems = Vec::new();
+        let mut links = BTreeMap::new();
+        let output = manuscript::output(&root);
+        for entry in WalkBuilder::new(&root).require_git(false).build() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(error) => {
+                    problems.push(error.to_string());
+                    continue;
+                }
+            };
+            if !entry
+                .file_type()
+                .is_some_and(|file_type| file_type.is_file())
+                || !is_markdown(entry.path())
+                || output.as_deref() == Some(entry.path())
+            {
+                continue;
+            }
+            match read_to_string(entry.path()) {
+                Ok(data) => {
+                    links.insert(entry.path().to_path_buf(), scan(&data));
+                }
+                Err(error) => problems.push(format!("{}: {error}", entry.path().display())),
+            }
+        }
+        (Self { root, links }, problems)
+    }
+
+    /// Lists the files that link to a file by its name, in order.
+    fn backlinks(&self, path: &Path) -> Vec<PathBuf> {
+        let Some(name) = path
+            .file_stem()
+            .map(|stem| note_name(&stem.to_string_lossy()))
+        else {
+            return Vec::new();
+        };
+        self.links
+            .iter()
+            .filter(|(file, links)| *file != path && links.contains(&name))
+            .map(|(file, _)| file.clone())
+            .collect()
+    }
+}
+
+/// Indexes the links between the Markdown files in a project, replacing the index of any other
+/// project.
+///
+/// Returns every Markdown file in the project, which links can lead to, and descriptions of what
+/// went wrong reading them.
+#[command]
+pub fn index_links(index: State<LinkIndex>, root: PathBuf) -> (Vec<PathBuf>, Vec<String>) {
+    let (built, problems) = Index::build(root);
+    let files = built.links.keys().cloned().collect();
+    *index.0.lock().unwrap() = Some(built);
+    (files, problems)
+}
+
+/// Updates the links of a file that was saved with some data, if it's in the indexed project.
+pub fn update(index: &LinkIndex, path: &Path, data: &str) {
+    let mut index = index.0.lock().unwrap();
+    let Some(index) = index.as_mut() else {
+        return;
+    };
+    if path.starts_with(&index.root)
+        && is_markdown(path)
+        && manuscript::output(&index.root).as_deref() != Some(path)
+    {
+        index.links.insert(path.to_path_buf(), scan(data));
+    }
+}
+
+/// Lists the files in the indexed project that link to a file by its name, in order.
+#[command]
+pub fn backlinks(index: State<LinkIndex>, path: PathBuf) -> Vec<PathBuf> {
+    index
+        .0
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|index| index.backlinks(&path))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+    use std::fs::{create_dir_all, remove_dir_all, write};
+    use std::process;
+
+    use super::*;
+
+    #[test]
+    fn scanning_links_works() {
+        let data = "# [[Heading Note]]\nSee **[[Note#Part]]**, [[Other|shown]] and [[a [[b]].\n\
+                    [[]] [[unclosed\n[[split\nacross]]";
+        assert_eq!(
+            scan(data),
+            BTreeSet::from(["b", "heading note", "note", "other"].map(String::from))
+        );
+    }
+
+    #[test]
+    fn finding_backlinks_works() {
+        let root = temp_dir().join(format!("links-{}", process::id()));
+        let _ = remove_dir_all(&root);
+        create_dir_all(root.join("notes")).unwrap();
+        write(root.join("notes/Idea.md"), "[[Other]]").unwrap();
+        write(root.join("Other.md"), "See [[idea]] and [[Other]].").unwrap();
+        write(root.join("Plain.md"), "No links, just *idea*.").unwrap();
+        write(root.join("notes.txt"), "[[Idea]]").unwrap();
+        let (built, problems) = Index::build(root.clone());
+        let files: Vec<_> = built.links.keys().cloned().collect();
+        assert_eq!(
+            files,
+            [
+                root.join("Other.md"),
+                root.join("Plain.md"),
+                root.join("notes/Idea.md")
+            ]
+        );
+        assert!(problems.is_empty());
+        let index = LinkIndex(Mutex::new(Some(built)));
+        let backlinks = |path: &Path| index.0.lock().unwrap().as_ref().unwrap().backlinks(path);
+        assert_eq!(
+            backlinks(&root.join("notes/Idea.md")),
+            [root.join("Other.md")]
+        );
+        assert_eq!(
+            backlinks(&root.join("Other.md")),
+            [root.join("notes/Idea.md")]
+        );
+        assert!(backlinks(&root.join("Plain.md")).is_empty());
+        update(&index, &root.join("Plain.md"), "[[Other]]");
+        assert_eq!(
+            backlinks(&root.join("Other.md")),
+            [root.join("Plain.md"), root.join("notes/Idea.md")]
+        );
+        remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,197 @@
+use std::fs::{create_dir_all, read_to_string, write};
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+/// The name of the file in a project's folder that its manifest is stored in.
+const MANIFEST_FILE: &str = "manuscript.json";
+
+/// The version of the manifest's layout, stored in it as `version`.
+const VERSION: u64 = 1;
+
+/// How the files of a project are compiled into one manuscript.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Manuscript {
+    version: u64,
+    /// The files compiled, in order, relative to the project's folder and separated by `/`.
+    files: Vec<String>,
+    /// How many levels the headings of every file are moved down, or up if it's negative.
+    heading_shift: i8,
+    /// What's put between files, if anything.
+    separator: String,
+    /// The file the manuscript is written to, relative to the project's folder.
+    output: String,
+}
+
+impl Default for Manuscript {
+    fn default() -> Self {
+        Self {
+            version: VERSION,
+            files: Vec::new(),
+            heading_shift: 0,
+            separator: String::new(),
+            output: "manuscript.md".to_string(),
+        }
+    }
+}
+
+/// Finds a file in a project's folder from its path relative to it, unless the path leads out of
+/// the folder.
+fn project_file(root: &Path, relative: &str) -> Result<PathBuf, String> {
+    let relative = Path::new(relative);
+    if relative.as_os_str().is_empty()
+        || !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return Err(format!(
+            "{} isn't a path in the project",
+            relative.display()
+        ));
+    }
+    Ok(root.join(relative))
+}
+
+/// Loads the manifest of a project.
+///
+/// Returns the manifest, which is [`None`] if the project doesn't have one, and a description of
+/// what went wrong loading it.
+#[command]
+pub fn get_manuscript(root: PathBuf) -> (Option<Manuscript>, Option<String>) {
+    let path = root.join(MANIFEST_FILE);
+    let data = match read_to_string(&path) {
+        Ok(data) => data,
+        Err(error) if error.kind() == ErrorKind::NotFound => return (None, None),
+        Err(error) => return (None, Some(format!("{}: {error}", path.display()))),
+    };
+    match serde_json::from_str::<Manuscript>(&data) {
+        Ok(manuscript) if manuscript.version > VERSION => (
+            None,
+            Some(format!(
+                "{}: version {} isn't supported, the latest is {VERSION}",
+                path.display(),
+                manuscript.version
+            )),
+        ),
+        Ok(manuscript) => (Some(manuscript), None),
+        Err(error) => (None, Some(format!("{}: {error}", path.display()))),
+    }
+}
+
+/// Finds the file a project's manuscript is compiled into, which the link and search indexes
+/// leave out since it only repeats the files compiled into it.
+pub fn output(root: &Path) -> Option<PathBuf> {
+    let (manuscript, _) = get_manuscript(root.to_path_buf());
+    project_file(root, &manuscript.unwrap_or_default().output).ok()
+}
+
+/// Saves the manifest of a project.
+///
+/// Returns a description of what went wrong.
+#[command]
+pub fn set_manuscript(root: PathBuf, mut manuscript: Manuscript) -> Option<String> {
+    manuscript.version = VERSION;
+    let path = root.join(MANIFEST_FILE);
+    write(&path, serde_json::to_string_pretty(&manuscript).unwrap())
+        .err()
+        .map(|error| format!("{}: {error}", path.display()))
+}
+
+/// Writes a compiled manuscript to the output file of a project's manifest.
+///
+/// Returns the path of the file written, or a description of what went wrong.
+#[command]
+pub fn write_manuscript(
+    root: PathBuf,
+    output: String,
+    data: String,
+) -> (Option<PathBuf>, Option<String>) {
+    let path = match project_file(&root, &output) {
+        Ok(path) => path,
+        Err(error) => return (None, Some(error)),
+    };
+    let written = path
+        .parent()
+        .map_or(Ok(()), create_dir_all)
+        .and_then(|()| write(&path, data));
+    match written {
+        Ok(()) => (Some(path), None),
+        Err(error) => (None, Some(format!("{}: {error}", path.display()))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+    use std::fs::remove_dir_all;
+    use std::process;
+
+    use super::*;
+
+    fn project(name: &str) -> PathBuf {
+        let root = temp_dir().join(format!("manuscript-{name}-{}", process::id()));
+        let _ = remove_dir_all(&root);
+        create_dir_all(&root).unwrap();
+        root
+    }
+
+    #[test]
+    fn finding_project_files_works() {
+        let root = project("files");
+        assert_eq!(
+            project_file(&root, "book/one.md"),
+            Ok(root.join("book/one.md"))
+        );
+        for relative in [
+            "",
+            "../outside.md",
+            "book/../../outside.md",
+            "./one.md",
+            "/etc/passwd",
+        ] {
+            assert!(project_file(&root, relative).is_err(), "{relative}");
+        }
+        let (path, problem) = write_manuscript(
+            root.clone(),
+            "../outside.md".to_string(),
+            "text".to_string(),
+        );
+        assert!(path.is_none() && problem.is_some());
+        assert!(!root.parent().unwrap().join("outside.md").exists());
+        let (path, problem) =
+            write_manuscript(root.clone(), "out/book.md".to_string(), "text".to_string());
+        assert_eq!((path, problem), (Some(root.join("out/book.md")), None));
+        assert_eq!(read_to_string(root.join("out/book.md")).unwrap(), "text");
+        remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn loading_manifests_works() {
+        let root = project("manifest");
+        assert_eq!(get_manuscript(root.clone()), (None, None));
+        assert_eq!(output(&root), Some(root.join("manuscript.md")));
+        let manuscript = Manuscript {
+            files: vec!["one.md".to_string()],
+            output: "book.md".to_string(),
+            ..Manuscript::default()
+        };
+        assert_eq!(set_manuscript(root.clone(), manuscript.clone()), None);
+        assert_eq!(get_manuscript(root.clone()), (Some(manuscript), None));
+        assert_eq!(output(&root), Some(root.join("book.md")));
+        write(
+            root.join(MANIFEST_FILE),
+            r#"{"version": 2, "files": ["one.md"]}"#,
+        )
+        .unwrap();
+        let (manuscript, problem) = get_manuscript(root.clone());
+        assert_eq!(manuscript, None);
+        assert!(problem.unwrap().contains("version 2 isn't supported"));
+        write(root.join(MANIFEST_FILE), "{").unwrap();
+        let (manuscript, problem) = get_manuscript(root.clone());
+        assert!(manuscript.is_none() && problem.is_some());
+        remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,233 @@
+use std::fs::{create_dir, read_dir, remove_dir, remove_file, rename, File};
+use std::path::{Path, PathBuf};
+
+use rfd::FileDialog;
+use same_file::is_same_file;
+use serde::Serialize;
+use tauri::command;
+
+/// The extension of the files listed in a project.
+const MARKDOWN_EXTENSION: &str = "md";
+
+/// A Markdown file or a folder in a project.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Entry {
+    path: PathBuf,
+    name: String,
+    /// The entries in the folder, or [`None`] if this is a file.
+    children: Option<Vec<Entry>>,
+}
+
+/// Lists the Markdown files and folders in a folder, folders first and then by name, skipping
+/// hidden ones. Links to folders aren't followed, since they can lead back into the folder.
+fn list(folder: &Path, problems: &mut Vec<String>) -> Vec<Entry> {
+    let entries = match read_dir(folder) {
+        Ok(entries) => entries,
+        Err(error) => {
+            problems.push(format!("{}: {error}", folder.display()));
+            return Vec::new();
+        }
+    };
+    let mut listed = Vec::new();
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(error) => {
+                problems.push(format!("{}: {error}", folder.display()));
+                continue;
+            }
+        };
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') {
+            continue;
+        }
+        let file_type = match entry.file_type() {
+            Ok(file_type) => file_type,
+            Err(error) => {
+                problems.push(format!("{}: {error}", path.display()));
+                continue;
+            }
+        };
+        if file_type.is_dir() {
+            let children = list(&path, problems);
+            listed.push(Entry {
+                path,
+                name,
+                children: Some(children),
+            });
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == MARKDOWN_EXTENSION)
+        {
+            listed.push(Entry {
+                path,
+                name,
+                children: None,
+            });
+        }
+    }
+    listed.sort_by_cached_key(|entry| (entry.children.is_none(), entry.name.to_lowercase()));
+    listed
+}
+
+/// Prompts the user for a folder to open as a project.
+///
+/// Returns the folder, or [`None`] if the user cancelled the dialog.
+#[command]
+pub fn pick_project() -> Option<PathBuf> {
+    FileDialog::new().pick_folder()
+}
+
+/// Lists the Markdown files and the folders in a project, as a tree.
+///
+/// Returns the entries in the project's folder, and descriptions of what went wrong reading it.
+#[command]
+pub fn list_project(root: PathBuf) -> (Vec<Entry>, Vec<String>) {
+    let mut problems = Vec::new();
+    let entries = list(&root, &mut problems);
+    (entries, problems)
+}
+
+/// Creates an empty file, unless something is already at the path.
+///
+/// Returns a description of what went wrong.
+#[command]
+pub fn create_file(path: PathBuf) -> Option<String> {
+    File::create_new(&path)
+        .err()
+        .map(|error| format!("{}: {error}", path.display()))
+}
+
+/// Creates a folder, unless something is already at the path.
+///
+/// Returns a description of what went wrong.
+#[command]
+pub fn create_folder(path: PathBuf) -> Option<String> {
+    create_dir(&path)
+        .err()
+        .map(|error| format!("{}: {error}", path.display()))
+}
+
+/// Renames or moves a file or a folder, unless something else is already at the new path. The
+/// new path can be the same file, which renames it to another case on a file system that ignores
+/// it.
+///
+/// Returns a description of what went wrong.
+#[command]
+pub fn rename_path(path: PathBuf, to: PathBuf) -> Option<String> {
+    if to.exists() && !is_same_file(&path, &to).unwrap_or(false) {
+        return Some(format!("{} already exists", to.display()));
+    }
+    rename(&path, &to)
+        .err()
+        .map(|error| format!("{}: {error}", path.display()))
+}
+
+/// Deletes a file, or a folder if it's empty.
+///
+/// Returns a description of what went wrong.
+#[command]
+pub fn delete_path(path: PathBuf) -> Option<String> {
+    let deleted = if path.is_dir() {
+        remove_dir(&path)
+    } else {
+        remove_file(&path)
+    };
+    deleted
+        .err()
+        .map(|error| format!("{}: {error}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+    use std::fs::{create_dir_all, read_to_string, remove_dir_all, write};
+    use std::process;
+
+    use super::*;
+
+    fn project(name: &str) -> PathBuf {
+        let root = temp_dir().join(format!("project-{name}-{}", process::id()));
+        let _ = remove_dir_all(&root);
+        create_dir_all(&root).unwrap();
+        root
+    }
+
+    /// The names in a tree of entries, with the children of folders after a `/`.
+    fn names(entries: &[Entry]) -> Vec<String> {
+        entries
+            .iter()
+            .flat_map(|entry| {
+                let mut listed = vec![entry.name.clone()];
+                if let Some(children) = &entry.children {
+                    listed.extend(
+                        names(children)
+                            .into_iter()
+                            .map(|name| format!("{}/{name}", entry.name)),
+                    );
+                }
+                listed
+            })
+            .collect()
+    }
+
+    #[test]
+    fn listing_works() {
+        let root = project("list");
+        create_dir_all(root.join("b folder/.hidden")).unwrap();
+        create_dir_all(root.join("A folder")).unwrap();
+        create_dir_all(root.join(".git")).unwrap();
+        for file in [
+            "b.md",
+            "a.md",
+            "C.md",
+            "notes.txt",
+            ".hidden.md",
+            "b folder/one.md",
+        ] {
+            write(root.join(file), "").unwrap();
+        }
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&root, root.join("b folder/loop")).unwrap();
+        let (entries, problems) = list_project(root.clone());
+        assert!(problems.is_empty());
+        assert_eq!(
+            names(&entries),
+            [
+                "A folder",
+                "b folder",
+                "b folder/one.md",
+                "a.md",
+                "b.md",
+                "C.md"
+            ]
+        );
+        let (entries, problems) = list_project(root.join("missing"));
+        assert!(entries.is_empty());
+        assert_eq!(problems.len(), 1);
+        remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn renaming_and_deleting_works() {
+        let root = project("rename");
+        write(root.join("one.md"), "one").unwrap();
+        write(root.join("two.md"), "two").unwrap();
+        assert!(rename_path(root.join("one.md"), root.join("two.md")).is_some());
+        assert_eq!(read_to_string(root.join("two.md")).unwrap(), "two");
+        assert_eq!(
+            rename_path(root.join("one.md"), root.join("three.md")),
+            None
+        );
+        assert!(!root.join("one.md").exists());
+        create_dir_all(root.join("folder")).unwrap();
+        write(root.join("folder/inside.md"), "").unwrap();
+        assert!(delete_path(root.join("folder")).is_some());
+        assert!(root.join("folder/inside.md").exists());
+        assert_eq!(delete_path(root.join("folder/inside.md")), None);
+        assert_eq!(delete_path(root.join("folder")), None);
+        assert!(!root.join("folder").exists());
+        remove_dir_all(&root).unwrap();
+    }
+}
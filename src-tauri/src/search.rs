@@ -0,0 +1,119 @@
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::thread::spawn;
+
+use ignore::WalkBuilder;
+use regex::Regex;
+use rfd::FileDialog;
+use serde::Serialize;
+use tauri::{command, AppHandle, Emitter, Manager, State};
+use typewriter_core::find::Query;
+
+/// The most matches a single search will report before giving up.
+const MAX_RESULTS: usize = 1000;
+
+/// The id of the most recently started search. Searches with any other id stop early.
+#[derive(Default)]
+pub struct Searches(AtomicU32);
+
+#[derive(Clone, Serialize)]
+struct SearchResult {
+    id: u32,
+    path: PathBuf,
+    line: usize,
+    start: usize,
+    end: usize,
+    text: String,
+}
+
+#[derive(Clone, Serialize)]
+struct SearchFinished {
+    id: u32,
+    count: usize,
+    error: Option<String>,
+}
+
+/// Searches every Markdown file under `root` (prompting the user if it is [`None`]) on a
+/// background thread, skipping anything ignored by `.gitignore` files.
+///
+/// Each match is emitted as a `search-result` event, followed by a single `search-finished`
+/// event. Returns the root and the id tagging this search's events, or [`None`] if the user
+/// cancelled the dialog.
+#[command]
+pub fn search_folder(
+    app_handle: AppHandle,
+    searches: State<Searches>,
+    root: Option<PathBuf>,
+    query: Query,
+) -> Option<(PathBuf, u32)> {
+    let root = match root {
+        Some(root) => root,
+        None => FileDialog::new().pick_folder()?,
+    };
+    let id = searches.0.fetch_add(1, Ordering::SeqCst) + 1;
+    spawn({
+        let root = root.clone();
+        move || {
+            let (count, error) = match query.build() {
+                Ok(regex) => (search(&app_handle, id, &root, &query, &regex), None),
+                Err(error) => (0, Some(error.to_string())),
+            };
+            // The window may be gone by the time the search finishes, with no one left to tell.
+            let _ = app_handle.emit("search-finished", SearchFinished { id, count, error });
+        }
+    });
+    Some((root, id))
+}
+
+fn search(app_handle: &AppHandle, id: u32, root: &Path, query: &Query, regex: &Regex) -> usize {
+    let searches = app_handle.state::<Searches>();
+    let mut count = 0;
+    let files = WalkBuilder::new(root)
+        .require_git(false)
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry
+                .file_type()
+                .is_some_and(|file_type| file_type.is_file())
+        })
+        .filter(|entry| {
+            entry
+                .path()
+                .extension()
+                .is_some_and(|extension| extension == "md")
+        });
+    for entry in files {
+        if searches.0.load(Ordering::SeqCst) != id {
+            break;
+        }
+        let Ok(data) = read_to_string(entry.path()) else {
+            continue;
+        };
+        for (line, text) in data.lines().enumerate() {
+            for found in query.matches(regex, text) {
+                if count == MAX_RESULTS {
+                    return count;
+                }
+                count += 1;
+                let sent = app_handle.emit(
+                    "search-result",
+                    SearchResult {
+                        id,
+                        path: entry.path().to_path_buf(),
+                        line,
+                        start: found.start,
+                        end: found.end,
+                        text: text.to_string(),
+                    },
+                );
+                // The window is gone, so there's no one to show the rest to.
+                if sent.is_err() {
+                    return count;
+                }
+            }
+        }
+    }
+    count
+}
@@ -0,0 +1,745 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{create_dir_all, read_to_string, write, File};
+use std::io::{ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread::spawn;
+use std::time::UNIX_EPOCH;
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use ignore::WalkBuilder;
+use notify::{recommended_watcher, Event, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, Manager, State};
+use typewriter_core::document;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::manuscript;
+use crate::snapshots::hash;
+
+/// The folder in the app data directory that the search indexes of projects are stored in, each
+/// compressed and named by the hash of the project's folder.
+const INDEXES_FOLDER: &str = "search-indexes";
+
+/// The version of the stored index's layout. Indexes of any other version are rebuilt.
+const VERSION: u64 = 2;
+
+/// The most results a query returns.
+const MAX_RESULTS: usize = 100;
+
+/// The longest the text shown for a result can be, in characters.
+const MAX_RESULT_TEXT: usize = 200;
+
+/// How much more a match in a heading counts than one in the text under it.
+const HEADING_WEIGHT: f64 = 2.;
+
+/// A run of a file under one heading, or before the first one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Section {
+    /// The heading's text, which is empty before the first heading.
+    heading: String,
+    /// The line the section starts on.
+    line: u32,
+    /// The position of the section's first word in the file.
+    start: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedFile {
+    /// When the file was last modified, in milliseconds since the epoch.
+    modified: u64,
+    sections: Vec<Section>,
+    /// The position of the first word of each line, which finds the line a word is on.
+    lines: Vec<u32>,
+    /// The distinct words in the file, which are removed from the index with it.
+    words: BTreeSet<String>,
+}
+
+impl IndexedFile {
+    fn section(&self, position: u32) -> usize {
+        self.sections
+            .partition_point(|section| section.start <= position)
+            .saturating_sub(1)
+    }
+
+    fn line(&self, position: u32) -> u32 {
+        let line = self
+            .lines
+            .partition_point(|start| *start <= position)
+            .saturating_sub(1);
+        u32::try_from(line).unwrap_or(u32::MAX)
+    }
+}
+
+/// An inverted index of the words in the Markdown files of a project.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Index {
+    version: u64,
+    root: PathBuf,
+    files: BTreeMap<PathBuf, IndexedFile>,
+    /// The positions of each word, lowercased, in each file it's in.
+    postings: BTreeMap<String, BTreeMap<PathBuf, Vec<u32>>>,
+    /// The positions in each file that are in headings.
+    headings: BTreeMap<PathBuf, BTreeSet<u32>>,
+}
+
+/// Splits a text into lowercased words.
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.unicode_words().map(str::to_lowercase)
+}
+
+fn modified(path: &Path) -> Option<u64> {
+    let modified = path.metadata().ok()?.modified().ok()?;
+    let since = modified.duration_since(UNIX_EPOCH).ok()?;
+    Some(u64::try_from(since.as_millis()).unwrap_or(u64::MAX))
+}
+
+fn is_markdown(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "md")
+}
+
+impl Index {
+    fn new(root: PathBuf) -> Self {
+        Self {
+            version: VERSION,
+            root,
+            ..Self::default()
+        }
+    }
+
+    fn remove(&mut self, path: &Path) {
+        let Some(file) = self.files.remove(path) else {
+            return;
+        };
+        for word in &file.words {
+            if let Some(files) = self.postings.get_mut(word) {
+                files.remove(path);
+                if files.is_empty() {
+                    self.postings.remove(word);
+                }
+            }
+        }
+        self.headings.remove(path);
+    }
+
+    /// Indexes the data of a file, replacing what was indexed of it before.
+    fn add(&mut self, path: &Path, data: &str, modified: u64) {
+        self.remove(path);
+        let mut file = IndexedFile {
+            modified,
+            sections: vec![Section {
+                heading: String::new(),
+                line: 0,
+                start: 0,
+            }],
+            lines: Vec::new(),
+            words: BTreeSet::new(),
+        };
+        // The headings the editor shows, which start at the start of some line.
+        let mut found = document::headings(data).into_iter().peekable();
+        let mut headings = BTreeSet::new();
+        let mut position = 0;
+        let mut offset = 0;
+        for (line, text) in data.split_inclusive('\n').enumerate() {
+            let line = u32::try_from(line).unwrap_or(u32::MAX);
+            file.lines.push(position);
+            let heading = found.next_if(|heading| heading.offset == offset);
+            offset += text.len();
+            if let Some(heading) = &heading {
+                file.sections.push(Section {
+                    heading: heading.title.clone(),
+                    line,
+                    start: position,
+                });
+            }
+            for word in words(text) {
+                if heading.is_some() {
+                    headings.insert(position);
+                }
+                self.postings
+                    .entry(word.clone())
+                    .or_default()
+                    .entry(path.to_path_buf())
+                    .or_default()
+                    .push(position);
+                file.words.insert(word);
+                position += 1;
+            }
+        }
+        self.headings.insert(path.to_path_buf(), headings);
+        self.files.insert(path.to_path_buf(), file);
+    }
+
+    /// Whether a file belongs in the index, which is every Markdown file in the project but the
+    /// compiled manuscript at `output`.
+    fn wanted(&self, path: &Path, output: Option<&Path>) -> bool {
+        path.starts_with(&self.root) && is_markdown(path) && output != Some(path)
+    }
+
+    /// Indexes a file from disk if it changed since it was indexed, or drops it if it's gone or
+    /// doesn't belong in the index.
+    ///
+    /// Returns whether the index changed.
+    fn refresh(&mut self, path: &Path, output: Option<&Path>) -> bool {
+        if !self.wanted(path, output) {
+            let indexed = self.files.contains_key(path);
+            self.remove(path);
+            return indexed;
+        }
+        self.reindex(path)
+    }
+
+    /// Indexes a file from disk if it changed since it was indexed, or drops it if it's gone.
+    ///
+    /// Returns whether the index changed.
+    fn reindex(&mut self, path: &Path) -> bool {
+        let Some(modified) = modified(path) else {
+            let indexed = self.files.contains_key(path);
+            self.remove(path);
+            return indexed;
+        };
+        if self
+            .files
+            .get(path)
+            .is_some_and(|file| file.modified == modified)
+        {
+            return false;
+        }
+        match read_to_string(path) {
+            Ok(data) => self.add(path, &data, modified),
+            Err(_) => self.remove(path),
+        }
+        true
+    }
+
+    /// Brings the index up to date with the files in the project, skipping anything ignored by
+    /// `.gitignore` files and the compiled manuscript.
+    ///
+    /// Returns whether the index changed.
+    fn sync(&mut self) -> bool {
+        let output = manuscript::output(&self.root);
+        let files = WalkBuilder::new(&self.root)
+            .require_git(false)
+            .build()
+            .filter_map(Result::ok)
+            .filter(|entry| {
+                entry
+                    .file_type()
+                    .is_some_and(|file_type| file_type.is_file())
+                    && is_markdown(entry.path())
+                    && output.as_deref() != Some(entry.path())
+            })
+            .map(ignore::DirEntry::into_path)
+            .collect::<BTreeSet<_>>();
+        let gone = self
+            .files
+            .keys()
+            .filter(|path| !files.contains(*path))
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut changed = !gone.is_empty();
+        for path in gone {
+            self.remove(&path);
+        }
+        for path in &files {
+            changed |= self.reindex(path);
+        }
+        changed
+    }
+
+    /// Finds the positions a phrase starts at in each file it's in.
+    fn phrase(&self, phrase: &[String]) -> BTreeMap<&PathBuf, Vec<u32>> {
+        let Some((first, rest)) = phrase.split_first() else {
+            return BTreeMap::new();
+        };
+        let Some(files) = self.postings.get(first) else {
+            return BTreeMap::new();
+        };
+        files
+            .iter()
+            .filter_map(|(path, positions)| {
+                let starts = positions
+                    .iter()
+                    .copied()
+                    .filter(|start| {
+                        rest.iter().zip(1..).all(|(word, offset)| {
+                            self.postings
+                                .get(word)
+                                .and_then(|files| files.get(path))
+                                .is_some_and(|positions| {
+                                    positions.binary_search(&(start + offset)).is_ok()
+                                })
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                (!starts.is_empty()).then_some((path, starts))
+            })
+            .collect()
+    }
+
+    /// Finds the sections that have every word and phrase of a query, best first.
+    fn search(&self, query: &[Vec<String>]) -> Vec<SearchHit> {
+        #[allow(clippy::cast_precision_loss)]
+        let total = self.files.len().max(1) as f64;
+        let matches = query
+            .iter()
+            .map(|phrase| self.phrase(phrase))
+            .collect::<Vec<_>>();
+        let Some((first, rest)) = matches.split_first() else {
+            return Vec::new();
+        };
+        let mut hits = Vec::new();
+        for path in first.keys() {
+            if !rest.iter().all(|files| files.contains_key(path)) {
+                continue;
+            }
+            let file = &self.files[*path];
+            let headings = &self.headings[*path];
+            // The score of each section, and the first match in it.
+            let mut sections = BTreeMap::<usize, (f64, u32, usize)>::new();
+            for (clause, files) in matches.iter().enumerate() {
+                #[allow(clippy::cast_precision_loss)]
+                let rarity = (total / files.len() as f64).ln() + 1.;
+                for &position in &files[*path] {
+                    let weight = if headings.contains(&position) {
+                        HEADING_WEIGHT
+                    } else {
+                        1.
+                    };
+                    let section = sections
+                        .entry(file.section(position))
+                        .or_insert((0., position, 0));
+                    section.0 += weight * rarity;
+                    section.1 = section.1.min(position);
+                    section.2 |= 1 << clause.min(63);
+                }
+            }
+            let every = if matches.len() >= 64 {
+                usize::MAX
+            } else {
+                (1 << matches.len()) - 1
+            };
+            for (section, (score, position, clauses)) in sections {
+                if clauses & every != every {
+                    continue;
+                }
+                hits.push(SearchHit {
+                    path: (*path).clone(),
+                    heading: file.sections[section].heading.clone(),
+                    line: file.line(position),
+                    score,
+                });
+            }
+        }
+        hits.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        hits.truncate(MAX_RESULTS);
+        hits
+    }
+}
+
+/// A section of a file that matches a query.
+struct SearchHit {
+    path: PathBuf,
+    heading: String,
+    line: u32,
+    score: f64,
+}
+
+/// Splits a query into its words and its phrases in double quotes.
+fn parse_query(query: &str) -> Vec<Vec<String>> {
+    let mut clauses = Vec::new();
+    for (index, part) in query.split('"').enumerate() {
+        if index % 2 == 1 {
+            let phrase = words(part).collect::<Vec<_>>();
+            if !phrase.is_empty() {
+                clauses.push(phrase);
+            }
+        } else {
+            clauses.extend(words(part).map(|word| vec![word]));
+        }
+    }
+    clauses
+}
+
+/// The search index of the open project, and what watches its folder for changes.
+#[derive(Default)]
+pub struct SearchIndexStore {
+    index: Arc<Mutex<Option<Index>>>,
+    /// The project opened last, so an index that finishes loading after another project is
+    /// opened is dropped.
+    opened: Arc<Mutex<Option<PathBuf>>>,
+    watcher: Mutex<Option<RecommendedWatcher>>,
+}
+
+fn index_path(app_handle: &AppHandle, root: &Path) -> Result<PathBuf, String> {
+    let data = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|error| error.to_string())?;
+    let key = hash(root.as_os_str().as_encoded_bytes());
+    Ok(data
+        .join(INDEXES_FOLDER)
+        .join(format!("{}.json.gz", &key[..16])))
+}
+
+/// Loads the stored index of a project, or starts a new one if it has none or it's outdated.
+fn load(app_handle: &AppHandle, root: &Path) -> Result<Index, String> {
+    let path = index_path(app_handle, root)?;
+    let mut data = String::new();
+    let loaded = File::open(&path).and_then(|file| GzDecoder::new(file).read_to_string(&mut data));
+    match loaded {
+        Ok(_) => {}
+        Err(error) if error.kind() == ErrorKind::NotFound => {
+            return Ok(Index::new(root.to_path_buf()))
+        }
+        Err(error) => return Err(format!("{}: {error}", path.display())),
+    }
+    let index = serde_json::from_str::<Index>(&data)
+        .map_err(|error| format!("{}: {error}", path.display()))?;
+    if index.version != VERSION || index.root != root {
+        return Ok(Index::new(root.to_path_buf()));
+    }
+    Ok(index)
+}
+
+fn save(app_handle: &AppHandle, index: &Index) -> Result<(), String> {
+    let path = index_path(app_handle, &index.root)?;
+    if let Some(folder) = path.parent() {
+        create_dir_all(folder).map_err(|error| format!("{}: {error}", folder.display()))?;
+    }
+    let data = serde_json::to_string(index).map_err(|error| error.to_string())?;
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(data.as_bytes())
+        .and_then(|()| encoder.finish())
+        .and_then(|compressed| write(&path, compressed))
+        .map_err(|error| format!("{}: {error}", path.display()))
+}
+
+/// Tells the UI what went wrong with the index. The error is dropped if the window is already
+/// gone, since there's no one left to tell.
+fn report(app_handle: &AppHandle, error: String) {
+    let _ = app_handle.emit("search-index-error", error);
+}
+
+/// Brings the files some events are about up to date in the index, and stores it if it changed.
+fn apply_event(app_handle: &AppHandle, index: &Mutex<Option<Index>>, event: &Event) {
+    let mut index = index.lock().unwrap();
+    let Some(index) = index.as_mut() else {
+        return;
+    };
+    // Finding the manuscript's output reads its file, so it's only done once for every path.
+    let output = manuscript::output(&index.root);
+    let mut changed = false;
+    for path in &event.paths {
+        changed |= index.refresh(path, output.as_deref());
+    }
+    if changed {
+        if let Err(error) = save(app_handle, index) {
+            report(app_handle, error);
+        }
+    }
+}
+
+/// Opens the search index of a project on a background thread, loading the stored one and
+/// bringing it up to date with the files on disk, then watches the project's folder for changes
+/// to keep it up to date.
+///
+/// Emits `search-index-error` events describing what went wrong.
+#[command]
+pub fn open_search_index(app_handle: AppHandle, store: State<SearchIndexStore>, root: PathBuf) {
+    *store.watcher.lock().unwrap() = None;
+    *store.index.lock().unwrap() = None;
+    store.opened.lock().unwrap().replace(root.clone());
+    let shared = store.index.clone();
+    let opened = store.opened.clone();
+    let watched = app_handle.clone();
+    let events = shared.clone();
+    let watcher = recommended_watcher(move |event: notify::Result<Event>| match event {
+        Ok(event) => apply_event(&watched, &events, &event),
+        Err(error) => report(&watched, error.to_string()),
+    })
+    .and_then(|mut watcher| {
+        watcher.watch(&root, RecursiveMode::Recursive)?;
+        Ok(watcher)
+    });
+    match watcher {
+        Ok(watcher) => *store.watcher.lock().unwrap() = Some(watcher),
+        Err(error) => report(&app_handle, format!("{}: {error}", root.display())),
+    }
+    spawn(move || {
+        let mut index = match load(&app_handle, &root) {
+            Ok(index) => index,
+            Err(error) => {
+                report(&app_handle, error);
+                Index::new(root)
+            }
+        };
+        if index.sync() {
+            if let Err(error) = save(&app_handle, &index) {
+                report(&app_handle, error);
+            }
+        }
+        let opened = opened.lock().unwrap();
+        if opened.as_ref() == Some(&index.root) {
+            *shared.lock().unwrap() = Some(index);
+        }
+    });
+}
+
+/// Updates a file that was saved with some data in the search index, if it's in the open project.
+pub fn update(app_handle: &AppHandle, store: &SearchIndexStore, path: &Path, data: &str) {
+    let mut index = store.index.lock().unwrap();
+    let Some(index) = index.as_mut() else {
+        return;
+    };
+    if !index.wanted(path, manuscript::output(&index.root).as_deref()) {
+        return;
+    }
+    index.add(path, data, modified(path).unwrap_or_default());
+    if let Err(error) = save(app_handle, index) {
+        report(app_handle, error);
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexResult {
+    path: PathBuf,
+    /// The heading of the section that matched, which is empty before the first heading.
+    heading: String,
+    /// The line of the first match in the section.
+    line: u32,
+    /// The text of that line.
+    text: String,
+    score: f64,
+}
+
+/// Searches the open project's index for the sections of files that have every word of a query,
+/// and every phrase in double quotes in it, best matches first.
+///
+/// Returns the results, and a description of what went wrong.
+#[command]
+pub fn query_search_index(
+    store: State<SearchIndexStore>,
+    query: String,
+) -> (Vec<IndexResult>, Option<String>) {
+    let clauses = parse_query(&query);
+    if clauses.is_empty() {
+        return (Vec::new(), None);
+    }
+    let hits = match store.index.lock().unwrap().as_ref() {
+        Some(index) => index.search(&clauses),
+        None => {
+            return (
+                Vec::new(),
+                Some("the project isn't indexed yet".to_string()),
+            )
+        }
+    };
+    let results = hits
+        .into_iter()
+        .map(|hit| {
+            let text = read_to_string(&hit.path)
+                .ok()
+                .and_then(|data| {
+                    let line = data.lines().nth(usize::try_from(hit.line).ok()?)?;
+                    Some(line.trim().chars().take(MAX_RESULT_TEXT).collect())
+                })
+                .unwrap_or_default();
+            IndexResult {
+                path: hit.path,
+                heading: hit.heading,
+                line: hit.line,
+                text,
+                score: hit.score,
+            }
+        })
+        .collect();
+    (results, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+    use std::fs::{remove_dir_all, remove_file};
+    use std::process;
+
+    use super::*;
+
+    fn search(index: &Index, query: &str) -> Vec<(PathBuf, String, u32)> {
+        index
+            .search(&parse_query(query))
+            .into_iter()
+            .map(|hit| (hit.path, hit.heading, hit.line))
+            .collect()
+    }
+
+    fn score(index: &Index, query: &str, path: &str) -> f64 {
+        index
+            .search(&parse_query(query))
+            .into_iter()
+            .find(|hit| hit.path == Path::new(path))
+            .unwrap()
+            .score
+    }
+
+    #[test]
+    fn splitting_words_works() {
+        assert_eq!(
+            words("Don't STOP, café-au-lait! 3.14").collect::<Vec<_>>(),
+            ["don't", "stop", "café", "au", "lait", "3.14"]
+        );
+        assert_eq!(
+            parse_query(r#"one "Two  three" four "" "five"#),
+            [
+                vec!["one"],
+                vec!["two", "three"],
+                vec!["four"],
+                vec!["five"]
+            ]
+        );
+    }
+
+    #[test]
+    fn searching_works() {
+        let mut index = Index::new(PathBuf::from("/notes"));
+        index.add(
+            Path::new("/notes/a.md"),
+            "intro\n# Ships\nthe ship sails\n## Harbour\nno ships here",
+            0,
+        );
+        index.add(Path::new("/notes/b.md"), "a ship and a harbour", 0);
+        assert_eq!(
+            search(&index, "ship"),
+            [
+                (PathBuf::from("/notes/a.md"), "Ships".to_string(), 2),
+                (PathBuf::from("/notes/b.md"), String::new(), 0),
+            ]
+        );
+        assert_eq!(
+            search(&index, "ship harbour"),
+            [(PathBuf::from("/notes/b.md"), String::new(), 0)]
+        );
+        assert!(search(&index, "ship whale").is_empty());
+    }
+
+    #[test]
+    fn ranking_works() {
+        let mut index = Index::new(PathBuf::from("/notes"));
+        index.add(Path::new("/notes/a.md"), "whales swim", 0);
+        index.add(Path::new("/notes/b.md"), "# Whales\nthey swim", 0);
+        index.add(Path::new("/notes/c.md"), "whales and whales", 0);
+        index.add(Path::new("/notes/d.md"), "fish swim", 0);
+        // Matches in headings count double, and more matches count more.
+        let ranked = search(&index, "whales")
+            .into_iter()
+            .map(|(path, ..)| path)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ranked,
+            [
+                PathBuf::from("/notes/b.md"),
+                PathBuf::from("/notes/c.md"),
+                PathBuf::from("/notes/a.md"),
+            ]
+        );
+        // Rarer words count more.
+        assert!(score(&index, "fish", "/notes/d.md") > score(&index, "swim", "/notes/d.md"));
+    }
+
+    #[test]
+    fn finding_sections_works() {
+        let path = Path::new("/notes/a.md");
+        let mut index = Index::new(PathBuf::from("/notes"));
+        // Only the headings the editor shows start sections, not ones inside emphasis or empty
+        // ones.
+        index.add(
+            path,
+            "**Bold\n# not a heading**\n#hashtag\n# \n## Real *one*\ntext",
+            0,
+        );
+        let sections = index.files[path]
+            .sections
+            .iter()
+            .map(|section| (section.heading.as_str(), section.line, section.start))
+            .collect::<Vec<_>>();
+        assert_eq!(sections, [("", 0, 0), ("Real *one*", 4, 5)]);
+        assert_eq!(index.headings[path], BTreeSet::from([5, 6]));
+        assert_eq!(
+            search(&index, "heading"),
+            [(path.to_path_buf(), String::new(), 1)]
+        );
+    }
+
+    #[test]
+    fn searching_phrases_works() {
+        let mut index = Index::new(PathBuf::from("/notes"));
+        index.add(Path::new("/notes/a.md"), "the quick brown fox", 0);
+        index.add(Path::new("/notes/b.md"), "brown and quick", 0);
+        index.add(
+            Path::new("/notes/c.md"),
+            "# Bears\nthe quick\nbrown bear",
+            0,
+        );
+        assert_eq!(search(&index, "quick brown").len(), 3);
+        // Phrases run across lines, like the words of a paragraph.
+        assert_eq!(
+            search(&index, r#""Quick Brown""#),
+            [
+                (PathBuf::from("/notes/a.md"), String::new(), 0),
+                (PathBuf::from("/notes/c.md"), "Bears".to_string(), 1),
+            ]
+        );
+        assert!(search(&index, r#""fox brown""#).is_empty());
+    }
+
+    #[test]
+    fn updating_works() {
+        let path = Path::new("/notes/a.md");
+        let mut index = Index::new(PathBuf::from("/notes"));
+        index.add(path, "# Old\nold words", 1);
+        index.add(path, "new words", 2);
+        assert!(search(&index, "old").is_empty());
+        assert!(!index.postings.contains_key("old"));
+        assert_eq!(search(&index, "new").len(), 1);
+        assert_eq!(index.files[path].modified, 2);
+        index.remove(path);
+        assert!(index.files.is_empty());
+        assert!(index.postings.is_empty());
+        assert!(index.headings.is_empty());
+    }
+
+    #[test]
+    fn refreshing_works() {
+        let root = temp_dir().join(format!("search-index-{}", process::id()));
+        create_dir_all(&root).unwrap();
+        let note = root.join("note.md");
+        let other = root.join("note.txt");
+        write(&note, "hello there").unwrap();
+        write(&other, "hello there").unwrap();
+        let mut index = Index::new(root.clone());
+        assert!(index.sync());
+        assert!(!index.refresh(&note, None));
+        assert!(!index.refresh(&other, None));
+        assert_eq!(search(&index, "hello").len(), 1);
+        remove_file(&note).unwrap();
+        assert!(index.refresh(&note, None));
+        assert!(search(&index, "hello").is_empty());
+        write(&note, "hello again").unwrap();
+        assert!(index.sync());
+        assert_eq!(search(&index, "again").len(), 1);
+        // The compiled manuscript is left out.
+        assert!(index.refresh(&note, Some(&note)));
+        assert!(index.files.is_empty());
+        assert!(index.refresh(&note, None));
+        remove_dir_all(&root).unwrap();
+        assert!(index.sync());
+        assert!(index.files.is_empty());
+    }
+}
@@ -0,0 +1,408 @@
+use std::fs::{create_dir_all, read_to_string, write};
+use std::io::ErrorKind;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{from_value, to_value, Map, Value};
+use tauri::{command, AppHandle, Emitter, Manager, State};
+
+/// The name of the file in the app config directory that the settings are stored in.
+const SETTINGS_FILE: &str = "settings.json";
+
+/// The version of the settings file's layout, stored in it as `version`. Bump it and teach
+/// [`migrate`] to upgrade the previous layout whenever a setting is renamed or changes meaning.
+const VERSION: u64 = 2;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    Dark,
+    Light,
+    Custom(Palette),
+}
+
+/// The colours of a custom theme, each written as `#rrggbb`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Palette {
+    background: String,
+    highlight: String,
+    accent: String,
+    fade: String,
+    text: String,
+    caret: String,
+    error: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    Sentence,
+    Paragraph,
+}
+
+/// Which style rules flag the text, where rules missing from the file are enabled.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct StyleRules {
+    repeated_words: bool,
+    long_sentences: bool,
+    passive_voice: bool,
+    adverbs: bool,
+    cliches: bool,
+}
+
+impl Default for StyleRules {
+    fn default() -> Self {
+        Self {
+            repeated_words: true,
+            long_sentences: true,
+            passive_voice: true,
+            adverbs: true,
+            cliches: true,
+        }
+    }
+}
+
+/// Everything the user can configure besides the keymap, mirroring `Settings` in the UI.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Settings {
+    theme: Theme,
+    font_family: String,
+    font_size: f64,
+    line_height: f64,
+    column_width: u32,
+    focus: bool,
+    focus_granularity: Granularity,
+    typewriter: bool,
+    typewriter_position: f64,
+    spell_check: bool,
+    spelling_language: String,
+    style: StyleRules,
+    /// The words to write each day, or 0 for no goal.
+    daily_goal: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            theme: Theme::Dark,
+            font_family: "DejaVu".to_string(),
+            font_size: 14.,
+            line_height: 1.5,
+            column_width: 96,
+            focus: false,
+            focus_granularity: Granularity::Sentence,
+            typewriter: false,
+            typewriter_position: 0.5,
+            spell_check: true,
+            spelling_language: "en_US".to_string(),
+            style: StyleRules::default(),
+            daily_goal: 0,
+        }
+    }
+}
+
+impl Settings {
+    /// Describes every setting with a value out of its range.
+    fn validate(&self) -> Vec<String> {
+        fn check<T: PartialOrd + Copy + std::fmt::Display>(
+            problems: &mut Vec<String>,
+            name: &str,
+            value: T,
+            range: RangeInclusive<T>,
+        ) {
+            if !range.contains(&value) {
+                problems.push(format!(
+                    "`{name}` is {value} but has to be from {} to {}",
+                    range.start(),
+                    range.end()
+                ));
+            }
+        }
+
+        let mut problems = Vec::new();
+        if let Theme::Custom(palette) = &self.theme {
+            for color in [
+                &palette.background,
+                &palette.highlight,
+                &palette.accent,
+                &palette.fade,
+                &palette.text,
+                &palette.caret,
+                &palette.error,
+            ] {
+                let valid = color.strip_prefix('#').is_some_and(|hex| {
+                    hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit())
+                });
+                if !valid {
+                    problems.push(format!("`{color}` isn't a colour like `#rrggbb`"));
+                }
+            }
+        }
+        if self.font_family.trim().is_empty() {
+            problems.push("`font_family` is empty".to_string());
+        }
+        if self.spelling_language.trim().is_empty() {
+            problems.push("`spelling_language` is empty".to_string());
+        }
+        check(&mut problems, "font_size", self.font_size, 8.0..=48.);
+        check(&mut problems, "line_height", self.line_height, 1.0..=3.);
+        check(&mut problems, "column_width", self.column_width, 20..=400);
+        check(&mut problems, "daily_goal", self.daily_goal, 0..=100_000);
+        check(
+            &mut problems,
+            "typewriter_position",
+            self.typewriter_position,
+            0.0..=1.,
+        );
+        problems
+    }
+}
+
+/// The settings as they were last loaded or saved.
+#[derive(Default)]
+pub struct SettingsStore(Mutex<Settings>);
+
+fn settings_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    app_handle
+        .path()
+        .app_config_dir()
+        .map(|directory| directory.join(SETTINGS_FILE))
+        .map_err(|error| error.to_string())
+}
+
+/// Upgrades the settings of a file written with an older layout version to the current one.
+fn migrate(settings: &mut Map<String, Value>, version: u64) {
+    // Version 2 added the colour of misspellings to custom themes.
+    if version < 2 {
+        let palette = settings
+            .get_mut("theme")
+            .and_then(|theme| theme.get_mut("custom"))
+            .and_then(Value::as_object_mut);
+        if let Some(palette) = palette {
+            palette.entry("error").or_insert_with(|| "#e06c5f".into());
+        }
+    }
+}
+
+/// Reads the settings from their file's contents, falling back to the default of every setting
+/// that is missing or invalid so one mistake doesn't lose the rest.
+///
+/// Returns the settings and a description of everything that was wrong with the file.
+fn parse(data: &str) -> (Settings, Vec<String>) {
+    let mut problems = Vec::new();
+    let mut file = match serde_json::from_str::<Map<String, Value>>(data) {
+        Ok(file) => file,
+        Err(error) => return (Settings::default(), vec![error.to_string()]),
+    };
+    let version = match file.remove("version") {
+        // Files without a version were written by hand in the current layout.
+        None => VERSION,
+        Some(version) => match version.as_u64() {
+            Some(version) if version <= VERSION => version,
+            _ => {
+                let problem = format!(
+                    "version {version} isn't supported, the latest is {VERSION}; using the defaults"
+                );
+                return (Settings::default(), vec![problem]);
+            }
+        },
+    };
+    migrate(&mut file, version);
+    let mut settings = Settings::default();
+    for (name, value) in file {
+        let Value::Object(mut candidate) = to_value(&settings).unwrap() else {
+            unreachable!();
+        };
+        if !candidate.contains_key(&name) {
+            problems.push(format!("unknown setting `{name}`"));
+            continue;
+        }
+        candidate.insert(name.clone(), value);
+        match from_value::<Settings>(Value::Object(candidate)) {
+            Ok(candidate) => match candidate.validate().into_iter().next() {
+                Some(problem) => problems.push(problem),
+                None => settings = candidate,
+            },
+            Err(error) => problems.push(format!("`{name}`: {error}")),
+        }
+    }
+    (settings, problems)
+}
+
+/// Loads the settings from `settings.json` in the app config directory.
+///
+/// Returns the settings, which are the defaults if there is no settings file, and a description
+/// of everything that was wrong with it.
+#[command]
+pub fn get_settings(app_handle: AppHandle, store: State<SettingsStore>) -> (Settings, Vec<String>) {
+    let (settings, problems) = match settings_path(&app_handle) {
+        Ok(path) => match read_to_string(&path) {
+            Ok(data) => {
+                let (settings, problems) = parse(&data);
+                let problems = problems
+                    .into_iter()
+                    .map(|problem| format!("{}: {problem}", path.display()))
+                    .collect();
+                (settings, problems)
+            }
+            Err(error) if error.kind() == ErrorKind::NotFound => (Settings::default(), Vec::new()),
+            Err(error) => (
+                Settings::default(),
+                vec![format!("{}: {error}", path.display())],
+            ),
+        },
+        Err(error) => (Settings::default(), vec![error]),
+    };
+    store.0.lock().unwrap().clone_from(&settings);
+    (settings, problems)
+}
+
+/// Validates and saves the settings, then emits them in a `settings-changed` event if they
+/// changed.
+///
+/// Returns a description of everything wrong with the settings, which aren't saved if there is
+/// anything, or of what went wrong saving them.
+#[command]
+pub fn set_settings(
+    app_handle: AppHandle,
+    store: State<SettingsStore>,
+    settings: Settings,
+) -> Vec<String> {
+    let problems = settings.validate();
+    if !problems.is_empty() {
+        return problems;
+    }
+    let mut stored = store.0.lock().unwrap();
+    if *stored == settings {
+        return Vec::new();
+    }
+    let path = match settings_path(&app_handle) {
+        Ok(path) => path,
+        Err(error) => return vec![error],
+    };
+    let Value::Object(mut file) = to_value(&settings).unwrap() else {
+        unreachable!();
+    };
+    file.insert("version".to_string(), VERSION.into());
+    let data = serde_json::to_string_pretty(&file).unwrap();
+    if let Err(error) = path
+        .parent()
+        .map_or(Ok(()), create_dir_all)
+        .and_then(|()| write(&path, data))
+    {
+        return vec![format!("{}: {error}", path.display())];
+    }
+    stored.clone_from(&settings);
+    // The settings are saved either way, and windows that miss the change read them when they
+    // open.
+    let _ = app_handle.emit("settings-changed", settings);
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn validating_works() {
+        assert!(Settings::default().validate().is_empty());
+        let settings = Settings {
+            font_size: 4.,
+            font_family: " ".to_string(),
+            theme: Theme::Custom(Palette {
+                background: "#000000".to_string(),
+                highlight: "#111111".to_string(),
+                accent: "#22222g".to_string(),
+                fade: "#333333".to_string(),
+                text: "444444".to_string(),
+                caret: "#555555".to_string(),
+                error: "#666666".to_string(),
+            }),
+            ..Settings::default()
+        };
+        assert_eq!(
+            settings.validate(),
+            [
+                "`#22222g` isn't a colour like `#rrggbb`",
+                "`444444` isn't a colour like `#rrggbb`",
+                "`font_family` is empty",
+                "`font_size` is 4 but has to be from 8 to 48",
+            ]
+        );
+    }
+
+    #[test]
+    fn migrating_works() {
+        let Value::Object(mut settings) = json!({
+            "theme": { "custom": { "background": "#000000" } },
+        }) else {
+            unreachable!();
+        };
+        migrate(&mut settings, 1);
+        assert_eq!(settings["theme"]["custom"]["error"], "#e06c5f");
+        let Value::Object(mut settings) = json!({
+            "theme": { "custom": { "error": "#123456" } },
+        }) else {
+            unreachable!();
+        };
+        migrate(&mut settings, 1);
+        assert_eq!(settings["theme"]["custom"]["error"], "#123456");
+        migrate(&mut settings, VERSION);
+        assert_eq!(settings["theme"]["custom"]["error"], "#123456");
+    }
+
+    #[test]
+    fn parsing_works() {
+        let (settings, problems) = parse(r#"{ "version": 2, "font_size": 20, "focus": true }"#);
+        assert!(problems.is_empty());
+        assert_eq!(settings.font_size, 20.);
+        assert!(settings.focus);
+
+        let (settings, problems) =
+            parse(r#"{ "font_size": 100, "unknown": 1, "focus": "yes", "typewriter": true }"#);
+        assert_eq!(settings.font_size, Settings::default().font_size);
+        assert!(!settings.focus);
+        assert!(settings.typewriter);
+        assert_eq!(problems.len(), 3);
+        assert!(problems.contains(&"unknown setting `unknown`".to_string()));
+
+        let (settings, problems) = parse(r#"{ "version": 3, "focus": true }"#);
+        assert_eq!(settings, Settings::default());
+        assert_eq!(
+            problems,
+            ["version 3 isn't supported, the latest is 2; using the defaults"]
+        );
+
+        let (settings, problems) = parse("not json");
+        assert_eq!(settings, Settings::default());
+        assert_eq!(problems.len(), 1);
+    }
+
+    #[test]
+    fn parsing_migrates_old_versions() {
+        let data = json!({
+            "version": 1,
+            "theme": { "custom": {
+                "background": "#000000",
+                "highlight": "#111111",
+                "accent": "#222222",
+                "fade": "#333333",
+                "text": "#444444",
+                "caret": "#555555",
+            } },
+        });
+        let (settings, problems) = parse(&data.to_string());
+        assert!(problems.is_empty(), "{problems:?}");
+        let Theme::Custom(palette) = settings.theme else {
+            panic!("the custom theme wasn't kept");
+        };
+        assert_eq!(palette.error, "#e06c5f");
+    }
+}
@@ -0,0 +1,226 @@
+use std::fs::{create_dir_all, read_to_string, write, File};
+use std::io::{ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{command, AppHandle, Manager};
+
+/// The folder in the app data directory that snapshots are stored in, with a folder for each
+/// file holding its index and the compressed contents of its snapshots named by their hash.
+const SNAPSHOTS_FOLDER: &str = "snapshots";
+
+/// The name of the file in a file's snapshot folder listing its snapshots.
+const INDEX_FILE: &str = "index.json";
+
+/// The contents of a file as it was saved at some time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// When the file was saved, in milliseconds since the epoch.
+    time: u64,
+    /// The SHA-256 hash of the contents, which names their compressed copy.
+    hash: String,
+    /// The size of the contents in bytes.
+    size: usize,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Index {
+    /// The file the snapshots are of.
+    path: PathBuf,
+    /// The snapshots, oldest first.
+    snapshots: Vec<Snapshot>,
+}
+
+pub fn hash(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Finds the folder the snapshots of a file are stored in.
+fn folder(app_handle: &AppHandle, path: &Path) -> Result<PathBuf, String> {
+    let data = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|error| error.to_string())?;
+    let key = hash(path.as_os_str().as_encoded_bytes());
+    Ok(data.join(SNAPSHOTS_FOLDER).join(&key[..16]))
+}
+
+fn read_index(folder: &Path) -> Result<Index, String> {
+    let path = folder.join(INDEX_FILE);
+    match read_to_string(&path) {
+        Ok(data) => {
+            serde_json::from_str(&data).map_err(|error| format!("{}: {error}", path.display()))
+        }
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(Index::default()),
+        Err(error) => Err(format!("{}: {error}", path.display())),
+    }
+}
+
+fn write_index(folder: &Path, index: &Index) -> Result<(), String> {
+    let path = folder.join(INDEX_FILE);
+    let data = serde_json::to_string_pretty(index).map_err(|error| error.to_string())?;
+    write(&path, data).map_err(|error| format!("{}: {error}", path.display()))
+}
+
+/// Takes a snapshot of the contents a file was saved with, unless they're the same as the last
+/// snapshot's. Contents shared with an older snapshot are only stored once.
+pub fn record(app_handle: &AppHandle, path: &Path, data: &str) -> Result<(), String> {
+    record_in(&folder(app_handle, path)?, path, data)
+}
+
+/// Takes a snapshot of a file in the folder its snapshots are stored in, like [`record`].
+fn record_in(folder: &Path, path: &Path, data: &str) -> Result<(), String> {
+    let mut index = read_index(folder)?;
+    let hash = hash(data.as_bytes());
+    if index
+        .snapshots
+        .last()
+        .is_some_and(|snapshot| snapshot.hash == hash)
+    {
+        return Ok(());
+    }
+    let object = folder.join(format!("{hash}.gz"));
+    create_dir_all(folder).map_err(|error| format!("{}: {error}", folder.display()))?;
+    if !object.is_file() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        let compressed = encoder
+            .write_all(data.as_bytes())
+            .and_then(|()| encoder.finish())
+            .and_then(|compressed| write(&object, compressed));
+        compressed.map_err(|error| format!("{}: {error}", object.display()))?;
+    }
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| {
+            u64::try_from(time.as_millis()).unwrap_or(u64::MAX)
+        });
+    path.clone_into(&mut index.path);
+    index.snapshots.push(Snapshot {
+        time,
+        hash,
+        size: data.len(),
+    });
+    write_index(folder, &index)
+}
+
+/// Lists the snapshots of a file, newest first.
+///
+/// Returns the snapshots, and a description of what went wrong reading them.
+#[command]
+pub fn list_snapshots(app_handle: AppHandle, path: PathBuf) -> (Vec<Snapshot>, Option<String>) {
+    match folder(&app_handle, &path).and_then(|folder| read_index(&folder)) {
+        Ok(index) => (index.snapshots.into_iter().rev().collect(), None),
+        Err(error) => (Vec::new(), Some(error)),
+    }
+}
+
+/// Loads the contents of a file's snapshot by their hash.
+///
+/// Returns the contents, or a description of what went wrong loading them.
+#[command]
+pub fn load_snapshot(
+    app_handle: AppHandle,
+    path: PathBuf,
+    hash: String,
+) -> (Option<String>, Option<String>) {
+    if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return (None, Some(format!("`{hash}` isn't a snapshot hash")));
+    }
+    let object = match folder(&app_handle, &path) {
+        Ok(folder) => folder.join(format!("{hash}.gz")),
+        Err(error) => return (None, Some(error)),
+    };
+    let mut data = String::new();
+    let loaded =
+        File::open(&object).and_then(|file| GzDecoder::new(file).read_to_string(&mut data));
+    match loaded {
+        Ok(_) => (Some(data), None),
+        Err(error) => (None, Some(format!("{}: {error}", object.display()))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+    use std::fs::{read_dir, remove_dir_all};
+    use std::process;
+
+    use super::*;
+
+    fn snapshot_folder(name: &str) -> PathBuf {
+        let folder = temp_dir().join(format!("snapshots-{name}-{}", process::id()));
+        let _ = remove_dir_all(&folder);
+        folder
+    }
+
+    fn load(folder: &Path, hash: &str) -> String {
+        let mut data = String::new();
+        GzDecoder::new(File::open(folder.join(format!("{hash}.gz"))).unwrap())
+            .read_to_string(&mut data)
+            .unwrap();
+        data
+    }
+
+    #[test]
+    fn recording_works() {
+        let folder = snapshot_folder("record");
+        let path = Path::new("/notes/a.md");
+        for data in ["one", "one", "two", "one"] {
+            record_in(&folder, path, data).unwrap();
+        }
+        let index = read_index(&folder).unwrap();
+        assert_eq!(index.path, path);
+        let hashes = index
+            .snapshots
+            .iter()
+            .map(|snapshot| snapshot.hash.as_str())
+            .collect::<Vec<_>>();
+        // The same contents saved twice in a row are one snapshot.
+        assert_eq!(hashes, [hash(b"one"), hash(b"two"), hash(b"one")]);
+        assert!(index
+            .snapshots
+            .windows(2)
+            .all(|pair| pair[0].time <= pair[1].time));
+        assert_eq!(index.snapshots[1].size, 3);
+        // Contents shared by snapshots are stored once.
+        assert_eq!(read_dir(&folder).unwrap().count(), 3);
+        assert_eq!(load(&folder, &hash(b"one")), "one");
+        assert_eq!(load(&folder, &hash(b"two")), "two");
+        remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn reading_and_writing_indexes_works() {
+        let folder = snapshot_folder("index");
+        let index = read_index(&folder).unwrap();
+        assert_eq!(index.path, PathBuf::new());
+        assert!(index.snapshots.is_empty());
+        assert!(write_index(&folder, &index).is_err());
+
+        create_dir_all(&folder).unwrap();
+        let index = Index {
+            path: PathBuf::from("/notes/a.md"),
+            snapshots: vec![Snapshot {
+                time: 1,
+                hash: hash(b"one"),
+                size: 3,
+            }],
+        };
+        write_index(&folder, &index).unwrap();
+        let read = read_index(&folder).unwrap();
+        assert_eq!(read.path, index.path);
+        assert_eq!(read.snapshots, index.snapshots);
+
+        write(folder.join(INDEX_FILE), "not json").unwrap();
+        assert!(read_index(&folder).is_err());
+        remove_dir_all(&folder).unwrap();
+    }
+}
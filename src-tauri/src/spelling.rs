@@ -0,0 +1,147 @@
+use std::fs::{create_dir_all, read_to_string, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::iter::once;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use spellbook::Dictionary;
+use tauri::{command, AppHandle, Manager, State};
+
+/// The folder in the app config directory that Hunspell dictionaries are looked for in first.
+const DICTIONARIES_FOLDER: &str = "dictionaries";
+
+/// Where Hunspell dictionaries installed on the system are looked for, after the app config
+/// directory.
+const SYSTEM_DICTIONARIES: &[&str] = &[
+    "/usr/share/hunspell",
+    "/usr/share/myspell",
+    "/usr/local/share/hunspell",
+    "/Library/Spelling",
+];
+
+/// The name of the file in the app config directory with the words added to the personal
+/// dictionary, one per line.
+const PERSONAL_DICTIONARY: &str = "personal.dic";
+
+/// The dictionary of the language last checked, if it loaded.
+#[derive(Default)]
+pub struct Spelling(Mutex<Option<(String, Dictionary)>>);
+
+/// Loads the Hunspell dictionary of a language like `en_US` from its `.aff` and `.dic` files,
+/// along with the personal dictionary.
+fn load(app_handle: &AppHandle, language: &str) -> Result<Dictionary, String> {
+    let config = app_handle
+        .path()
+        .app_config_dir()
+        .map_err(|error| error.to_string())?;
+    let folder = once(config.join(DICTIONARIES_FOLDER))
+        .chain(SYSTEM_DICTIONARIES.iter().map(PathBuf::from))
+        .find(|folder| folder.join(format!("{language}.dic")).is_file())
+        .ok_or_else(|| format!("no dictionary for `{language}` was found"))?;
+    let read = |extension: &str| {
+        let path = folder.join(format!("{language}.{extension}"));
+        read_to_string(&path).map_err(|error| format!("{}: {error}", path.display()))
+    };
+    let mut dictionary =
+        Dictionary::new(&read("aff")?, &read("dic")?).map_err(|error| error.to_string())?;
+    let path = config.join(PERSONAL_DICTIONARY);
+    match read_to_string(&path) {
+        Ok(words) => {
+            for word in words.lines().map(str::trim).filter(|word| !word.is_empty()) {
+                dictionary
+                    .add(word)
+                    .map_err(|error| format!("{}: {error}", path.display()))?;
+            }
+        }
+        Err(error) if error.kind() == ErrorKind::NotFound => {}
+        Err(error) => return Err(format!("{}: {error}", path.display())),
+    }
+    Ok(dictionary)
+}
+
+/// Runs a function with the dictionary of a language, loading it first if it isn't loaded.
+fn with_dictionary<R>(
+    app_handle: &AppHandle,
+    spelling: &Spelling,
+    language: &str,
+    f: impl FnOnce(&mut Dictionary) -> R,
+) -> Result<R, String> {
+    let mut loaded = spelling.0.lock().unwrap();
+    if loaded.as_ref().is_none_or(|(loaded, _)| loaded != language) {
+        *loaded = Some((language.to_string(), load(app_handle, language)?));
+    }
+    Ok(f(&mut loaded.as_mut().unwrap().1))
+}
+
+/// Checks the spelling of some words in a language like `en_US`.
+///
+/// Returns the misspelled words, and a description of what went wrong if the dictionary couldn't
+/// be loaded.
+#[command]
+pub fn check_words(
+    app_handle: AppHandle,
+    spelling: State<Spelling>,
+    language: String,
+    words: Vec<String>,
+) -> (Vec<String>, Option<String>) {
+    let checked = with_dictionary(&app_handle, &spelling, &language, |dictionary| {
+        words
+            .into_iter()
+            .filter(|word| !dictionary.check(word))
+            .collect()
+    });
+    match checked {
+        Ok(misspelled) => (misspelled, None),
+        Err(error) => (Vec::new(), Some(error)),
+    }
+}
+
+/// Suggests corrections for a misspelled word, best first. There are none if the dictionary
+/// couldn't be loaded.
+#[command]
+pub fn suggest_words(
+    app_handle: AppHandle,
+    spelling: State<Spelling>,
+    language: String,
+    word: String,
+) -> Vec<String> {
+    with_dictionary(&app_handle, &spelling, &language, |dictionary| {
+        let mut suggestions = Vec::new();
+        dictionary.suggest(&word, &mut suggestions);
+        suggestions
+    })
+    .unwrap_or_default()
+}
+
+/// Adds a word to the personal dictionary, which every language accepts.
+///
+/// Returns a description of what went wrong if the word couldn't be saved.
+#[command]
+pub fn add_to_dictionary(
+    app_handle: AppHandle,
+    spelling: State<Spelling>,
+    word: String,
+) -> Option<String> {
+    let word = word.trim();
+    if word.is_empty() || word.contains('/') {
+        return Some(format!("`{word}` can't be added to the dictionary"));
+    }
+    let config = match app_handle.path().app_config_dir() {
+        Ok(config) => config,
+        Err(error) => return Some(error.to_string()),
+    };
+    let path = config.join(PERSONAL_DICTIONARY);
+    let saved = create_dir_all(&config).and_then(|()| {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| writeln!(file, "{word}"))
+    });
+    if let Err(error) = saved {
+        return Some(format!("{}: {error}", path.display()));
+    }
+    let mut loaded = spelling.0.lock().unwrap();
+    let (_, dictionary) = loaded.as_mut()?;
+    dictionary.add(word).err().map(|error| error.to_string())
+}
@@ -0,0 +1,178 @@
+use std::collections::BTreeMap;
+use std::fs::{create_dir_all, read_to_string, write};
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Manager, State};
+
+/// The name of the file in the app config directory that the writing log is stored in.
+const LOG_FILE: &str = "writing-log.json";
+
+/// The version of the writing log file's layout, stored in it as `version`.
+const VERSION: u64 = 1;
+
+/// The words written each day in each file, and the word goals of files.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WritingLog {
+    version: u64,
+    /// The net words written in each file, by day as `yyyy-mm-dd` and then by path. Untitled
+    /// documents have an empty path.
+    days: BTreeMap<String, BTreeMap<String, i64>>,
+    /// The word goals of files, by path.
+    goals: BTreeMap<String, u64>,
+}
+
+impl WritingLog {
+    /// Adds some net words written in a file on a day to the words written in it that day.
+    fn add_words(&mut self, day: String, path: String, words: i64) {
+        *self.days.entry(day).or_default().entry(path).or_default() += words;
+    }
+}
+
+/// The writing log as it was last loaded or saved, if it has been.
+#[derive(Default)]
+pub struct WritingLogStore(Mutex<Option<WritingLog>>);
+
+fn log_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    app_handle
+        .path()
+        .app_config_dir()
+        .map(|directory| directory.join(LOG_FILE))
+        .map_err(|error| error.to_string())
+}
+
+fn load(app_handle: &AppHandle) -> Result<WritingLog, String> {
+    let path = log_path(app_handle)?;
+    let data = match read_to_string(&path) {
+        Ok(data) => data,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(WritingLog::default()),
+        Err(error) => return Err(format!("{}: {error}", path.display())),
+    };
+    let log = serde_json::from_str::<WritingLog>(&data)
+        .map_err(|error| format!("{}: {error}", path.display()))?;
+    if log.version > VERSION {
+        return Err(format!(
+            "{}: version {} isn't supported, the latest is {VERSION}",
+            path.display(),
+            log.version
+        ));
+    }
+    Ok(log)
+}
+
+/// Runs a function that changes the writing log, loading it first if it isn't loaded, then saves
+/// it.
+///
+/// Returns a description of what went wrong loading or saving the log.
+fn update(
+    app_handle: &AppHandle,
+    store: &WritingLogStore,
+    f: impl FnOnce(&mut WritingLog),
+) -> Option<String> {
+    let mut stored = store.0.lock().unwrap();
+    if stored.is_none() {
+        match load(app_handle) {
+            Ok(log) => *stored = Some(log),
+            Err(error) => return Some(error),
+        }
+    }
+    let log = stored.get_or_insert_with(WritingLog::default);
+    f(log);
+    log.version = VERSION;
+    let path = match log_path(app_handle) {
+        Ok(path) => path,
+        Err(error) => return Some(error),
+    };
+    let data = match serde_json::to_string_pretty(log) {
+        Ok(data) => data,
+        Err(error) => return Some(format!("{}: {error}", path.display())),
+    };
+    path.parent()
+        .map_or(Ok(()), create_dir_all)
+        .and_then(|()| write(&path, data))
+        .err()
+        .map(|error| format!("{}: {error}", path.display()))
+}
+
+/// Loads the writing log from `writing-log.json` in the app config directory.
+///
+/// Returns the log, which is empty if there is no log file, and a description of what went wrong
+/// reading it.
+#[command]
+pub fn get_writing_log(
+    app_handle: AppHandle,
+    store: State<WritingLogStore>,
+) -> (WritingLog, Option<String>) {
+    let mut stored = store.0.lock().unwrap();
+    match load(&app_handle) {
+        Ok(log) => {
+            *stored = Some(log.clone());
+            (log, None)
+        }
+        Err(error) => (WritingLog::default(), Some(error)),
+    }
+}
+
+/// Adds some net words written in a file on a day like `2024-01-31` to the writing log.
+///
+/// Returns a description of what went wrong saving the log.
+#[command]
+pub fn log_words(
+    app_handle: AppHandle,
+    store: State<WritingLogStore>,
+    day: String,
+    path: String,
+    words: i64,
+) -> Option<String> {
+    update(&app_handle, &store, |log| log.add_words(day, path, words))
+}
+
+/// Sets the word goal of a file, or removes it if it is [`None`].
+///
+/// Returns a description of what went wrong saving the log.
+#[command]
+pub fn set_document_goal(
+    app_handle: AppHandle,
+    store: State<WritingLogStore>,
+    path: String,
+    goal: Option<u64>,
+) -> Option<String> {
+    update(&app_handle, &store, |log| match goal {
+        Some(goal) => {
+            log.goals.insert(path, goal);
+        }
+        None => {
+            log.goals.remove(&path);
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adding_words_works() {
+        let mut log = WritingLog::default();
+        log.add_words("2024-01-31".to_string(), "/a.md".to_string(), 100);
+        log.add_words("2024-01-31".to_string(), "/a.md".to_string(), -30);
+        log.add_words("2024-01-31".to_string(), String::new(), 5);
+        log.add_words("2024-02-01".to_string(), "/a.md".to_string(), 10);
+        assert_eq!(
+            log.days,
+            BTreeMap::from([
+                (
+                    "2024-01-31".to_string(),
+                    BTreeMap::from([(String::new(), 5), ("/a.md".to_string(), 70)]),
+                ),
+                (
+                    "2024-02-01".to_string(),
+                    BTreeMap::from([("/a.md".to_string(), 10)]),
+                ),
+            ])
+        );
+    }
+}
@@ -0,0 +1,162 @@
+//! Finding what differs between two versions of a text.
+
+use std::ops::Range;
+
+use similar::{ChangeTag, TextDiff};
+
+use crate::history::Edit;
+
+/// A run of a text that differs from an older version of it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// The byte range of the new text that differs, which is empty if the old text had
+    /// something the new one doesn't.
+    pub range: Range<usize>,
+    /// What the old text had instead.
+    pub old: String,
+}
+
+impl Hunk {
+    /// Makes the edit that turns the hunk of the new text back into the old text.
+    pub fn revert(&self, new: &str) -> Edit {
+        Edit {
+            offset: self.range.start,
+            removed: new[self.range.clone()].to_string(),
+            inserted: self.old.clone(),
+        }
+    }
+}
+
+/// Makes the edit that turns some hunks of a new text, in order, back into the old text, leaving
+/// the text between them as it is.
+pub fn revert(hunks: &[Hunk], new: &str) -> Option<Edit> {
+    let (first, last) = (hunks.first()?, hunks.last()?);
+    let mut inserted = String::new();
+    let mut offset = first.range.start;
+    for hunk in hunks {
+        inserted.push_str(&new[offset..hunk.range.start]);
+        inserted.push_str(&hunk.old);
+        offset = hunk.range.end;
+    }
+    Some(Edit {
+        offset: first.range.start,
+        removed: new[first.range.start..last.range.end].to_string(),
+        inserted,
+    })
+}
+
+/// What the text is compared with in the diff view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffBase {
+    /// The file as it was last loaded or saved.
+    Saved,
+    /// The contents of a snapshot.
+    Snapshot(String),
+}
+
+/// What texts are compared by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Granularity {
+    Lines,
+    /// Words and the whitespace between them.
+    Words,
+}
+
+/// Finds the hunks of a new text that differ from an old one, in order.
+pub fn hunks(old: &str, new: &str, granularity: Granularity) -> Vec<Hunk> {
+    let diff = match granularity {
+        Granularity::Lines => TextDiff::from_lines(old, new),
+        Granularity::Words => TextDiff::from_words(old, new),
+    };
+    let mut hunks = Vec::new();
+    let mut hunk = None::<Hunk>;
+    let mut offset = 0;
+    for change in diff.iter_all_changes() {
+        let value = change.value();
+        match change.tag() {
+            ChangeTag::Equal => {
+                hunks.extend(hunk.take());
+                offset += value.len();
+            }
+            ChangeTag::Delete => hunk
+                .get_or_insert_with(|| Hunk {
+                    range: offset..offset,
+                    old: String::new(),
+                })
+                .old
+                .push_str(value),
+            ChangeTag::Insert => {
+                offset += value.len();
+                hunk.get_or_insert_with(|| Hunk {
+                    range: offset - value.len()..offset,
+                    old: String::new(),
+                })
+                .range
+                .end = offset;
+            }
+        }
+    }
+    hunks.extend(hunk);
+    hunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hunk(range: Range<usize>, old: &str) -> Hunk {
+        Hunk {
+            range,
+            old: old.to_string(),
+        }
+    }
+
+    #[test]
+    fn finding_line_hunks_works() {
+        let old = "one\ntwo\nthree\nfour\n";
+        let new = "one\n2\nthree\nfour\nfive\n";
+        assert_eq!(
+            hunks(old, new, Granularity::Lines),
+            [hunk(4..6, "two\n"), hunk(17..22, "")]
+        );
+        assert!(hunks(old, old, Granularity::Lines).is_empty());
+        assert_eq!(
+            hunks(old, "", Granularity::Lines),
+            [hunk(0..0, "one\ntwo\nthree\nfour\n")]
+        );
+    }
+
+    #[test]
+    fn finding_word_hunks_works() {
+        assert_eq!(
+            hunks("the quick fox", "the slow brown fox", Granularity::Words),
+            [hunk(4..14, "quick")]
+        );
+        assert_eq!(
+            hunks("a b c d", "a c d!", Granularity::Words),
+            [hunk(2..2, "b "), hunk(4..6, "d")]
+        );
+    }
+
+    #[test]
+    fn reverting_hunks_works() {
+        let old = "one\ntwo\nthree\n";
+        let new = "one\nTWO\nthree\nfour\n";
+        let mut text = new.to_string();
+        for hunk in hunks(old, new, Granularity::Lines).iter().rev() {
+            hunk.revert(new).apply(&mut text);
+        }
+        assert_eq!(text, old);
+    }
+
+    #[test]
+    fn reverting_several_hunks_works() {
+        let old = "the quick fox jumps";
+        let new = "a quick red fox jumped";
+        let hunks = hunks(old, new, Granularity::Words);
+        let mut text = new.to_string();
+        revert(&hunks, new).unwrap().apply(&mut text);
+        assert_eq!(text, old);
+        assert_eq!(revert(&[], new), None);
+    }
+}
@@ -0,0 +1,62 @@
+use leptos::{component, view, CollectView, IntoView, RwSignal, SignalUpdate};
+use typewriter_core::find::Query;
+use web_sys::KeyboardEvent;
+
+/// A query option that can be toggled, as its label, description, the [`KeyboardEvent::code`]
+/// toggling it alongside Alt, and the field it toggles.
+type Toggle = (
+    &'static str,
+    &'static str,
+    &'static str,
+    fn(&mut Query) -> &mut bool,
+);
+
+const TOGGLES: [Toggle; 3] = [
+    (".*", "Regex (a-R)", "KeyR", |query| &mut query.regex),
+    ("Aa", "Case sensitive (a-C)", "KeyC", |query| {
+        &mut query.case_sensitive
+    }),
+    ("word", "Whole word (a-W)", "KeyW", |query| {
+        &mut query.whole_word
+    }),
+];
+
+/// Toggles a query option if the event is its Alt shortcut.
+pub fn toggle_keydown(query: RwSignal<Query>, event: &KeyboardEvent) {
+    if !event.alt_key() {
+        return;
+    }
+    let Some((.., field)) = TOGGLES.iter().find(|(.., code, _)| event.code() == *code) else {
+        return;
+    };
+    event.prevent_default();
+    query.update(|query| {
+        let enabled = field(query);
+        *enabled = !*enabled;
+    });
+}
+
+#[component]
+pub fn Toggles(query: RwSignal<Query>) -> impl IntoView {
+    TOGGLES
+        .into_iter()
+        .map(|(label, title, _, field)| {
+            view! {
+                <div
+                    class="cursor-pointer"
+                    class=("text-text", move || *field(&mut query()))
+                    title=title
+                    on:click=move |_| {
+                        query
+                            .update(|query| {
+                                let enabled = field(query);
+                                *enabled = !*enabled;
+                            });
+                    }
+                >
+                    {label}
+                </div>
+            }
+        })
+        .collect_view()
+}
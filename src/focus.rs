@@ -0,0 +1,95 @@
+//! Focus mode, dimming everything but the sentence or paragraph being written.
+
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::outline::paragraphs;
+
+/// How much of the text around the caret focus mode keeps undimmed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    #[default]
+    Sentence,
+    Paragraph,
+}
+
+impl Granularity {
+    pub const fn other(self) -> Self {
+        match self {
+            Self::Sentence => Self::Paragraph,
+            Self::Paragraph => Self::Sentence,
+        }
+    }
+}
+
+/// Finds the byte range of the sentence or paragraph containing a byte offset, without any
+/// trailing whitespace.
+pub fn focused_range(text: &str, offset: usize, granularity: Granularity) -> Range<usize> {
+    let paragraph = paragraph(text, offset);
+    let range = match granularity {
+        Granularity::Paragraph => paragraph,
+        Granularity::Sentence => text[paragraph.clone()]
+            .split_sentence_bound_indices()
+            .take_while(|(start, _)| paragraph.start + start <= offset)
+            .last()
+            .map_or(paragraph.start..paragraph.start, |(start, sentence)| {
+                let start = paragraph.start + start;
+                start..start + sentence.len()
+            }),
+    };
+    range.start..range.start + text[range].trim_end().len()
+}
+
+/// Finds the byte range of the paragraph containing a byte offset, like the statistics count
+/// them. A heading or blank line is a paragraph of its own.
+fn paragraph(text: &str, offset: usize) -> Range<usize> {
+    if let Some(paragraph) = paragraphs(text)
+        .into_iter()
+        .find(|paragraph| paragraph.start <= offset && offset <= paragraph.end)
+    {
+        return paragraph;
+    }
+    let start = text[..offset].rfind('\n').map_or(0, |index| index + 1);
+    let end = text[offset..]
+        .find('\n')
+        .map_or(text.len(), |index| offset + index);
+    start..end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "# Title\nOne. Two three?\nFour\n\nFive... six.\n";
+
+    fn focused(offset: usize, granularity: Granularity) -> &'static str {
+        &TEXT[focused_range(TEXT, offset, granularity)]
+    }
+
+    #[test]
+    fn focusing_paragraphs_works() {
+        assert_eq!(focused(2, Granularity::Paragraph), "# Title");
+        assert_eq!(focused(8, Granularity::Paragraph), "One. Two three?\nFour");
+        assert_eq!(focused(28, Granularity::Paragraph), "One. Two three?\nFour");
+        assert_eq!(focused(29, Granularity::Paragraph), "");
+        assert_eq!(focused(30, Granularity::Paragraph), "Five... six.");
+        assert_eq!(focused(TEXT.len(), Granularity::Paragraph), "");
+        assert_eq!(focused_range("", 0, Granularity::Paragraph), 0..0);
+        let text = "One\n#hashtag two\n# Three";
+        assert_eq!(focused_range(text, 0, Granularity::Paragraph), 0..16);
+        assert_eq!(focused_range(text, 19, Granularity::Paragraph), 17..24);
+    }
+
+    #[test]
+    fn focusing_sentences_works() {
+        assert_eq!(focused(8, Granularity::Sentence), "One.");
+        assert_eq!(focused(12, Granularity::Sentence), "One.");
+        assert_eq!(focused(13, Granularity::Sentence), "Two three?");
+        assert_eq!(focused(24, Granularity::Sentence), "Four");
+        assert_eq!(focused(33, Granularity::Sentence), "Five... six.");
+        assert_eq!(focused(29, Granularity::Sentence), "");
+    }
+}
@@ -0,0 +1,268 @@
+//! Folding the sections under headings out of view.
+//!
+//! The text area shows the text with the bodies of folded sections cut out. Offsets into what it
+//! shows are converted to and from offsets into the text with these, so folded text is kept
+//! verbatim.
+
+use std::ops::Range;
+
+use typewriter_core::document::{headings, Heading};
+
+use crate::history::Edit;
+
+/// The headings whose sections are folded, as the byte offsets of the starts of their lines.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Folds(Vec<usize>);
+
+impl Folds {
+    /// Folds the section under the heading at a byte offset if it is unfolded, or unfolds it if
+    /// it is folded. Does nothing if there is nothing under the heading to fold.
+    pub fn toggle(&mut self, text: &str, heading: usize) {
+        if let Some(index) = self.0.iter().position(|&folded| folded == heading) {
+            self.0.remove(index);
+            return;
+        }
+        let headings = headings(text);
+        let Some(index) = headings.iter().position(|other| other.offset == heading) else {
+            return;
+        };
+        if !section_body(text, &headings, index).is_empty() {
+            self.0.push(heading);
+        }
+    }
+
+    /// Folds every section with something under its heading.
+    pub fn fold_all(&mut self, text: &str) {
+        let headings = headings(text);
+        self.0 = (0..headings.len())
+            .filter(|&index| !section_body(text, &headings, index).is_empty())
+            .map(|index| headings[index].offset)
+            .collect();
+    }
+
+    pub fn is_folded(&self, heading: usize) -> bool {
+        self.0.contains(&heading)
+    }
+
+    /// Finds the byte ranges of the text hidden by the folds, in order.
+    pub fn hidden(&self, text: &str) -> Vec<Range<usize>> {
+        let headings = headings(text);
+        let mut hidden: Vec<Range<usize>> = Vec::new();
+        for (index, heading) in headings.iter().enumerate() {
+            if !self.is_folded(heading.offset) {
+                continue;
+            }
+            let body = section_body(text, &headings, index);
+            if body.is_empty() || hidden.last().is_some_and(|last| last.end >= body.end) {
+                continue;
+            }
+            hidden.push(body);
+        }
+        hidden
+    }
+
+    /// Moves the folds along with their headings after an edit has been applied to the text,
+    /// dropping any whose heading was removed.
+    pub fn shift(&mut self, text: &str, edit: &Edit) {
+        let removed = edit.offset..edit.offset + edit.removed.len();
+        self.0.retain_mut(|heading| {
+            if removed.contains(heading) {
+                return false;
+            }
+            if *heading >= edit.offset {
+                *heading = *heading + edit.inserted.len() - edit.removed.len();
+            }
+            true
+        });
+        let headings = headings(text);
+        self.0.retain(|&folded| {
+            headings
+                .binary_search_by_key(&folded, |heading| heading.offset)
+                .is_ok()
+        });
+    }
+
+    /// Unfolds every section hiding part of a byte range, or the offset if the range is empty.
+    pub fn reveal(&mut self, text: &str, range: Range<usize>) {
+        self.unfold_where(text, |body| {
+            if range.is_empty() {
+                (body.start + 1..body.end).contains(&range.start)
+            } else {
+                body.start < range.end && range.start < body.end
+            }
+        });
+    }
+
+    /// Unfolds every section whose hidden body contains or borders a byte offset.
+    pub fn unfold_at(&mut self, text: &str, offset: usize) {
+        self.unfold_where(text, |body| body.start <= offset && offset <= body.end);
+    }
+
+    fn unfold_where(&mut self, text: &str, unfold: impl Fn(&Range<usize>) -> bool) {
+        let headings = headings(text);
+        self.0.retain(|&folded| {
+            headings
+                .iter()
+                .position(|heading| heading.offset == folded)
+                .is_none_or(|index| !unfold(&section_body(text, &headings, index)))
+        });
+    }
+}
+
+/// Finds the byte range of what folding the section under a heading hides: everything from the
+/// end of the heading's line up to the line before the next heading that is at least as deep.
+pub fn section_body(text: &str, headings: &[Heading], index: usize) -> Range<usize> {
+    let heading = &headings[index];
+    let Some(line_end) = text[heading.offset..]
+        .find('\n')
+        .map(|end| heading.offset + end)
+    else {
+        return heading.offset..heading.offset;
+    };
+    let end = headings[index + 1..]
+        .iter()
+        .find(|next| next.depth <= heading.depth)
+        .map_or(text.len(), |next| next.offset - 1);
+    line_end..end.max(line_end)
+}
+
+/// Cuts the hidden ranges out of some text.
+pub fn project(text: &str, hidden: &[Range<usize>]) -> String {
+    let mut shown = String::with_capacity(text.len());
+    let mut start = 0;
+    for range in hidden {
+        shown.push_str(&text[start..range.start]);
+        start = range.end;
+    }
+    shown.push_str(&text[start..]);
+    shown
+}
+
+/// Converts a byte offset of the text into a byte offset of what is shown, moving offsets inside
+/// hidden ranges to where they were cut out.
+pub fn to_shown(hidden: &[Range<usize>], offset: usize) -> usize {
+    let mut shown = offset;
+    for range in hidden {
+        if range.start >= offset {
+            break;
+        }
+        shown -= range.end.min(offset) - range.start;
+    }
+    shown
+}
+
+/// Converts a byte offset of what is shown into a byte offset of the text. An offset where a
+/// range was cut out is placed after it if `after` is set, and before it otherwise.
+pub fn to_text(hidden: &[Range<usize>], shown: usize, after: bool) -> usize {
+    let mut offset = shown;
+    for range in hidden {
+        if range.start > offset || (range.start == offset && !after) {
+            break;
+        }
+        offset += range.len();
+    }
+    offset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "# A\nbody\n## A1\nmore\n# B\nlast\n";
+
+    fn folded(headings: &[usize]) -> Folds {
+        Folds(headings.to_vec())
+    }
+
+    #[test]
+    fn finding_section_bodies_works() {
+        let headings = headings(TEXT);
+        let bodies = (0..headings.len())
+            .map(|index| &TEXT[section_body(TEXT, &headings, index)])
+            .collect::<Vec<_>>();
+        assert_eq!(bodies, ["\nbody\n## A1\nmore", "\nmore", "\nlast\n"]);
+        let text = "# A\n# B";
+        let headings = super::headings(text);
+        assert!(section_body(text, &headings, 0).is_empty());
+        assert!(section_body(text, &headings, 1).is_empty());
+    }
+
+    #[test]
+    fn projecting_works() {
+        let hidden = folded(&[0, 20]).hidden(TEXT);
+        assert_eq!(project(TEXT, &hidden), "# A\n# B");
+        let hidden = folded(&[9, 20]).hidden(TEXT);
+        assert_eq!(project(TEXT, &hidden), "# A\nbody\n## A1\n# B");
+        // A folded section inside a folded section is hidden along with it.
+        let hidden = folded(&[9, 0]).hidden(TEXT);
+        assert_eq!(hidden.len(), 1);
+        assert_eq!(hidden[0], 3..19);
+        assert_eq!(project(TEXT, &folded(&[]).hidden(TEXT)), TEXT);
+    }
+
+    #[test]
+    fn converting_offsets_works() {
+        let hidden = folded(&[0, 20]).hidden(TEXT);
+        assert_eq!(to_shown(&hidden, 0), 0);
+        assert_eq!(to_shown(&hidden, 3), 3);
+        assert_eq!(to_shown(&hidden, 10), 3);
+        assert_eq!(to_shown(&hidden, 20), 4);
+        assert_eq!(to_shown(&hidden, TEXT.len()), 7);
+        assert_eq!(to_text(&hidden, 2, false), 2);
+        assert_eq!(to_text(&hidden, 3, false), 3);
+        assert_eq!(to_text(&hidden, 3, true), 19);
+        assert_eq!(to_text(&hidden, 4, false), 20);
+        assert_eq!(to_text(&hidden, 7, false), 23);
+        assert_eq!(to_text(&hidden, 7, true), TEXT.len());
+    }
+
+    #[test]
+    fn toggling_works() {
+        let mut folds = Folds::default();
+        folds.toggle(TEXT, 0);
+        folds.toggle(TEXT, 4);
+        assert_eq!(folds, folded(&[0]));
+        folds.toggle(TEXT, 0);
+        assert_eq!(folds, folded(&[]));
+        folds.toggle("# A\n# B", 0);
+        assert_eq!(folds, folded(&[]));
+        folds.fold_all(TEXT);
+        assert_eq!(folds, folded(&[0, 9, 20]));
+    }
+
+    #[test]
+    fn shifting_works() {
+        let mut folds = folded(&[0, 9, 20]);
+        let mut text = TEXT.to_string();
+        let edit = Edit::diff(&text, "intro\n# A\nbody\n## A1\nmore\n# B\nlast\n").unwrap();
+        edit.apply(&mut text);
+        folds.shift(&text, &edit);
+        assert_eq!(folds, folded(&[6, 15, 26]));
+        let edit = Edit::diff(&text, "intro\n# A\nbody\nmore\n# B\nlast\n").unwrap();
+        edit.apply(&mut text);
+        folds.shift(&text, &edit);
+        assert_eq!(folds, folded(&[6, 20]));
+        let edit = Edit::diff(&text, "intro\n# A\nbody\nmore\n# B!\nlast\n").unwrap();
+        edit.apply(&mut text);
+        folds.shift(&text, &edit);
+        assert_eq!(folds, folded(&[6, 20]));
+        let edit = Edit::diff(&text, "intro\n# A\nbody\nmore\nB!\nlast\n").unwrap();
+        edit.apply(&mut text);
+        folds.shift(&text, &edit);
+        assert_eq!(folds, folded(&[6]));
+    }
+
+    #[test]
+    fn revealing_works() {
+        let mut folds = folded(&[0, 20]);
+        folds.reveal(TEXT, 3..3);
+        assert_eq!(folds, folded(&[0, 20]));
+        folds.reveal(TEXT, 5..5);
+        assert_eq!(folds, folded(&[20]));
+        folds.reveal(TEXT, 0..TEXT.len());
+        assert_eq!(folds, folded(&[]));
+        let mut folds = folded(&[0, 20]);
+        folds.unfold_at(TEXT, 19);
+        assert_eq!(folds, folded(&[20]));
+    }
+}
@@ -0,0 +1,355 @@
+//! Showing the state of the git repository the document is in, the lines changed since the
+//! commit checked out, and committing the document.
+
+use std::path::PathBuf;
+
+use leptos::ev::keydown;
+use leptos::html::Input;
+use leptos::logging::warn;
+use leptos::{
+    component, create_effect, create_node_ref, create_rw_signal, event_target_value, spawn_local,
+    use_context, view, window_event_listener, CollectView, IntoView, NodeRef, RwSignal,
+    SignalGetUntracked, SignalSet, SignalWith,
+};
+use serde::Deserialize;
+
+use crate::diff::{hunks, Granularity, Hunk};
+use crate::problems::report;
+use crate::snapshots::time_label;
+use crate::{Context, Horizontal, Inter, Popup, Vertical};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileStatus {
+    Unmodified,
+    Modified,
+    /// Added to the index but not committed yet.
+    Added,
+    Untracked,
+    Ignored,
+}
+
+impl FileStatus {
+    /// Gets the letter the status is shown as, like `git status --short` does.
+    pub const fn letter(self) -> &'static str {
+        match self {
+            Self::Unmodified => "",
+            Self::Modified => "M",
+            Self::Added => "A",
+            Self::Untracked => "?",
+            Self::Ignored => "!",
+        }
+    }
+}
+
+/// The state of the repository the document is in.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct GitStatus {
+    /// The name of the checked out branch, or the short id of the commit if none is.
+    pub branch: String,
+    pub status: FileStatus,
+    /// The contents of the document in the commit checked out, if it's in it.
+    pub head: Option<String>,
+}
+
+impl GitStatus {
+    /// Finds the hunks of lines of the text changed since the commit checked out. Everything is
+    /// new if the document isn't in it.
+    pub fn changes(&self, text: &str) -> Vec<Hunk> {
+        if self.status == FileStatus::Ignored {
+            return Vec::new();
+        }
+        hunks(
+            self.head.as_deref().unwrap_or_default(),
+            text,
+            Granularity::Lines,
+        )
+    }
+}
+
+/// A commit that changed the document.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct GitCommit {
+    /// The short id of the commit.
+    pub id: String,
+    pub summary: String,
+    pub author: String,
+    /// When the commit was made, in seconds since the epoch.
+    pub time: i64,
+}
+
+/// How lines changed since the commit checked out, which their gutter marker shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineChange {
+    Added,
+    Modified,
+    /// Lines were removed before this one.
+    Removed,
+}
+
+impl LineChange {
+    pub fn of(hunk: &Hunk) -> Self {
+        if hunk.range.is_empty() {
+            Self::Removed
+        } else if hunk.old.is_empty() {
+            Self::Added
+        } else {
+            Self::Modified
+        }
+    }
+
+    pub const fn class(self) -> &'static str {
+        match self {
+            Self::Added => "w-0.5 h-full bg-accent",
+            Self::Modified => "w-0.5 h-full bg-fade",
+            Self::Removed => "w-1.5 border-t-2 border-error",
+        }
+    }
+
+    pub const fn title(self) -> &'static str {
+        match self {
+            Self::Added => "Added since the last commit",
+            Self::Modified => "Changed since the last commit",
+            Self::Removed => "Lines removed above since the last commit",
+        }
+    }
+}
+
+/// Reloads the state of the repository the document is in, reporting what went wrong reading it.
+fn refresh(
+    git: RwSignal<Option<GitStatus>>,
+    problems: RwSignal<Vec<String>>,
+    path: Option<PathBuf>,
+) {
+    let Some(path) = path else {
+        git.set(None);
+        return;
+    };
+    spawn_local(async move {
+        let (status, error) = Inter::git_status(path).await;
+        if let Some(error) = error {
+            report(problems, "git", &error);
+        }
+        git.set(status);
+    });
+}
+
+/// Keeps the state of the repository up to date as the document is opened and saved.
+pub fn track_git(context: &Context) {
+    let Context {
+        git,
+        save_path: (read_save_path, _),
+        unsaved,
+        problems,
+        ..
+    } = *context;
+    create_effect(move |_| {
+        // Saving makes the document no longer unsaved, which can change its status.
+        unsaved.track();
+        refresh(git, problems, read_save_path());
+    });
+}
+
+/// Shows the branch and the status of the document, if it's in a repository.
+#[component]
+pub fn GitIndicator(show_log: RwSignal<bool>) -> impl IntoView {
+    let Context { git, .. } = use_context::<Context>().unwrap();
+
+    move || {
+        git.with(|git| {
+            let git = git.as_ref()?;
+            let label = format!("\u{2387} {} {}", git.branch, git.status.letter());
+            Some(view! {
+                <div class="cursor-pointer" title="Git log" on:click=move |_| show_log.set(true)>
+                    {label.trim_end().to_string()}
+                </div>
+            })
+        })
+    }
+}
+
+#[component]
+pub fn GitCommitPopup(show: RwSignal<bool>) -> impl IntoView {
+    let Context {
+        text_area,
+        save_path: (read_save_path, _),
+        unsaved,
+        git,
+        problems,
+        ..
+    } = use_context::<Context>().unwrap();
+    let message = create_rw_signal(String::new());
+    let error = create_rw_signal(None::<String>);
+    let input: NodeRef<Input> = create_node_ref();
+
+    create_effect(move |_| {
+        if show() {
+            error.set(None);
+            input.get_untracked().unwrap().focus().unwrap();
+        }
+    });
+
+    let close = move || {
+        show.set(false);
+        text_area.get_untracked().unwrap().focus().unwrap();
+    };
+    let commit = move || {
+        let Some(path) = read_save_path.get_untracked() else {
+            return;
+        };
+        spawn_local(async move {
+            if let Some(problem) = Inter::git_commit(path.clone(), message.get_untracked()).await {
+                warn!("git: {problem}");
+                error.set(Some(problem));
+                return;
+            }
+            message.set(String::new());
+            refresh(git, problems, Some(path));
+            close();
+        });
+    };
+
+    window_event_listener(keydown, move |event| {
+        if event.key() == "Escape" && show.get_untracked() {
+            close();
+        }
+    });
+
+    view! {
+        <Popup show=show>
+            <Vertical gap=2>
+                <Horizontal gap=1>
+                    <div class="text-text">"message:"</div>
+                    <input
+                        type="text"
+                        class="outline-none grow select-text text-text bg-highlight cursor-text selection:bg-fade"
+                        ref=input
+                        prop:value=message
+                        on:input=move |event| message.set(event_target_value(&event))
+                        on:keydown=move |event| {
+                            if event.key() == "Enter" {
+                                commit();
+                            }
+                        }
+                    />
+                </Horizontal>
+                {move || {
+                    let note = if read_save_path().is_none() {
+                        Some("the document hasn't been saved to a file".to_string())
+                    } else if git.with(Option::is_none) {
+                        Some("the document isn't in a git repository".to_string())
+                    } else if unsaved() {
+                        Some("unsaved changes aren't committed".to_string())
+                    } else {
+                        error()
+                    };
+                    note.map(|note| view! { <div class="px-1">{note}</div> })
+                }}
+            </Vertical>
+        </Popup>
+    }
+}
+
+#[component]
+pub fn GitLog(show: RwSignal<bool>) -> impl IntoView {
+    let Context {
+        text_area,
+        save_path: (read_save_path, _),
+        problems,
+        ..
+    } = use_context::<Context>().unwrap();
+    let commits = create_rw_signal(Vec::<GitCommit>::new());
+
+    create_effect(move |_| {
+        if !show() {
+            return;
+        }
+        commits.set(Vec::new());
+        let Some(path) = read_save_path.get_untracked() else {
+            return;
+        };
+        spawn_local(async move {
+            let (listed, error) = Inter::git_log(path).await;
+            if let Some(error) = error {
+                report(problems, "git", &error);
+            }
+            commits.set(listed);
+        });
+    });
+
+    window_event_listener(keydown, move |event| {
+        if event.key() == "Escape" && show.get_untracked() {
+            show.set(false);
+            text_area.get_untracked().unwrap().focus().unwrap();
+        }
+    });
+
+    view! {
+        <Popup show=show>
+            <Vertical class="overflow-y-auto max-h-80">
+                {move || {
+                    if commits.with(Vec::is_empty) {
+                        return view! { <div class="px-1">"no commits"</div> }.into_view();
+                    }
+                    commits
+                        .with(|commits| {
+                            commits
+                                .iter()
+                                .map(|commit| {
+                                    let time = u64::try_from(commit.time).unwrap_or_default() * 1000;
+                                    let details = format!("{} {}", commit.author, time_label(time));
+                                    let (id, summary) = (commit.id.clone(), commit.summary.clone());
+                                    view! {
+                                        <Horizontal gap=2 class="justify-between px-1">
+                                            <Horizontal gap=2>
+                                                <div>{id}</div>
+                                                <div class="text-text">{summary}</div>
+                                            </Horizontal>
+                                            <div class="shrink-0">{details}</div>
+                                        </Horizontal>
+                                    }
+                                })
+                                .collect_view()
+                        })
+                }}
+            </Vertical>
+        </Popup>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finding_line_changes_works() {
+        let status = GitStatus {
+            branch: "main".to_string(),
+            status: FileStatus::Modified,
+            head: Some("one\ntwo\nthree\nfour\n".to_string()),
+        };
+        let changes = status.changes("one\n2\nthree\nfive\n");
+        assert_eq!(
+            changes.iter().map(LineChange::of).collect::<Vec<_>>(),
+            [LineChange::Modified, LineChange::Modified]
+        );
+        let changes = status.changes("zero\none\ntwo\nfour\n");
+        assert_eq!(
+            changes.iter().map(LineChange::of).collect::<Vec<_>>(),
+            [LineChange::Added, LineChange::Removed]
+        );
+        let untracked = GitStatus {
+            status: FileStatus::Untracked,
+            head: None,
+            ..status
+        };
+        assert_eq!(
+            untracked
+                .changes("new\n")
+                .iter()
+                .map(LineChange::of)
+                .collect::<Vec<_>>(),
+            [LineChange::Added]
+        );
+    }
+}
@@ -0,0 +1,295 @@
+use std::mem::take;
+use std::ops::Range;
+
+/// How long, in milliseconds, a pause in typing can be before the next keystroke starts a new
+/// undo step.
+const GROUP_TIMEOUT: f64 = 1000.;
+
+/// A replacement of the text at a byte offset.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Edit {
+    pub offset: usize,
+    pub removed: String,
+    pub inserted: String,
+}
+
+impl Edit {
+    /// Finds the smallest single edit turning `old` into `new`, or [`None`] if they are equal.
+    pub fn diff(old: &str, new: &str) -> Option<Self> {
+        if old == new {
+            return None;
+        }
+        let prefix = old
+            .char_indices()
+            .zip(new.chars())
+            .find(|((_, old), new)| old != new)
+            .map_or(old.len().min(new.len()), |((offset, _), _)| offset);
+        let suffix: usize = old[prefix..]
+            .chars()
+            .rev()
+            .zip(new[prefix..].chars().rev())
+            .take_while(|(old, new)| old == new)
+            .map(|(char, _)| char.len_utf8())
+            .sum();
+        Some(Self {
+            offset: prefix,
+            removed: old[prefix..old.len() - suffix].to_string(),
+            inserted: new[prefix..new.len() - suffix].to_string(),
+        })
+    }
+
+    pub fn apply(&self, text: &mut String) {
+        text.replace_range(
+            self.offset..self.offset + self.removed.len(),
+            &self.inserted,
+        );
+    }
+
+    pub fn invert(&self) -> Self {
+        Self {
+            offset: self.offset,
+            removed: self.inserted.clone(),
+            inserted: self.removed.clone(),
+        }
+    }
+
+    /// Extends this edit with one made straight after it, if the two are contiguous.
+    fn merge(&mut self, next: &Self) -> bool {
+        if next.removed.is_empty() && next.offset == self.offset + self.inserted.len() {
+            // Typing forwards.
+            self.inserted.push_str(&next.inserted);
+        } else if !self.inserted.is_empty() || !next.inserted.is_empty() {
+            return false;
+        } else if next.offset + next.removed.len() == self.offset {
+            // Deleting backwards.
+            self.offset = next.offset;
+            self.removed.insert_str(0, &next.removed);
+        } else if next.offset == self.offset {
+            // Deleting forwards.
+            self.removed.push_str(&next.removed);
+        } else {
+            return false;
+        }
+        true
+    }
+}
+
+/// What caused an edit, deciding which neighbouring edits it is grouped into one undo step with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Typing,
+    Deleting,
+    Other,
+}
+
+#[derive(Debug, Clone)]
+struct Transaction {
+    edit: Edit,
+    before: Range<usize>,
+    after: Range<usize>,
+    kind: Kind,
+    time: f64,
+}
+
+/// The undo and redo stacks of the editor. Selections are byte ranges of the text.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    undo: Vec<Transaction>,
+    redo: Vec<Transaction>,
+    sealed: bool,
+}
+
+impl History {
+    /// Records an edit that has been applied to the text, made at `time` milliseconds.
+    ///
+    /// Consecutive typing or deleting within [`GROUP_TIMEOUT`] of each other is grouped into a
+    /// single undo step, unless the history was [sealed](History::seal) in between.
+    pub fn record(
+        &mut self,
+        edit: Edit,
+        before: Range<usize>,
+        after: Range<usize>,
+        kind: Kind,
+        time: f64,
+    ) {
+        self.redo.clear();
+        let sealed = take(&mut self.sealed);
+        if let Some(last) = self.undo.last_mut() {
+            if !sealed
+                && kind != Kind::Other
+                && last.kind == kind
+                && time - last.time < GROUP_TIMEOUT
+                && last.edit.merge(&edit)
+            {
+                last.after = after;
+                last.time = time;
+                return;
+            }
+        }
+        self.undo.push(Transaction {
+            edit,
+            before,
+            after,
+            kind,
+            time,
+        });
+    }
+
+    /// Stops the next edit from being grouped with the previous one.
+    pub const fn seal(&mut self) {
+        self.sealed = true;
+    }
+
+    /// Reverts the last undo step, returning the selection from before it.
+    pub fn undo(&mut self, text: &mut String) -> Option<Range<usize>> {
+        let transaction = self.undo.pop()?;
+        transaction.edit.invert().apply(text);
+        let selection = transaction.before.clone();
+        self.redo.push(transaction);
+        self.seal();
+        Some(selection)
+    }
+
+    /// Reapplies the last undone step, returning the selection from after it.
+    pub fn redo(&mut self, text: &mut String) -> Option<Range<usize>> {
+        let transaction = self.redo.pop()?;
+        transaction.edit.apply(text);
+        let selection = transaction.after.clone();
+        self.undo.push(transaction);
+        self.seal();
+        Some(selection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(offset: usize, removed: &str, inserted: &str) -> Edit {
+        Edit {
+            offset,
+            removed: removed.to_string(),
+            inserted: inserted.to_string(),
+        }
+    }
+
+    /// Applies a sequence of new texts to a history as if they were typed at 100ms intervals.
+    fn record(history: &mut History, text: &mut String, kind: Kind, steps: &[&str]) {
+        for (index, step) in steps.iter().enumerate() {
+            let edit = Edit::diff(text, step).unwrap();
+            let after = edit.offset + edit.inserted.len();
+            history.record(
+                edit,
+                0..0,
+                after..after,
+                kind,
+                f64::from(u32::try_from(index).unwrap()) * 100.,
+            );
+            *text = (*step).to_string();
+        }
+    }
+
+    #[test]
+    fn diffing_works() {
+        assert_eq!(Edit::diff("same", "same"), None);
+        assert_eq!(Edit::diff("", "new"), Some(edit(0, "", "new")));
+        assert_eq!(Edit::diff("hello", "help"), Some(edit(3, "lo", "p")));
+        assert_eq!(Edit::diff("aaa", "aaaa"), Some(edit(3, "", "a")));
+        assert_eq!(Edit::diff("a b c", "a c"), Some(edit(2, "b ", "")));
+        assert_eq!(Edit::diff("café", "cafe"), Some(edit(3, "é", "e")));
+        assert_eq!(Edit::diff("a😀b", "a😃b"), Some(edit(1, "😀", "😃")));
+        assert_eq!(Edit::diff("éé", "é"), Some(edit(2, "é", "")));
+    }
+
+    #[test]
+    fn applying_and_inverting_works() {
+        let pairs = [
+            ("", "new"),
+            ("hello", "help"),
+            ("ünïcödé", "unicode"),
+            ("a😀b", "a😃b"),
+        ];
+        for (old, new) in pairs {
+            let edit = Edit::diff(old, new).unwrap();
+            let mut text = old.to_string();
+            edit.apply(&mut text);
+            assert_eq!(text, new);
+            edit.invert().apply(&mut text);
+            assert_eq!(text, old);
+        }
+    }
+
+    #[test]
+    fn typing_is_grouped() {
+        let mut history = History::default();
+        let mut text = String::new();
+        record(
+            &mut history,
+            &mut text,
+            Kind::Typing,
+            &["h", "hé", "hél", "héll", "héllo"],
+        );
+        assert_eq!(history.undo(&mut text), Some(0..0));
+        assert_eq!(text, "");
+        assert_eq!(history.undo(&mut text), None);
+        assert_eq!(history.redo(&mut text), Some(6..6));
+        assert_eq!(text, "héllo");
+    }
+
+    #[test]
+    fn deleting_is_grouped() {
+        let mut history = History::default();
+        let mut text = "one two".to_string();
+        record(
+            &mut history,
+            &mut text,
+            Kind::Deleting,
+            &["one tw", "one t", "one "],
+        );
+        record(&mut history, &mut text, Kind::Deleting, &["ne ", "e "]);
+        assert_eq!(history.undo(&mut text), Some(0..0));
+        assert_eq!(text, "one ");
+        assert_eq!(history.undo(&mut text), Some(0..0));
+        assert_eq!(text, "one two");
+    }
+
+    #[test]
+    fn edits_are_split_into_steps() {
+        let mut history = History::default();
+        let mut text = String::new();
+        record(&mut history, &mut text, Kind::Typing, &["a", "ab"]);
+        record(&mut history, &mut text, Kind::Deleting, &["a"]);
+        record(&mut history, &mut text, Kind::Other, &["a\t"]);
+        record(&mut history, &mut text, Kind::Typing, &["a\tc"]);
+        history.seal();
+        record(&mut history, &mut text, Kind::Typing, &["a\tcd"]);
+        record(&mut history, &mut text, Kind::Typing, &["x\tcd"]);
+        for expected in ["a\tcd", "a\tc", "a\t", "a", "ab", ""] {
+            history.undo(&mut text);
+            assert_eq!(text, expected);
+        }
+    }
+
+    #[test]
+    fn pauses_split_typing() {
+        let mut history = History::default();
+        let mut text = String::new();
+        history.record(edit(0, "", "a"), 0..0, 1..1, Kind::Typing, 0.);
+        history.record(edit(1, "", "b"), 1..1, 2..2, Kind::Typing, 500.);
+        history.record(edit(2, "", "c"), 2..2, 3..3, Kind::Typing, 2000.);
+        text.push_str("abc");
+        assert_eq!(history.undo(&mut text), Some(2..2));
+        assert_eq!(text, "ab");
+    }
+
+    #[test]
+    fn new_edits_clear_redo() {
+        let mut history = History::default();
+        let mut text = String::new();
+        record(&mut history, &mut text, Kind::Other, &["a", "ab"]);
+        history.undo(&mut text);
+        record(&mut history, &mut text, Kind::Other, &["ac"]);
+        assert_eq!(history.redo(&mut text), None);
+        assert_eq!(text, "ac");
+    }
+}
@@ -0,0 +1,386 @@
+//! Keyboard shortcuts, written like `c-sh-S` or as chords like `c-K c-S`.
+//!
+//! `c-` is the command modifier (Cmd on macOS and Ctrl everywhere else), `a-` is Alt and `sh-`
+//! is Shift. Letters and digits match the physical key, so Alt combos work on macOS too.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::mem::take;
+use std::str::FromStr;
+
+use leptos::window;
+use web_sys::KeyboardEvent;
+
+/// Whether the command modifier is Cmd rather than Ctrl.
+pub fn is_mac() -> bool {
+    window()
+        .navigator()
+        .platform()
+        .is_ok_and(|platform| platform.starts_with("Mac"))
+}
+
+/// Whether the command modifier is held during an event.
+pub fn command_held(event: &KeyboardEvent) -> bool {
+    if is_mac() {
+        event.meta_key()
+    } else {
+        event.ctrl_key()
+    }
+}
+
+/// A single key pressed alongside some modifiers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keystroke {
+    command: bool,
+    alt: bool,
+    shift: bool,
+    /// An uppercase letter, a digit, another single character or a [`KeyboardEvent::key`] name.
+    key: String,
+}
+
+impl Keystroke {
+    /// Gets the keystroke an event is for, or [`None`] if only a modifier was pressed.
+    pub fn from_event(event: &KeyboardEvent) -> Option<Self> {
+        let key = event.key();
+        if matches!(
+            key.as_str(),
+            "Control" | "Meta" | "Alt" | "Shift" | "CapsLock" | "OS" | "Dead"
+        ) {
+            return None;
+        }
+        let code = event.code();
+        let key = code
+            .strip_prefix("Key")
+            .or_else(|| code.strip_prefix("Digit"))
+            .map_or(key, str::to_string);
+        Some(Self {
+            command: command_held(event),
+            alt: event.alt_key(),
+            shift: event.shift_key(),
+            key: normalize(&key),
+        })
+    }
+}
+
+/// Uppercases keys that are single characters, so `c-s` and `c-S` are the same keystroke.
+fn normalize(key: &str) -> String {
+    if key.chars().count() == 1 {
+        key.to_uppercase()
+    } else {
+        key.to_string()
+    }
+}
+
+impl FromStr for Keystroke {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let mut keystroke = Self {
+            command: false,
+            alt: false,
+            shift: false,
+            key: String::new(),
+        };
+        let mut rest = string;
+        loop {
+            let (modifier, remaining) = if let Some(remaining) = rest.strip_prefix("c-") {
+                (&mut keystroke.command, remaining)
+            } else if let Some(remaining) = rest.strip_prefix("a-") {
+                (&mut keystroke.alt, remaining)
+            } else if let Some(remaining) = rest.strip_prefix("sh-") {
+                (&mut keystroke.shift, remaining)
+            } else {
+                break;
+            };
+            if *modifier {
+                return Err(format!("`{string}` repeats a modifier"));
+            }
+            *modifier = true;
+            rest = remaining;
+        }
+        if rest.is_empty() {
+            return Err(format!("`{string}` has no key"));
+        }
+        keystroke.key = normalize(rest);
+        Ok(keystroke)
+    }
+}
+
+impl Display for Keystroke {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        for (held, prefix) in [(self.command, "c-"), (self.alt, "a-"), (self.shift, "sh-")] {
+            if held {
+                formatter.write_str(prefix)?;
+            }
+        }
+        formatter.write_str(&self.key)
+    }
+}
+
+/// A sequence of keystrokes triggering an action.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Binding(Vec<Keystroke>);
+
+impl Binding {
+    fn starts_with(&self, keystrokes: &[Keystroke]) -> bool {
+        self.0.starts_with(keystrokes)
+    }
+}
+
+impl FromStr for Binding {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let keystrokes = string
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        if keystrokes.is_empty() {
+            return Err("a binding needs at least one keystroke".to_string());
+        }
+        Ok(Self(keystrokes))
+    }
+}
+
+impl Display for Binding {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        for (index, keystroke) in self.0.iter().enumerate() {
+            if index > 0 {
+                formatter.write_str(" ")?;
+            }
+            keystroke.fmt(formatter)?;
+        }
+        Ok(())
+    }
+}
+
+/// What pressing a keystroke did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Press {
+    /// A binding was completed, triggering the action with this id.
+    Action(&'static str),
+    /// The keystrokes so far are the start of a chord.
+    Pending,
+    /// The keystroke isn't bound to anything.
+    Unbound,
+}
+
+/// The bindings of every action, and the keystrokes of a chord in progress.
+#[derive(Debug, Clone, Default)]
+pub struct Keymap {
+    defaults: Vec<(&'static str, Option<Binding>)>,
+    bindings: Vec<(&'static str, Option<Binding>)>,
+    pending: Vec<Keystroke>,
+}
+
+impl Keymap {
+    /// Creates a keymap from the default binding of each action id, where an empty binding
+    /// leaves the action unbound.
+    ///
+    /// Panics if a default binding is invalid.
+    pub fn new(defaults: impl IntoIterator<Item = (&'static str, &'static str)>) -> Self {
+        let defaults: Vec<_> = defaults
+            .into_iter()
+            .map(|(id, binding)| (id, (!binding.is_empty()).then(|| binding.parse().unwrap())))
+            .collect();
+        Self {
+            bindings: defaults.clone(),
+            defaults,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Rebinds actions from their defaults with a map of action ids to bindings, where an empty
+    /// binding unbinds the action. Anything else bound to the same keys is unbound.
+    ///
+    /// Returns a description of every override that was skipped for being invalid.
+    pub fn set_overrides(&mut self, overrides: &BTreeMap<String, String>) -> Vec<String> {
+        self.bindings.clone_from(&self.defaults);
+        let mut problems = Vec::new();
+        for (id, binding) in overrides {
+            let Some(index) = self.bindings.iter().position(|(action, _)| action == id) else {
+                problems.push(format!("unknown action `{id}`"));
+                continue;
+            };
+            let binding = if binding.trim().is_empty() {
+                None
+            } else {
+                match binding.parse::<Binding>() {
+                    Ok(binding) => Some(binding),
+                    Err(problem) => {
+                        problems.push(format!("`{id}`: {problem}"));
+                        continue;
+                    }
+                }
+            };
+            for (_, other) in &mut self.bindings {
+                if other.is_some() && *other == binding {
+                    *other = None;
+                }
+            }
+            self.bindings[index].1 = binding;
+        }
+        problems
+    }
+
+    pub fn binding(&self, id: &str) -> Option<&Binding> {
+        self.bindings
+            .iter()
+            .find(|(action, _)| *action == id)
+            .and_then(|(_, binding)| binding.as_ref())
+    }
+
+    /// The keystrokes of the chord in progress, if any.
+    pub fn pending(&self) -> &[Keystroke] {
+        &self.pending
+    }
+
+    /// Whether the action's binding starts with the chord in progress.
+    pub fn continues_pending(&self, id: &str) -> bool {
+        self.binding(id)
+            .is_some_and(|binding| binding.starts_with(&self.pending))
+    }
+
+    /// Presses a keystroke, continuing the chord in progress if it can.
+    pub fn press(&mut self, keystroke: Keystroke) -> Press {
+        let mut chord = take(&mut self.pending);
+        let restart = !chord.is_empty();
+        chord.push(keystroke.clone());
+        let attempts = if restart {
+            vec![chord, vec![keystroke]]
+        } else {
+            vec![chord]
+        };
+        for keystrokes in attempts {
+            let mut pending = false;
+            for (id, binding) in &self.bindings {
+                let Some(binding) = binding else {
+                    continue;
+                };
+                if binding.0 == keystrokes {
+                    return Press::Action(id);
+                }
+                pending |= binding.starts_with(&keystrokes);
+            }
+            if pending {
+                self.pending = keystrokes;
+                return Press::Pending;
+            }
+        }
+        Press::Unbound
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keymap() -> Keymap {
+        Keymap::new([
+            ("save", "c-S"),
+            ("save_as", "c-sh-S"),
+            ("toggle", "a-T"),
+            ("settings", "c-K c-S"),
+            ("next", "c-K N"),
+            ("palette_only", ""),
+        ])
+    }
+
+    fn press(keymap: &mut Keymap, keystrokes: &str) -> Vec<Press> {
+        keystrokes
+            .split_whitespace()
+            .map(|keystroke| keymap.press(keystroke.parse().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn parsing_keystrokes_works() {
+        let keystroke: Keystroke = "c-a-sh-x".parse().unwrap();
+        assert!(keystroke.command && keystroke.alt && keystroke.shift);
+        assert_eq!(keystroke.key, "X");
+        assert_eq!(keystroke.to_string(), "c-a-sh-X");
+        assert_eq!(
+            "sh-c-Enter".parse::<Keystroke>().unwrap().to_string(),
+            "c-sh-Enter"
+        );
+        assert_eq!("c--".parse::<Keystroke>().unwrap().to_string(), "c--");
+        assert_eq!("F5".parse::<Keystroke>().unwrap().to_string(), "F5");
+        assert!("c-".parse::<Keystroke>().is_err());
+        assert!("c-c-x".parse::<Keystroke>().is_err());
+        assert!("".parse::<Keystroke>().is_err());
+    }
+
+    #[test]
+    fn parsing_bindings_works() {
+        let binding: Binding = "c-k  c-s".parse().unwrap();
+        assert_eq!(binding.to_string(), "c-K c-S");
+        assert!("".parse::<Binding>().is_err());
+        assert!("c-K c-c-S".parse::<Binding>().is_err());
+    }
+
+    #[test]
+    fn pressing_works() {
+        let mut keymap = keymap();
+        assert_eq!(
+            press(&mut keymap, "c-S c-sh-S a-T S"),
+            [
+                Press::Action("save"),
+                Press::Action("save_as"),
+                Press::Action("toggle"),
+                Press::Unbound
+            ]
+        );
+    }
+
+    #[test]
+    fn chords_work() {
+        let mut keymap = keymap();
+        assert_eq!(
+            press(&mut keymap, "c-K c-S"),
+            [Press::Pending, Press::Action("settings")]
+        );
+        assert_eq!(
+            press(&mut keymap, "c-K N"),
+            [Press::Pending, Press::Action("next")]
+        );
+        assert!(keymap.pending().is_empty());
+        assert_eq!(press(&mut keymap, "c-K"), [Press::Pending]);
+        assert!(keymap.continues_pending("next"));
+        assert!(!keymap.continues_pending("save"));
+        // A keystroke that doesn't continue the chord starts over.
+        assert_eq!(press(&mut keymap, "c-sh-S"), [Press::Action("save_as")]);
+        assert_eq!(
+            press(&mut keymap, "c-K X"),
+            [Press::Pending, Press::Unbound]
+        );
+        assert!(keymap.pending().is_empty());
+    }
+
+    #[test]
+    fn overriding_works() {
+        let mut keymap = keymap();
+        let overrides = [
+            ("save", "c-W"),
+            ("toggle", ""),
+            ("settings", "c-sh-S"),
+            ("next", "c-c-N"),
+            ("missing", "c-M"),
+        ]
+        .into_iter()
+        .map(|(id, binding)| (id.to_string(), binding.to_string()))
+        .collect();
+        assert_eq!(keymap.set_overrides(&overrides).len(), 2);
+        assert_eq!(keymap.binding("save").unwrap().to_string(), "c-W");
+        assert_eq!(keymap.binding("toggle"), None);
+        assert_eq!(keymap.binding("save_as"), None);
+        assert_eq!(keymap.binding("next").unwrap().to_string(), "c-K N");
+        assert_eq!(
+            press(&mut keymap, "c-S c-sh-S"),
+            [Press::Unbound, Press::Action("settings")]
+        );
+        let overrides = [("palette_only".to_string(), "c-P".to_string())].into();
+        assert!(keymap.set_overrides(&overrides).is_empty());
+        assert_eq!(keymap.binding("save").unwrap().to_string(), "c-S");
+        assert_eq!(keymap.binding("palette_only").unwrap().to_string(), "c-P");
+    }
+}
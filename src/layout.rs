@@ -0,0 +1,193 @@
+//! A model of how the text area soft-wraps its text, so the overlay can draw highlights in the
+//! same places as the text they cover.
+//!
+//! This mirrors `white-space: pre-wrap` with `overflow-wrap: break-word` in a monospace font:
+//! rows break at Unicode line break opportunities, words too long for a row of their own break
+//! between graphemes, trailing whitespace hangs past the edge, tabs advance to the next tab stop
+//! and East Asian wide characters take up two columns.
+
+use std::ops::Range;
+
+use unicode_linebreak::linebreaks;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// How many columns apart tab stops are, matching the CSS default `tab-size`.
+pub const TAB_SIZE: usize = 8;
+
+/// A visual row of the text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    /// The byte range of the row's text, without the newline ending it.
+    pub range: Range<usize>,
+    /// How many columns the row's text takes up.
+    pub width: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    rows: Vec<Row>,
+    columns: usize,
+}
+
+impl Layout {
+    /// Wraps some text into rows at most `columns` wide.
+    pub fn new(text: &str, columns: usize) -> Self {
+        let columns = columns.max(1);
+        let mut rows = Vec::new();
+        let mut offset = 0;
+        for line in text.split('\n') {
+            wrap(line, offset, columns, &mut rows);
+            offset += line.len() + 1;
+        }
+        for row in &mut rows {
+            row.width = row.width.min(columns);
+        }
+        Self { rows, columns }
+    }
+
+    pub fn rows(&self) -> &[Row] {
+        &self.rows
+    }
+
+    /// Finds the row and column a byte offset of the text is drawn at.
+    ///
+    /// An offset where a line wraps is at the start of the next row, like the caret.
+    pub fn position(&self, text: &str, byte: usize) -> (usize, usize) {
+        let index = self
+            .rows
+            .partition_point(|row| row.range.start <= byte)
+            .saturating_sub(1);
+        let row = &self.rows[index];
+        let end = byte.clamp(row.range.start, row.range.end);
+        let column = text[row.range.start..end]
+            .graphemes(true)
+            .fold(0, |column, grapheme| column + advance(column, grapheme));
+        (index, column.min(self.columns))
+    }
+}
+
+/// How many columns a grapheme starting at a column takes up.
+fn advance(column: usize, grapheme: &str) -> usize {
+    if grapheme == "\t" {
+        TAB_SIZE - column % TAB_SIZE
+    } else {
+        grapheme.width()
+    }
+}
+
+/// Wraps a line without newlines, starting at byte `offset` of the text, into rows.
+fn wrap(line: &str, offset: usize, columns: usize, rows: &mut Vec<Row>) {
+    let mut start = 0;
+    let mut column = 0;
+    let mut segment_start = 0;
+    for (segment_end, _) in linebreaks(line) {
+        let segment = &line[segment_start..segment_end];
+        let content = segment.trim_end_matches([' ', '\t']);
+        let width = content.graphemes(true).fold(column, |column, grapheme| {
+            column + advance(column, grapheme)
+        });
+        if column > 0 && width > columns {
+            rows.push(Row {
+                range: offset + start..offset + segment_start,
+                width: column,
+            });
+            start = segment_start;
+            column = 0;
+        }
+        for (index, grapheme) in content.grapheme_indices(true) {
+            if column > 0 && column + advance(column, grapheme) > columns {
+                rows.push(Row {
+                    range: offset + start..offset + segment_start + index,
+                    width: column,
+                });
+                start = segment_start + index;
+                column = 0;
+            }
+            column += advance(column, grapheme);
+        }
+        for grapheme in segment[content.len()..].graphemes(true) {
+            column += advance(column, grapheme);
+        }
+        segment_start = segment_end;
+    }
+    rows.push(Row {
+        range: offset + start..offset + line.len(),
+        width: column,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(text: &str, columns: usize) -> Vec<&str> {
+        Layout::new(text, columns)
+            .rows()
+            .iter()
+            .map(|row| &text[row.range.clone()])
+            .collect()
+    }
+
+    fn widths(text: &str, columns: usize) -> Vec<usize> {
+        Layout::new(text, columns)
+            .rows()
+            .iter()
+            .map(|row| row.width)
+            .collect()
+    }
+
+    #[test]
+    fn wrapping_at_words_works() {
+        assert_eq!(rows("", 10), [""]);
+        assert_eq!(rows("one two three", 10), ["one two ", "three"]);
+        assert_eq!(rows("one\n\ntwo\n", 10), ["one", "", "two", ""]);
+        assert_eq!(rows("a-b well-known", 8), ["a-b ", "well-", "known"]);
+        assert_eq!(widths("one two three", 10), [8, 5]);
+    }
+
+    #[test]
+    fn breaking_long_words_works() {
+        assert_eq!(rows("abcdefghij", 4), ["abcd", "efgh", "ij"]);
+        assert_eq!(rows("to abcdefghij", 4), ["to ", "abcd", "efgh", "ij"]);
+        assert_eq!(rows("ae\u{301}io", 2), ["ae\u{301}", "io"]);
+    }
+
+    #[test]
+    fn trailing_whitespace_hangs() {
+        assert_eq!(rows("four    five", 4), ["four    ", "five"]);
+        assert_eq!(widths("four    five", 4), [4, 4]);
+        assert_eq!(rows("four\t", 4), ["four\t"]);
+    }
+
+    #[test]
+    fn tab_stops_work() {
+        assert_eq!(widths("\tx", 80), [TAB_SIZE + 1]);
+        assert_eq!(widths("abc\tx", 80), [TAB_SIZE + 1]);
+        assert_eq!(widths("abcdefgh\tx", 80), [2 * TAB_SIZE + 1]);
+        assert_eq!(rows("abc\tdefghijk", 10), ["abc\t", "defghijk"]);
+    }
+
+    #[test]
+    fn wide_characters_work() {
+        assert_eq!(widths("中文", 80), [4]);
+        assert_eq!(rows("中文字符", 5), ["中文", "字符"]);
+        assert_eq!(rows("ab中文", 5), ["ab中", "文"]);
+        assert_eq!(widths("😀x", 80), [3]);
+        assert_eq!(rows("😀😀😀", 4), ["😀😀", "😀"]);
+    }
+
+    #[test]
+    fn finding_positions_works() {
+        let text = "one two three\n中文\tx";
+        let layout = Layout::new(text, 10);
+        assert_eq!(layout.position(text, 0), (0, 0));
+        assert_eq!(layout.position(text, 4), (0, 4));
+        assert_eq!(layout.position(text, 8), (1, 0));
+        assert_eq!(layout.position(text, 13), (1, 5));
+        assert_eq!(layout.position(text, 14), (2, 0));
+        assert_eq!(layout.position(text, 17), (2, 2));
+        assert_eq!(layout.position(text, 21), (2, TAB_SIZE));
+        assert_eq!(layout.position(text, text.len()), (2, TAB_SIZE + 1));
+    }
+}
@@ -0,0 +1,258 @@
+//! Following wiki-style `[[links]]` between the documents of a project, and listing the documents
+//! that link to the open one.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use leptos::ev::keydown;
+use leptos::{
+    component, create_effect, create_rw_signal, spawn_local, use_context, view,
+    window_event_listener, CollectView, IntoView, RwSignal, SignalGetUntracked, SignalSet,
+    SignalUpdate, SignalWith, SignalWithUntracked,
+};
+use typewriter_core::document::{links, note_name};
+
+use crate::problems::report;
+use crate::project::{entry_name, open_file};
+use crate::{Context, Inter, Popup, Vertical};
+
+/// Maps the names of notes to the files they're in, by their file names without the extension.
+/// When several files have the same name, links lead to the first.
+pub fn notes_by_name(files: Vec<PathBuf>) -> BTreeMap<String, PathBuf> {
+    let mut notes = BTreeMap::new();
+    for file in files {
+        let Some(stem) = file.file_stem() else {
+            continue;
+        };
+        notes
+            .entry(note_name(&stem.to_string_lossy()))
+            .or_insert(file);
+    }
+    notes
+}
+
+/// Names the file a note is created in when a link leads to it and it doesn't exist, by the
+/// link's name without any heading, alias or surrounding spaces.
+pub fn note_file(link: &str) -> Option<String> {
+    entry_name(
+        link.split(['#', '|']).next().unwrap_or_default().trim(),
+        false,
+    )
+}
+
+/// Finds the name in the link at a byte offset of a text, if there is one.
+pub fn link_at(text: &str, offset: usize) -> Option<String> {
+    links(text)
+        .into_iter()
+        .find(|(range, _)| range.start < offset && offset < range.end)
+        .map(|(_, name)| name)
+}
+
+/// Indexes the links between the files of the open project in the backend, and finds the notes
+/// links can lead to.
+pub fn index_links(context: &Context) {
+    let Context {
+        notes,
+        problems: reported,
+        ..
+    } = *context;
+    let Some(root) = context.project.0.get_untracked() else {
+        notes.set(BTreeMap::new());
+        return;
+    };
+    spawn_local(async move {
+        let (files, problems) = Inter::index_links(root).await;
+        for problem in problems {
+            report(reported, "links", &problem);
+        }
+        notes.set(notes_by_name(files));
+    });
+}
+
+/// Keeps the link index up to date as projects are opened and documents are saved as new files.
+pub fn track_links(context: &Context) {
+    let context = *context;
+    let Context {
+        project: (read_project, _),
+        save_path: (read_save_path, _),
+        ..
+    } = context;
+    create_effect(move |_| {
+        read_project.track();
+        read_save_path.track();
+        index_links(&context);
+    });
+}
+
+/// Opens the note a link leads to, creating it next to the document if it doesn't exist.
+pub fn follow_link(context: &Context, name: &str) {
+    let context = *context;
+    let target = note_name(name);
+    if let Some(path) = context
+        .notes
+        .with_untracked(|notes| notes.get(&target).cloned())
+    {
+        open_file(&context, path, None);
+        return;
+    }
+    let folder = context
+        .save_path
+        .0
+        .get_untracked()
+        .and_then(|path| path.parent().map(Path::to_path_buf))
+        .or_else(|| context.project.0.get_untracked());
+    let (Some(folder), Some(file)) = (folder, note_file(name)) else {
+        return;
+    };
+    let path = folder.join(file);
+    spawn_local(async move {
+        if let Some(error) = Inter::create_file(path.clone()).await {
+            report(context.problems, "links", &error);
+            return;
+        }
+        open_file(&context, path.clone(), None);
+        context.notes.update(|notes| {
+            notes.insert(target, path);
+        });
+    });
+}
+
+#[component]
+pub fn Backlinks(show: RwSignal<bool>) -> impl IntoView {
+    let context = use_context::<Context>().unwrap();
+    let Context {
+        text_area,
+        save_path: (read_save_path, _),
+        project: (read_project, _),
+        ..
+    } = context;
+    let backlinks = create_rw_signal(Vec::<PathBuf>::new());
+    let selected = create_rw_signal(0);
+
+    create_effect(move |_| {
+        if !show() {
+            return;
+        }
+        text_area.get_untracked().unwrap().blur().unwrap();
+        selected.set(0);
+        backlinks.set(Vec::new());
+        let Some(path) = read_save_path.get_untracked() else {
+            return;
+        };
+        spawn_local(async move {
+            backlinks.set(Inter::backlinks(path).await);
+        });
+    });
+
+    let close = move || {
+        show.set(false);
+        text_area.get_untracked().unwrap().focus().unwrap();
+    };
+    let open = move |index: usize| {
+        let Some(path) = backlinks.with_untracked(|backlinks| backlinks.get(index).cloned()) else {
+            return;
+        };
+        close();
+        open_file(&context, path, None);
+    };
+
+    window_event_listener(keydown, move |event| {
+        if !show.get_untracked() {
+            return;
+        }
+        let count = backlinks.with_untracked(Vec::len);
+        match event.key().as_str() {
+            "Escape" => close(),
+            "Enter" => open(selected.get_untracked()),
+            "ArrowDown" => {
+                selected
+                    .update(|selected| *selected = (*selected + 1).min(count.saturating_sub(1)));
+            }
+            "ArrowUp" => selected.update(|selected| *selected = selected.saturating_sub(1)),
+            _ => return,
+        }
+        event.prevent_default();
+    });
+
+    view! {
+        <Popup show=show>
+            <Vertical class="overflow-y-auto max-h-80">
+                {move || {
+                    if read_project().is_none() {
+                        return view! {
+                            <div class="px-1">"open a project folder to find links between its files"</div>
+                        }
+                            .into_view();
+                    }
+                    if backlinks.with(Vec::is_empty) {
+                        return view! { <div class="px-1">"nothing links here"</div> }.into_view();
+                    }
+                    let root = read_project.get_untracked().unwrap_or_default();
+                    backlinks
+                        .with(|backlinks| {
+                            backlinks
+                                .iter()
+                                .enumerate()
+                                .map(|(index, path)| {
+                                    let label = path
+                                        .strip_prefix(&root)
+                                        .unwrap_or(path)
+                                        .display()
+                                        .to_string();
+                                    view! {
+                                        <div
+                                            class="px-1 rounded cursor-pointer"
+                                            class=("bg-background", move || selected() == index)
+                                            class=("text-text", move || selected() == index)
+                                            on:click=move |_| open(index)
+                                        >
+                                            {label}
+                                        </div>
+                                    }
+                                })
+                                .collect_view()
+                        })
+                }}
+            </Vertical>
+        </Popup>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolving_notes_works() {
+        let notes = notes_by_name(vec![
+            PathBuf::from("/notes/a/Idea.md"),
+            PathBuf::from("/notes/b/idea.md"),
+            PathBuf::from("/notes/Other Note.md"),
+        ]);
+        assert_eq!(notes.get("idea"), Some(&PathBuf::from("/notes/a/Idea.md")));
+        assert_eq!(
+            notes.get("other note"),
+            Some(&PathBuf::from("/notes/Other Note.md"))
+        );
+        assert_eq!(notes.len(), 2);
+    }
+
+    #[test]
+    fn finding_links_at_offsets_works() {
+        let text = "see [[Idea]] now";
+        assert_eq!(link_at(text, 7).as_deref(), Some("Idea"));
+        assert_eq!(link_at(text, 4), None);
+        assert_eq!(link_at(text, 14), None);
+        let text = "# C# notes\nSee [[Target]] now";
+        assert_eq!(links(text), [(15..25, "Target".to_string())]);
+        assert_eq!(link_at(text, 17).as_deref(), Some("Target"));
+        assert_eq!(link_at(text, 9), None);
+    }
+
+    #[test]
+    fn naming_new_notes_works() {
+        assert_eq!(note_file(" Note ").as_deref(), Some("Note.md"));
+        assert_eq!(note_file("Idea#Part|shown").as_deref(), Some("Idea.md"));
+        assert_eq!(note_file(" #Part"), None);
+    }
+}
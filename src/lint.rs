@@ -0,0 +1,357 @@
+//! A rule-based prose linter over the plain text of the document.
+
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::spelling::words;
+
+/// The most words a sentence can have before it's flagged as too long.
+const LONG_SENTENCE_WORDS: usize = 30;
+
+const BE: &[&str] = &["am", "is", "are", "was", "were", "be", "been", "being"];
+
+/// Past participles that don't end in "ed".
+const IRREGULAR_PARTICIPLES: &[&str] = &[
+    "begun",
+    "bitten",
+    "blown",
+    "born",
+    "bought",
+    "brought",
+    "built",
+    "caught",
+    "chosen",
+    "done",
+    "drawn",
+    "driven",
+    "eaten",
+    "fallen",
+    "felt",
+    "found",
+    "forgotten",
+    "given",
+    "gone",
+    "grown",
+    "heard",
+    "held",
+    "hidden",
+    "hit",
+    "kept",
+    "known",
+    "laid",
+    "led",
+    "left",
+    "lost",
+    "made",
+    "meant",
+    "met",
+    "paid",
+    "put",
+    "read",
+    "said",
+    "seen",
+    "sent",
+    "set",
+    "shaken",
+    "shown",
+    "shut",
+    "sold",
+    "spent",
+    "spoken",
+    "stolen",
+    "struck",
+    "sung",
+    "taken",
+    "taught",
+    "thought",
+    "thrown",
+    "told",
+    "torn",
+    "understood",
+    "won",
+    "worn",
+    "written",
+];
+
+/// Adverbs that rarely add anything.
+const FILLER_ADVERBS: &[&str] = &[
+    "actually",
+    "basically",
+    "certainly",
+    "definitely",
+    "extremely",
+    "just",
+    "literally",
+    "quite",
+    "rather",
+    "really",
+    "simply",
+    "somewhat",
+    "totally",
+    "truly",
+    "very",
+];
+
+const CLICHES: &[&str] = &[
+    "all in all",
+    "at the end of the day",
+    "avoid like the plague",
+    "better late than never",
+    "calm before the storm",
+    "every cloud has a silver lining",
+    "few and far between",
+    "in the nick of time",
+    "last but not least",
+    "low-hanging fruit",
+    "only time will tell",
+    "think outside the box",
+    "when all is said and done",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    RepeatedWords,
+    LongSentences,
+    PassiveVoice,
+    Adverbs,
+    Cliches,
+}
+
+impl Rule {
+    pub const ALL: [Self; 5] = [
+        Self::RepeatedWords,
+        Self::LongSentences,
+        Self::PassiveVoice,
+        Self::Adverbs,
+        Self::Cliches,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::RepeatedWords => "repeated words",
+            Self::LongSentences => "long sentences",
+            Self::PassiveVoice => "passive voice",
+            Self::Adverbs => "filler adverbs",
+            Self::Cliches => "clichés",
+        }
+    }
+}
+
+/// Which rules are enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Rules {
+    pub repeated_words: bool,
+    pub long_sentences: bool,
+    pub passive_voice: bool,
+    pub adverbs: bool,
+    pub cliches: bool,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            repeated_words: true,
+            long_sentences: true,
+            passive_voice: true,
+            adverbs: true,
+            cliches: true,
+        }
+    }
+}
+
+impl Rules {
+    pub const fn enabled(self, rule: Rule) -> bool {
+        match rule {
+            Rule::RepeatedWords => self.repeated_words,
+            Rule::LongSentences => self.long_sentences,
+            Rule::PassiveVoice => self.passive_voice,
+            Rule::Adverbs => self.adverbs,
+            Rule::Cliches => self.cliches,
+        }
+    }
+
+    pub const fn enabled_mut(&mut self, rule: Rule) -> &mut bool {
+        match rule {
+            Rule::RepeatedWords => &mut self.repeated_words,
+            Rule::LongSentences => &mut self.long_sentences,
+            Rule::PassiveVoice => &mut self.passive_voice,
+            Rule::Adverbs => &mut self.adverbs,
+            Rule::Cliches => &mut self.cliches,
+        }
+    }
+}
+
+/// Something a rule flagged in the text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    pub rule: Rule,
+    /// The byte range of the text flagged.
+    pub range: Range<usize>,
+    pub message: String,
+}
+
+/// Runs the enabled rules over some text, returning what they flagged in order.
+pub fn lint(text: &str, rules: Rules) -> Vec<Lint> {
+    let words = words(text);
+    let lowercase = |range: &Range<usize>| text[range.clone()].to_lowercase();
+    let mut lints = Vec::new();
+    if rules.repeated_words {
+        for pair in words.windows(2) {
+            let between = &text[pair[0].end..pair[1].start];
+            if between.chars().all(|char| char == ' ' || char == '\t')
+                && lowercase(&pair[0]) == lowercase(&pair[1])
+            {
+                lints.push(Lint {
+                    rule: Rule::RepeatedWords,
+                    range: pair[0].start..pair[1].end,
+                    message: format!("\u{201c}{}\u{201d} is repeated", &text[pair[0].clone()]),
+                });
+            }
+        }
+    }
+    if rules.long_sentences {
+        // Sentences and words are both in order, so the words of each sentence start where the
+        // last sentence's ended.
+        let mut first = 0;
+        for (start, sentence) in text.split_sentence_bound_indices() {
+            first += words[first..].partition_point(|word| word.start < start);
+            let count = words[first..].partition_point(|word| word.end <= start + sentence.len());
+            if count > LONG_SENTENCE_WORDS {
+                lints.push(Lint {
+                    rule: Rule::LongSentences,
+                    range: start..start + sentence.trim_end().len(),
+                    message: format!("this sentence has {count} words"),
+                });
+            }
+        }
+    }
+    if rules.passive_voice {
+        for (index, be) in words.iter().enumerate() {
+            if !BE.contains(&lowercase(be).as_str()) {
+                continue;
+            }
+            let mut next = words[index + 1..].iter();
+            let participle = match next.next() {
+                Some(word) if lowercase(word).ends_with("ly") => next.next(),
+                word => word,
+            };
+            let Some(participle) = participle else {
+                continue;
+            };
+            let participle_word = lowercase(participle);
+            let close = !text[be.end..participle.start].contains(['.', '!', '?', '\n']);
+            if close
+                && (participle_word.ends_with("ed")
+                    || IRREGULAR_PARTICIPLES.contains(&participle_word.as_str()))
+            {
+                lints.push(Lint {
+                    rule: Rule::PassiveVoice,
+                    range: be.start..participle.end,
+                    message: "this may be passive voice".to_string(),
+                });
+            }
+        }
+    }
+    if rules.adverbs {
+        for word in &words {
+            if FILLER_ADVERBS.contains(&lowercase(word).as_str()) {
+                lints.push(Lint {
+                    rule: Rule::Adverbs,
+                    range: word.clone(),
+                    message: format!("\u{201c}{}\u{201d} may be filler", &text[word.clone()]),
+                });
+            }
+        }
+    }
+    if rules.cliches {
+        // ASCII lowercasing keeps the byte offsets the same.
+        let lowercase = text.to_ascii_lowercase();
+        for cliche in CLICHES {
+            for (start, _) in lowercase.match_indices(cliche) {
+                let end = start + cliche.len();
+                let bounded =
+                    |offset: Option<char>| offset.is_none_or(|char| !char.is_alphanumeric());
+                if bounded(text[..start].chars().next_back()) && bounded(text[end..].chars().next())
+                {
+                    lints.push(Lint {
+                        rule: Rule::Cliches,
+                        range: start..end,
+                        message: format!("\u{201c}{}\u{201d} is a cliché", &text[start..end]),
+                    });
+                }
+            }
+        }
+    }
+    lints.sort_by_key(|lint| (lint.range.start, lint.range.end));
+    lints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flagged(text: &str, rule: Rule) -> Vec<&str> {
+        let mut rules = Rules {
+            repeated_words: false,
+            long_sentences: false,
+            passive_voice: false,
+            adverbs: false,
+            cliches: false,
+        };
+        *rules.enabled_mut(rule) = true;
+        lint(text, rules)
+            .into_iter()
+            .map(|lint| &text[lint.range])
+            .collect()
+    }
+
+    #[test]
+    fn flagging_repeated_words_works() {
+        assert_eq!(
+            flagged(
+                "It was the the best. The\nthe end, *is* is",
+                Rule::RepeatedWords
+            ),
+            ["the the"]
+        );
+    }
+
+    #[test]
+    fn flagging_long_sentences_works() {
+        let long = "Word".to_string() + &" word".repeat(30) + ".";
+        let text = format!("Short one. {long} Another.");
+        assert_eq!(flagged(&text, Rule::LongSentences), [long.as_str()]);
+        let text = format!("{long} Short one. {long}");
+        assert_eq!(flagged(&text, Rule::LongSentences), [&long, &long]);
+        assert!(flagged(&"word ".repeat(30), Rule::LongSentences).is_empty());
+    }
+
+    #[test]
+    fn flagging_passive_voice_works() {
+        assert_eq!(
+            flagged(
+                "The ball was thrown. It is quickly painted. She was. Is happy, were tired",
+                Rule::PassiveVoice
+            ),
+            ["was thrown", "is quickly painted", "were tired"]
+        );
+    }
+
+    #[test]
+    fn flagging_adverbs_and_cliches_works() {
+        assert_eq!(
+            flagged("It was Very, very justified.", Rule::Adverbs),
+            ["Very", "very"]
+        );
+        assert_eq!(
+            flagged(
+                "At the end of the day, it was low-hanging fruit. Wall in all",
+                Rule::Cliches
+            ),
+            ["At the end of the day", "low-hanging fruit"]
+        );
+    }
+}
@@ -1,35 +1,94 @@
 #![warn(clippy::pedantic, clippy::nursery)]
 #![allow(clippy::must_use_candidate)]
 
+mod diff;
 mod document;
+mod find;
+mod focus;
+mod fold;
+mod git;
+mod history;
+mod keymap;
+mod layout;
+mod links;
+mod lint;
+mod manuscript;
+mod offset;
+mod outline;
+mod palette;
+mod problems;
+mod project;
+mod search;
+mod search_index;
+mod settings;
+mod snapshots;
+mod spelling;
+mod sprint;
+mod stats;
+mod writing;
 
 use std::array::from_fn;
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::convert::Infallible;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use codee::{Decoder, Encoder};
-use document::{Document, Segment};
+use diff::{hunks, revert, DiffBase, Granularity, Hunk};
+use document::document_view;
+use find::{toggle_keydown, Toggles};
+use focus::focused_range;
+use fold::{project, to_shown, to_text, Folds};
+use git::{track_git, GitCommit, GitCommitPopup, GitIndicator, GitLog, GitStatus, LineChange};
+use history::{Edit, History, Kind};
 use itertools::Itertools;
-use leptos::html::Div;
+use js_sys::Date;
+use keymap::{command_held, is_mac, Keymap, Keystroke, Press};
+use layout::Layout;
+use leptos::html::{Div, Input, Textarea};
+use links::{follow_link, link_at, track_links, Backlinks};
+use lint::{lint, Lint};
+use manuscript::{Manuscript, ManuscriptPopup};
+use offset::{
+    byte_to_utf16, ceil_grapheme_boundary, floor_char_boundary, floor_grapheme_boundary,
+    utf16_to_byte,
+};
+use outline::{current_section, Outline};
+use palette::{Command, CommandPalette};
+use problems::{report, ProblemsIndicator, ProblemsPopup};
+use project::{Entry, Opening, Sidebar, UnsavedChanges};
+use search::FolderSearch;
+use search_index::{track_search_index, IndexResult, IndexSearch};
 use serde::de::DeserializeOwned;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use settings::{Settings, SettingsPanel};
+use snapshots::{Snapshot, SnapshotHistory};
+use spelling::{check_spelling, Checked, SpellingPopup};
+use sprint::{deletes, input_deletes, SprintPopup, SprintProgress, SprintState};
+use stats::StatisticsPanel;
+use writing::{
+    log_writing, path_key, track_writing, words_added, Writing, WritingHistory, WritingLog,
+    WritingProgress,
+};
 
 use console_error_panic_hook::set_once;
-use leptos::ev::{keydown, keyup};
+use leptos::ev::{keydown, keyup, resize};
 use leptos::{
     component, create_action, create_effect, create_memo, create_node_ref, create_rw_signal,
-    event_target, event_target_value, provide_context, spawn_local, use_context,
+    event_target, event_target_value, provide_context, spawn_local, untrack, use_context,
     window_event_listener, Action, AttributeValue, Callback, Children, CollectView, For,
-    HtmlElement, IntoView, NodeRef, RwSignal, Show, Signal, SignalGetUntracked, SignalSet,
-    SignalUpdate, ViewFn, WriteSignal,
+    HtmlElement, IntoView, Memo, NodeRef, RwSignal, Show, Signal, SignalGetUntracked, SignalSet,
+    SignalUpdate, SignalWith, SignalWithUntracked, ViewFn, WriteSignal,
 };
 use leptos::{mount_to_body, view};
 use leptos_use::storage::use_local_storage;
 use serde_wasm_bindgen::{from_value, to_value};
+use typewriter_core::document::{headings, Document, Segment};
+use typewriter_core::find::Query;
 use unicode_segmentation::UnicodeSegmentation;
-use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen::prelude::{wasm_bindgen, Closure};
 use wasm_bindgen::JsValue;
 use web_sys::HtmlTextAreaElement;
 
@@ -67,6 +126,9 @@ extern "C" {
 
     #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "core"])]
     async fn invoke(cmd: &str, args: JsValue) -> JsValue;
+
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "event"])]
+    async fn listen(event: &str, handler: &Closure<dyn Fn(JsValue)>) -> JsValue;
 }
 
 struct Inter;
@@ -77,13 +139,13 @@ impl Inter {
         from_value(invoke(cmd, to_value(args).unwrap()).await).unwrap()
     }
 
-    /// Saves a file containing some data to a path, prompting the user if the path is [`None`].
-    ///
-    /// Returns the path to the file or [`None`] if the
-    /// user cancelled the save by closing the dialog.
+    /// Saves a file containing some data to a path, prompting the user if the path is [`None`],
+    /// and takes a snapshot of it.
     ///
-    /// Panics if the data couldn't be written.
-    async fn save_file(data: String, path: Option<PathBuf>) -> Option<PathBuf> {
+    /// Returns the path to the file or [`None`] if the user cancelled the save by closing the
+    /// dialog or the data couldn't be written, and a description of what went wrong writing it or
+    /// taking the snapshot.
+    async fn save_file(data: String, path: Option<PathBuf>) -> (Option<PathBuf>, Option<String>) {
         #[derive(Serialize)]
         struct SaveFileArgs {
             data: String,
@@ -93,18 +155,13 @@ impl Inter {
     }
 
     /// Loads a file from a path.
-    /// Returns a tuple containing the data in the file and the path to the file.
     ///
-    /// The returned data is [`None`] if the file couldn't be read (it didn't exist, or did not
-    /// contain valid UTF-8) - or if the supplied path was [`None`] and the user did not provide a
-    /// fallback (they cancelled the load by closing the dialog).
-    ///
-    /// The returned path is [`None`] if the supplied path was [`None`] and the user did not
-    /// provide a fallback (they cancelled the load by closing the dialog).
-    async fn load_file(path: Option<PathBuf>) -> (Option<String>, Option<PathBuf>) {
+    /// Returns the data in the file, which is [`None`] if the file couldn't be read (it didn't
+    /// exist, or did not contain valid UTF-8).
+    async fn load_file(path: PathBuf) -> Option<String> {
         #[derive(Serialize)]
         struct LoadFileArgs {
-            path: Option<PathBuf>,
+            path: PathBuf,
         }
         Self::call("load_file", &LoadFileArgs { path }).await
     }
@@ -113,15 +170,537 @@ impl Inter {
     async fn quit() {
         invoke_without_args("quit").await;
     }
+
+    /// Searches every Markdown file under a folder in the background, prompting the user for the
+    /// folder if it is [`None`].
+    ///
+    /// Results arrive as `search-result` events and completion as a `search-finished` event, all
+    /// tagged with the returned id. Returns [`None`] if the user cancelled the dialog.
+    async fn search_folder(root: Option<PathBuf>, query: Query) -> Option<(PathBuf, u32)> {
+        #[derive(Serialize)]
+        struct SearchFolderArgs {
+            root: Option<PathBuf>,
+            query: Query,
+        }
+        Self::call("search_folder", &SearchFolderArgs { root, query }).await
+    }
+
+    /// Opens the full-text index of a project in the background, bringing it up to date with the
+    /// files on disk and keeping it so as they change.
+    ///
+    /// What goes wrong is sent as `search-index-error` events.
+    async fn open_search_index(root: PathBuf) {
+        #[derive(Serialize)]
+        struct OpenSearchIndexArgs {
+            root: PathBuf,
+        }
+        Self::call::<()>("open_search_index", &OpenSearchIndexArgs { root }).await;
+    }
+
+    /// Finds the sections of the project's files that have every word and quoted phrase of a
+    /// query, best matches first.
+    ///
+    /// Returns the results and a description of what went wrong.
+    async fn query_search_index(query: String) -> (Vec<IndexResult>, Option<String>) {
+        #[derive(Serialize)]
+        struct QuerySearchIndexArgs {
+            query: String,
+        }
+        Self::call("query_search_index", &QuerySearchIndexArgs { query }).await
+    }
+
+    /// Loads the user's keyboard shortcut overrides from the keymap file in the config directory.
+    ///
+    /// Returns a map of action ids to bindings, and a description of what went wrong if the file
+    /// couldn't be read.
+    async fn load_keymap() -> (BTreeMap<String, String>, Option<String>) {
+        from_value(invoke_without_args("load_keymap").await).unwrap()
+    }
+
+    /// Loads the settings from the settings file in the config directory.
+    ///
+    /// Returns the settings and a description of everything that was wrong with the file.
+    async fn get_settings() -> (Settings, Vec<String>) {
+        from_value(invoke_without_args("get_settings").await).unwrap()
+    }
+
+    /// Validates and saves the settings, which are then sent to every window in a
+    /// `settings-changed` event.
+    ///
+    /// Returns a description of everything that was wrong with the settings or saving them.
+    async fn set_settings(settings: Settings) -> Vec<String> {
+        #[derive(Serialize)]
+        struct SetSettingsArgs {
+            settings: Settings,
+        }
+        Self::call("set_settings", &SetSettingsArgs { settings }).await
+    }
+
+    /// Checks the spelling of some words with the Hunspell dictionary of a language like `en_US`.
+    ///
+    /// Returns the misspelled words, and a description of what went wrong if the dictionary
+    /// couldn't be loaded.
+    async fn check_words(language: String, words: Vec<String>) -> (Vec<String>, Option<String>) {
+        #[derive(Serialize)]
+        struct CheckWordsArgs {
+            language: String,
+            words: Vec<String>,
+        }
+        Self::call("check_words", &CheckWordsArgs { language, words }).await
+    }
+
+    /// Suggests corrections for a misspelled word, best first.
+    async fn suggest_words(language: String, word: String) -> Vec<String> {
+        #[derive(Serialize)]
+        struct SuggestWordsArgs {
+            language: String,
+            word: String,
+        }
+        Self::call("suggest_words", &SuggestWordsArgs { language, word }).await
+    }
+
+    /// Adds a word to the personal dictionary.
+    ///
+    /// Returns a description of what went wrong if the word couldn't be saved.
+    async fn add_to_dictionary(word: String) -> Option<String> {
+        #[derive(Serialize)]
+        struct AddToDictionaryArgs {
+            word: String,
+        }
+        Self::call("add_to_dictionary", &AddToDictionaryArgs { word }).await
+    }
+
+    /// Loads the log of the words written each day in each file, and the word goals of files.
+    ///
+    /// Returns the log and a description of what went wrong reading it.
+    async fn get_writing_log() -> (WritingLog, Option<String>) {
+        from_value(invoke_without_args("get_writing_log").await).unwrap()
+    }
+
+    /// Adds some net words written in a file, or an untitled document if the path is empty, on a
+    /// day like `2024-01-31` to the writing log.
+    ///
+    /// Returns a description of what went wrong saving the log.
+    async fn log_words(day: String, path: String, words: i64) -> Option<String> {
+        #[derive(Serialize)]
+        struct LogWordsArgs {
+            day: String,
+            path: String,
+            words: i64,
+        }
+        Self::call("log_words", &LogWordsArgs { day, path, words }).await
+    }
+
+    /// Sets the word goal of a file, or removes it if it is [`None`].
+    ///
+    /// Returns a description of what went wrong saving the log.
+    async fn set_document_goal(path: String, goal: Option<usize>) -> Option<String> {
+        #[derive(Serialize)]
+        struct SetDocumentGoalArgs {
+            path: String,
+            goal: Option<usize>,
+        }
+        Self::call("set_document_goal", &SetDocumentGoalArgs { path, goal }).await
+    }
+
+    /// Lists the snapshots taken of a file each time it was saved, newest first.
+    ///
+    /// Returns the snapshots and a description of what went wrong reading them.
+    async fn list_snapshots(path: PathBuf) -> (Vec<Snapshot>, Option<String>) {
+        #[derive(Serialize)]
+        struct ListSnapshotsArgs {
+            path: PathBuf,
+        }
+        Self::call("list_snapshots", &ListSnapshotsArgs { path }).await
+    }
+
+    /// Loads the contents of a snapshot of a file by their hash.
+    ///
+    /// Returns the contents, or a description of what went wrong loading them.
+    async fn load_snapshot(path: PathBuf, hash: String) -> (Option<String>, Option<String>) {
+        #[derive(Serialize)]
+        struct LoadSnapshotArgs {
+            path: PathBuf,
+            hash: String,
+        }
+        Self::call("load_snapshot", &LoadSnapshotArgs { path, hash }).await
+    }
+
+    /// Prompts the user for a folder to open as a project.
+    ///
+    /// Returns the folder, or [`None`] if the user cancelled the dialog.
+    async fn pick_project() -> Option<PathBuf> {
+        from_value(invoke_without_args("pick_project").await).unwrap()
+    }
+
+    /// Lists the Markdown files and the folders in a project, as a tree.
+    ///
+    /// Returns the entries in the project's folder, and descriptions of what went wrong reading
+    /// it.
+    async fn list_project(root: PathBuf) -> (Vec<Entry>, Vec<String>) {
+        #[derive(Serialize)]
+        struct ListProjectArgs {
+            root: PathBuf,
+        }
+        Self::call("list_project", &ListProjectArgs { root }).await
+    }
+
+    /// Creates an empty file, unless something is already at the path.
+    ///
+    /// Returns a description of what went wrong.
+    async fn create_file(path: PathBuf) -> Option<String> {
+        #[derive(Serialize)]
+        struct CreateFileArgs {
+            path: PathBuf,
+        }
+        Self::call("create_file", &CreateFileArgs { path }).await
+    }
+
+    /// Creates a folder, unless something is already at the path.
+    ///
+    /// Returns a description of what went wrong.
+    async fn create_folder(path: PathBuf) -> Option<String> {
+        #[derive(Serialize)]
+        struct CreateFolderArgs {
+            path: PathBuf,
+        }
+        Self::call("create_folder", &CreateFolderArgs { path }).await
+    }
+
+    /// Renames or moves a file or a folder, unless something is already at the new path.
+    ///
+    /// Returns a description of what went wrong.
+    async fn rename_path(path: PathBuf, to: PathBuf) -> Option<String> {
+        #[derive(Serialize)]
+        struct RenamePathArgs {
+            path: PathBuf,
+            to: PathBuf,
+        }
+        Self::call("rename_path", &RenamePathArgs { path, to }).await
+    }
+
+    /// Deletes a file, or a folder if it's empty.
+    ///
+    /// Returns a description of what went wrong.
+    async fn delete_path(path: PathBuf) -> Option<String> {
+        #[derive(Serialize)]
+        struct DeletePathArgs {
+            path: PathBuf,
+        }
+        Self::call("delete_path", &DeletePathArgs { path }).await
+    }
+
+    /// Loads the manifest of a project.
+    ///
+    /// Returns the manifest, which is [`None`] if the project doesn't have one, and a description
+    /// of what went wrong loading it.
+    async fn get_manuscript(root: PathBuf) -> (Option<Manuscript>, Option<String>) {
+        #[derive(Serialize)]
+        struct GetManuscriptArgs {
+            root: PathBuf,
+        }
+        Self::call("get_manuscript", &GetManuscriptArgs { root }).await
+    }
+
+    /// Saves the manifest of a project.
+    ///
+    /// Returns a description of what went wrong.
+    async fn set_manuscript(root: PathBuf, manuscript: Manuscript) -> Option<String> {
+        #[derive(Serialize)]
+        struct SetManuscriptArgs {
+            root: PathBuf,
+            manuscript: Manuscript,
+        }
+        Self::call("set_manuscript", &SetManuscriptArgs { root, manuscript }).await
+    }
+
+    /// Writes a compiled manuscript to a file in a project's folder.
+    ///
+    /// Returns the path of the file written, or a description of what went wrong.
+    async fn write_manuscript(
+        root: PathBuf,
+        output: String,
+        data: String,
+    ) -> (Option<PathBuf>, Option<String>) {
+        #[derive(Serialize)]
+        struct WriteManuscriptArgs {
+            root: PathBuf,
+            output: String,
+            data: String,
+        }
+        Self::call(
+            "write_manuscript",
+            &WriteManuscriptArgs { root, output, data },
+        )
+        .await
+    }
+
+    /// Indexes the links between the files of a project, replacing the index of any other
+    /// project.
+    ///
+    /// Returns every Markdown file in the project, and descriptions of what went wrong reading
+    /// them.
+    async fn index_links(root: PathBuf) -> (Vec<PathBuf>, Vec<String>) {
+        #[derive(Serialize)]
+        struct IndexLinksArgs {
+            root: PathBuf,
+        }
+        Self::call("index_links", &IndexLinksArgs { root }).await
+    }
+
+    /// Lists the files in the indexed project that link to a file.
+    async fn backlinks(path: PathBuf) -> Vec<PathBuf> {
+        #[derive(Serialize)]
+        struct BacklinksArgs {
+            path: PathBuf,
+        }
+        Self::call("backlinks", &BacklinksArgs { path }).await
+    }
+
+    /// Gets the branch of the repository a file is in, the file's status in it, and its contents
+    /// in the commit checked out.
+    ///
+    /// Returns the status, which is [`None`] if the file isn't in a repository, and a description
+    /// of what went wrong reading the repository.
+    async fn git_status(path: PathBuf) -> (Option<GitStatus>, Option<String>) {
+        #[derive(Serialize)]
+        struct GitStatusArgs {
+            path: PathBuf,
+        }
+        Self::call("git_status", &GitStatusArgs { path }).await
+    }
+
+    /// Commits a file as it is saved with a message.
+    ///
+    /// Returns a description of what went wrong.
+    async fn git_commit(path: PathBuf, message: String) -> Option<String> {
+        #[derive(Serialize)]
+        struct GitCommitArgs {
+            path: PathBuf,
+            message: String,
+        }
+        Self::call("git_commit", &GitCommitArgs { path, message }).await
+    }
+
+    /// Lists the commits that changed a file, newest first.
+    ///
+    /// Returns the commits and a description of what went wrong reading them.
+    async fn git_log(path: PathBuf) -> (Vec<GitCommit>, Option<String>) {
+        #[derive(Serialize)]
+        struct GitLogArgs {
+            path: PathBuf,
+        }
+        Self::call("git_log", &GitLogArgs { path }).await
+    }
+
+    /// Calls a handler with the payload of every event of some name emitted by the backend.
+    async fn listen<T: DeserializeOwned + 'static>(event: &str, handler: impl Fn(T) + 'static) {
+        #[derive(Deserialize)]
+        struct Event<T> {
+            payload: T,
+        }
+        let handler = Closure::<dyn Fn(JsValue)>::new(move |event| {
+            handler(from_value::<Event<T>>(event).unwrap().payload);
+        });
+        listen(event, &handler).await;
+        handler.forget();
+    }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Copy)]
 struct Context {
     text: RwSignal<String>,
     save_path: (Signal<Option<PathBuf>>, WriteSignal<Option<PathBuf>>),
+    /// The folder of the open project, if one is open.
+    project: (Signal<Option<PathBuf>>, WriteSignal<Option<PathBuf>>),
+    /// Whether the sidebar listing the project's files is shown.
+    sidebar: RwSignal<bool>,
+    /// The files in the project that links can lead to, by their normalized note names.
+    notes: RwSignal<BTreeMap<String, PathBuf>>,
+    /// A file asked to be opened while the document has unsaved changes, which needs confirming.
+    opening: RwSignal<Option<Opening>>,
+    /// What went wrong that the user should know about, oldest first.
+    problems: RwSignal<Vec<String>>,
     save: Action<bool, ()>,
-    unsaved: RwSignal<bool>,
-    selection: RwSignal<Option<(usize, usize)>>,
+    unsaved: Memo<bool>,
+    /// The byte range of the text selected in the text area, if any.
+    selection: RwSignal<Option<Range<usize>>>,
+    /// The byte offset of the caret in the text.
+    cursor: RwSignal<usize>,
+    /// The padding in pixels above and below the text that lets typewriter scrolling keep the
+    /// first and last rows in place, if it is on.
+    typewriter_padding: Memo<Option<(f64, f64)>>,
+    matches: RwSignal<Vec<Range<usize>>>,
+    current_match: RwSignal<Option<usize>>,
+    text_area: NodeRef<Textarea>,
+    history: RwSignal<History>,
+    /// The byte offset of the first row of the text scrolled into view.
+    top: RwSignal<usize>,
+    /// A byte offset of the text to scroll to the top of the view.
+    reveal: RwSignal<Option<usize>>,
+    folds: RwSignal<Folds>,
+    /// The byte ranges of the text hidden by folds.
+    hidden: Memo<Vec<Range<usize>>>,
+    /// The text shown in the text area, with the folded parts cut out. Offsets into it are only
+    /// used at the DOM and converted with [`fold::to_shown`] and [`fold::to_text`].
+    shown: Memo<String>,
+    settings: RwSignal<Settings>,
+    checked: RwSignal<Checked>,
+    /// The byte ranges of the misspelled words in the text, if spell checking is on.
+    misspelled: Memo<Vec<Range<usize>>>,
+    /// What the enabled style rules flagged in the text.
+    lints: Memo<Vec<Lint>>,
+    writing: RwSignal<Writing>,
+    sprint: RwSignal<SprintState>,
+    /// What the text is compared with in the diff view, if it's on.
+    diff_base: RwSignal<Option<DiffBase>>,
+    /// The hunks of the text that differ word by word from what it's compared with in the diff
+    /// view.
+    diff: Memo<Vec<Hunk>>,
+    /// The state of the git repository the document is in, if it's in one.
+    git: RwSignal<Option<GitStatus>>,
+    /// The hunks of lines of the text changed since the commit checked out.
+    git_changes: Memo<Vec<Hunk>>,
+}
+
+impl Context {
+    /// Gets the byte range of the text selected in the text area.
+    fn selected_range(self) -> Range<usize> {
+        let text_area = self.text_area.get_untracked().unwrap();
+        let (start, end) = self.shown.with_untracked(|shown| {
+            let to_byte = |utf16: u32| utf16_to_byte(shown, utf16 as usize);
+            (
+                to_byte(text_area.selection_start().unwrap().unwrap()),
+                to_byte(text_area.selection_end().unwrap().unwrap()),
+            )
+        });
+        self.hidden.with_untracked(|hidden| {
+            to_text(hidden, start, start != end)..to_text(hidden, end, false)
+        })
+    }
+
+    /// Selects a byte range of the text in the text area, unfolding any sections hiding it.
+    fn select_range(self, range: Range<usize>) {
+        self.text.with_untracked(|text| {
+            self.folds.update(|folds| folds.reveal(text, range.clone()));
+        });
+        self.show_selection(range);
+    }
+
+    /// Selects a byte range of the text in the text area, moving the ends of it that are hidden
+    /// to where they were folded.
+    fn show_selection(self, range: Range<usize>) {
+        let (start, end) = self
+            .hidden
+            .with_untracked(|hidden| (to_shown(hidden, range.start), to_shown(hidden, range.end)));
+        let text_area = self.text_area.get_untracked().unwrap();
+        self.shown.with_untracked(|shown| {
+            #[allow(clippy::cast_possible_truncation)]
+            text_area
+                .set_selection_range(
+                    byte_to_utf16(shown, start) as u32,
+                    byte_to_utf16(shown, end) as u32,
+                )
+                .unwrap();
+        });
+        self.cursor.set(range.end);
+    }
+
+    /// Changes which sections are folded, keeping the selection where it was.
+    fn update_folds(self, update: impl FnOnce(&mut Folds, &str)) {
+        let selection = self.selected_range();
+        self.text
+            .with_untracked(|text| self.folds.update(|folds| update(folds, text)));
+        self.show_selection(selection);
+    }
+
+    /// Applies an edit to the text, keeping the folds with their headings and recording the words
+    /// it writes.
+    fn apply(self, edit: &Edit) {
+        let words = self.text.with_untracked(|text| words_added(text, edit));
+        if words != 0 {
+            let path = path_key(self.save_path.0.get_untracked().as_deref());
+            self.writing.update(|writing| writing.record(&path, words));
+        }
+        self.text.update(|text| edit.apply(text));
+        self.text.with_untracked(|text| {
+            self.folds.update(|folds| folds.shift(text, edit));
+        });
+    }
+
+    /// Replaces the text with a newly loaded one, forgetting the history and folds.
+    fn load(self, text: String) {
+        self.text.set(text);
+        self.history.set(History::default());
+        self.folds.set(Folds::default());
+    }
+
+    /// Applies an edit to the text as its own undo step, then selects a byte range of the new
+    /// text.
+    fn edit(self, edit: Edit, selection: Range<usize>) {
+        let before = self.selected_range();
+        self.apply(&edit);
+        self.history.update(|history| {
+            history.record(edit, before, selection.clone(), Kind::Other, Date::now());
+        });
+        self.select_range(selection);
+    }
+
+    /// Replaces the whole text as a single undo step, keeping the selection where it was.
+    fn replace_text(self, text: &str) {
+        let Some(edit) = self.text.with_untracked(|old| Edit::diff(old, text)) else {
+            return;
+        };
+        let selection = self.selected_range();
+        let selection =
+            floor_char_boundary(text, selection.start)..floor_char_boundary(text, selection.end);
+        self.edit(edit, selection);
+    }
+
+    /// Replaces the text with one from the history, then selects a byte range of it.
+    fn restore(self, text: &str, selection: Range<usize>) {
+        if let Some(edit) = self.text.with_untracked(|old| Edit::diff(old, text)) {
+            self.apply(&edit);
+        }
+        self.select_range(selection);
+    }
+
+    /// Reverts some hunks of the diff view, in order, as a single undo step.
+    fn revert_hunks(self, hunks: &[Hunk]) {
+        let Some(edit) = self.text.with_untracked(|text| revert(hunks, text)) else {
+            return;
+        };
+        let end = edit.offset + edit.inserted.len();
+        self.edit(edit, end..end);
+    }
+
+    fn undo(self) {
+        // Undoing typing deletes it, which a strict sprint doesn't allow.
+        if self.sprint.with_untracked(SprintState::is_strict) {
+            return;
+        }
+        let mut text = self.text.get_untracked();
+        let Some(selection) = self
+            .history
+            .try_update(|history| history.undo(&mut text))
+            .flatten()
+        else {
+            return;
+        };
+        self.restore(&text, selection);
+    }
+
+    fn redo(self) {
+        let mut text = self.text.get_untracked();
+        let Some(selection) = self
+            .history
+            .try_update(|history| history.redo(&mut text))
+            .flatten()
+        else {
+            return;
+        };
+        self.restore(&text, selection);
+    }
 }
 
 pub struct PathBufCodec;
@@ -158,30 +737,172 @@ pub fn App() -> impl IntoView {
     let text = create_rw_signal(String::new());
     let (read_save_path, write_save_path, _) =
         use_local_storage::<Option<PathBuf>, PathBufCodec>("save_path");
-    let unsaved = create_rw_signal(false);
+    let (read_project, write_project, _) =
+        use_local_storage::<Option<PathBuf>, PathBufCodec>("project");
+    let sidebar = create_rw_signal(true);
+    let problems = create_rw_signal(Vec::new());
+    let original = create_rw_signal(None);
+    let unsaved = create_memo(move |_| {
+        original.with(|original| match original {
+            Some(Some(original)) => text.with(|text| text != original),
+            Some(None) => text.with(|text| !text.is_empty()),
+            None => false,
+        })
+    });
     let save = create_action(move |save_as| {
         let save_as: bool = *save_as;
         async move {
-            let Some(path) = Inter::save_file(
+            let (path, error) = Inter::save_file(
                 text.get_untracked(),
                 read_save_path.get_untracked().filter(|_| !save_as),
             )
-            .await
-            else {
+            .await;
+            if let Some(error) = error {
+                report(problems, "saving", &error);
+            }
+            let Some(path) = path else {
                 return;
             };
             write_save_path(Some(path));
-            unsaved.set(false);
+            original.set(Some(Some(text.get_untracked())));
         }
     });
     let selection = create_rw_signal(None);
-    provide_context(Context {
+    let settings = create_rw_signal(Settings::default());
+    create_effect(move |_| {
+        let css = settings.with(Settings::css);
+        leptos::document()
+            .document_element()
+            .unwrap()
+            .set_attribute("style", &css)
+            .unwrap();
+    });
+    let settings_loaded = create_rw_signal(false);
+    create_effect(move |_| {
+        let settings = settings();
+        if !settings_loaded.get_untracked() {
+            return;
+        }
+        spawn_local(async move {
+            for problem in Inter::set_settings(settings).await {
+                report(problems, "settings", &problem);
+            }
+        });
+    });
+    spawn_local(async move {
+        let (loaded, loading_problems) = Inter::get_settings().await;
+        for problem in loading_problems {
+            report(problems, "settings", &problem);
+        }
+        settings_loaded.set(true);
+        settings.set(loaded);
+        Inter::listen("settings-changed", move |changed: Settings| {
+            if settings.with_untracked(|settings| *settings != changed) {
+                settings.set(changed);
+            }
+        })
+        .await;
+    });
+    let cursor = create_rw_signal(0);
+    let text_area: NodeRef<Textarea> = create_node_ref();
+    let view_height = create_rw_signal(0.);
+    let measure_view = move || {
+        if let Some(text_area) = text_area.get_untracked() {
+            view_height.set(f64::from(text_area.client_height()));
+        }
+    };
+    create_effect(move |_| {
+        if text_area().is_some() {
+            measure_view();
+        }
+    });
+    window_event_listener(resize, move |_| measure_view());
+    let typewriter_padding = create_memo(move |_| {
+        let height = view_height();
+        settings.with(|settings| settings.typewriter).then(|| {
+            let position = settings.with(|settings| settings.typewriter_position);
+            (height * position, height * (1. - position))
+        })
+    });
+    let matches = create_rw_signal(Vec::new());
+    let current_match = create_rw_signal(None);
+    let history = create_rw_signal(History::default());
+    let top = create_rw_signal(0);
+    let reveal = create_rw_signal(None);
+    let folds = create_rw_signal(Folds::default());
+    let hidden = create_memo(move |_| text.with(|text| folds.with(|folds| folds.hidden(text))));
+    let shown = create_memo(move |_| text.with(|text| hidden.with(|hidden| project(text, hidden))));
+    let checked = create_rw_signal(Checked::default());
+    let misspelled = create_memo(move |_| {
+        if !settings.with(|settings| settings.spell_check) {
+            return Vec::new();
+        }
+        text.with(|text| checked.with(|checked| spelling::misspelled(text, checked)))
+    });
+    let lints = create_memo(move |_| {
+        text.with(|text| settings.with(|settings| lint(text, settings.style)))
+    });
+    let writing = create_rw_signal(Writing::new(Date::now()));
+    let sprint = create_rw_signal(SprintState::default());
+    let diff_base = create_rw_signal(None);
+    let diff = create_memo(move |_| {
+        diff_base
+            .with(|base| {
+                let base = match base.as_ref()? {
+                    DiffBase::Saved => original()?.unwrap_or_default(),
+                    DiffBase::Snapshot(snapshot) => snapshot.clone(),
+                };
+                Some(text.with(|text| hunks(&base, text, Granularity::Words)))
+            })
+            .unwrap_or_default()
+    });
+    let git = create_rw_signal(None::<GitStatus>);
+    let git_changes = create_memo(move |_| {
+        git.with(|git| {
+            git.as_ref()
+                .map(|git| text.with(|text| git.changes(text)))
+                .unwrap_or_default()
+        })
+    });
+    let context = Context {
         text,
         save_path: (read_save_path, write_save_path),
+        project: (read_project, write_project),
+        sidebar,
+        notes: create_rw_signal(BTreeMap::new()),
+        opening: create_rw_signal(None),
+        problems,
         save,
         unsaved,
         selection,
-    });
+        cursor,
+        typewriter_padding,
+        matches,
+        current_match,
+        text_area,
+        history,
+        top,
+        reveal,
+        folds,
+        hidden,
+        shown,
+        settings,
+        checked,
+        misspelled,
+        lints,
+        writing,
+        sprint,
+        diff_base,
+        diff,
+        git,
+        git_changes,
+    };
+    provide_context(context);
+    check_spelling(&context);
+    track_writing(&context);
+    track_git(&context);
+    track_links(&context);
+    track_search_index(&context);
     #[cfg(not(debug_assertions))]
     {
         use leptos::ev::contextmenu;
@@ -189,13 +910,23 @@ pub fn App() -> impl IntoView {
             event.prevent_default();
         });
     }
-    let original = create_rw_signal(None);
     create_effect(move |_| {
         let read_save_path = read_save_path();
         spawn_local(async move {
-            original.set(Some(Inter::load_file(read_save_path).await.0));
+            let data = match read_save_path {
+                Some(path) => Inter::load_file(path).await,
+                None => None,
+            };
+            original.set(Some(data));
         });
     });
+    let pending_input = create_rw_signal(None);
+    let track_cursor = move || {
+        let end = context.selected_range().end;
+        if end != cursor.get_untracked() {
+            cursor.set(end);
+        }
+    };
     let overlay = create_node_ref();
     let sync = move |event| {
         let overlay: HtmlElement<Div> = overlay().unwrap();
@@ -208,59 +939,136 @@ pub fn App() -> impl IntoView {
             gap=6
         >
             <div data-tauri-drag-region class="absolute inset-x-0 top-0 z-30 h-12" />
-            <div class="relative size-full">
-                <Overlay overlay=overlay />
-                <textarea
-                    class="absolute top-0 left-0 z-20 pt-20 overflow-y-auto text-sm text-transparent break-all whitespace-pre-wrap bg-transparent outline-none resize-none size-full overscroll-none px-80 selection:bg-transparent"
-                    prop:value=text
-                    autocorrect="off"
-                    on:input=move |event| {
-                        text.set(event_target_value(&event));
-                        unsaved
-                            .set(original.get_untracked().flatten() != Some(text.get_untracked()));
-                        sync(event);
-                    }
-                    on:select=move |event| {
-                        let text_area: HtmlTextAreaElement = event_target(&event);
-                        selection
-                            .set(
-                                Some((
-                                    text_area.selection_start().unwrap().unwrap() as usize,
-                                    text_area.selection_end().unwrap().unwrap() as usize,
-                                )),
-                            );
-                    }
-                    on:mousedown=move |_| {
-                        selection.set(None);
-                    }
-                    on:keydown=move |event| {
-                        selection.set(None);
-                        if event.key() == "Tab" {
-                            event.prevent_default();
-                            let text_area = event_target::<HtmlTextAreaElement>(&event);
-                            let selection = selection
-                                .get_untracked()
-                                .unwrap_or_else(|| (
-                                    text_area.selection_start().unwrap().unwrap() as usize,
-                                    text_area.selection_end().unwrap().unwrap() as usize,
-                                ));
-                            text.update(|text| {
-                                *text = format!(
-                                    "{}\t{}",
-                                    &text[0..selection.0],
-                                    &text[selection.1..],
-                                );
-                            });
-                            let position = selection.0 + 1;
-                            #[allow(clippy::cast_possible_truncation)]
+            <div class="flex min-h-0 size-full">
+                <Sidebar />
+                <div class="relative size-full">
+                    <Overlay overlay=overlay />
+                    <textarea
+                        ref=text_area
+                        class="absolute top-0 left-0 z-20 pt-20 overflow-y-auto text-editor text-transparent break-words whitespace-pre-wrap bg-transparent outline-none resize-none size-full overscroll-none px-column selection:bg-transparent"
+                        style:padding-top=move || typewriter_padding().map(|(top, _)| format!("{top}px"))
+                        style:padding-bottom=move || {
+                            typewriter_padding().map(|(_, bottom)| format!("{bottom}px"))
+                        }
+                        prop:value=shown
+                        autocorrect="off"
+                        on:beforeinput=move |event| {
+                            // Cutting, dragging, pasting over a selection and undoing delete without a
+                            // key to block.
+                            if sprint.with_untracked(SprintState::is_strict)
+                                && input_deletes(&event.input_type(), !context.selected_range().is_empty())
                             {
-                                text_area.set_selection_start(Some(position as u32)).unwrap();
-                                text_area.set_selection_end(Some(position as u32)).unwrap();
+                                event.prevent_default();
+                                return;
+                            }
+                            let kind = match event.input_type().as_str() {
+                                "historyUndo" => {
+                                    event.prevent_default();
+                                    context.undo();
+                                    return;
+                                }
+                                "historyRedo" => {
+                                    event.prevent_default();
+                                    context.redo();
+                                    return;
+                                }
+                                "insertText" => Kind::Typing,
+                                "deleteContentBackward" | "deleteContentForward" => Kind::Deleting,
+                                _ => Kind::Other,
+                            };
+                            pending_input.set(Some((kind, context.selected_range())));
+                        }
+                        on:input=move |event| {
+                            let new_shown = event_target_value(&event);
+                            if let Some(shown_edit) = shown.with_untracked(|shown| Edit::diff(shown, &new_shown)) {
+                                let (start, end) = hidden
+                                    .with_untracked(|hidden| {
+                                        let end = shown_edit.offset + shown_edit.removed.len();
+                                        (
+                                            to_text(hidden, shown_edit.offset, !shown_edit.removed.is_empty()),
+                                            to_text(hidden, end, false),
+                                        )
+                                    });
+                                let edit = Edit {
+                                    offset: start,
+                                    removed: text.with_untracked(|text| text[start..end].to_string()),
+                                    inserted: shown_edit.inserted,
+                                };
+                                let (kind, before) = pending_input
+                                    .get_untracked()
+                                    .unwrap_or((Kind::Other, start..start));
+                                let after = start + edit.inserted.len();
+                                context.apply(&edit);
+                                history
+                                    .update(|history| {
+                                        history.record(edit, before, after..after, kind, Date::now());
+                                    });
+                                if shown.with_untracked(|shown| *shown != new_shown) {
+                                    text.with_untracked(|text| folds.update(|folds| folds.unfold_at(text, after)));
+                                    context.select_range(after..after);
+                                }
                             }
+                            pending_input.set(None);
+                            track_cursor();
+                            sync(event);
+                        }
+                        on:select=move |_| {
+                            selection.set(Some(context.selected_range()));
+                            track_cursor();
+                        }
+                        on:mouseup=move |_| track_cursor()
+                        on:click=move |event| {
+                            let held = if is_mac() { event.meta_key() } else { event.ctrl_key() };
+                            if !held {
+                                return;
+                            }
+                            let offset = context.selected_range().end;
+                            if let Some(name) = text.with_untracked(|text| link_at(text, offset)) {
+                                follow_link(&context, &name);
+                            }
+                        }
+                        on:keyup=move |_| track_cursor()
+                        on:mousedown=move |_| {
+                            selection.set(None);
+                            history.update(History::seal);
                         }
-                    }
-                    on:scroll=sync
-                />
+                        on:keydown=move |event| {
+                            if sprint.with_untracked(SprintState::is_strict) {
+                                let selected = !context.selected_range().is_empty();
+                                let modified = event.ctrl_key() || event.meta_key() || event.alt_key();
+                                if deletes(&event.key(), selected, modified) {
+                                    event.prevent_default();
+                                    return;
+                                }
+                            }
+                            selection.set(None);
+                            if event.key().starts_with("Arrow") {
+                                history.update(History::seal);
+                            }
+                            if event.key() == "Tab" {
+                                event.prevent_default();
+                                let selection = context.selected_range();
+                                let selection = text
+                                    .with_untracked(|text| {
+                                        floor_grapheme_boundary(text, selection.start)
+                                            ..ceil_grapheme_boundary(text, selection.end)
+                                    });
+                                let removed = text.with_untracked(|text| text[selection.clone()].to_string());
+                                let position = selection.start + 1;
+                                context
+                                    .edit(
+                                        Edit {
+                                            offset: selection.start,
+                                            removed,
+                                            inserted: "\t".to_string(),
+                                        },
+                                        position..position,
+                                    );
+                            }
+                        }
+                        on:scroll=sync
+                    />
+                </div>
             </div>
             <StatusBar />
         </Vertical>
@@ -270,125 +1078,297 @@ pub fn App() -> impl IntoView {
 #[component]
 #[allow(clippy::too_many_lines)]
 fn Overlay(overlay: NodeRef<Div>) -> impl IntoView {
+    let context = use_context::<Context>().unwrap();
     let Context {
-        text, selection, ..
-    } = use_context().unwrap();
+        text,
+        selection,
+        matches,
+        current_match,
+        text_area,
+        top,
+        reveal,
+        folds,
+        hidden,
+        shown,
+        cursor,
+        typewriter_padding,
+        settings,
+        misspelled,
+        lints,
+        diff,
+        git_changes,
+        ..
+    } = context;
     let char: NodeRef<Div> = create_node_ref();
     let width_measure: NodeRef<Div> = create_node_ref();
-    let chars_per_line = move || {
-        width_measure().unwrap().get_bounding_client_rect().width()
-            / char().unwrap().get_bounding_client_rect().width()
+    let layout = move || {
+        settings.track();
+        #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+        let columns = (width_measure().unwrap().get_bounding_client_rect().width()
+            / char().unwrap().get_bounding_client_rect().width()) as usize;
+        shown.with(|shown| Layout::new(shown, columns))
     };
-    let wrapped_lengths = move || {
-        text()
-            .lines()
-            .flat_map(|line| {
-                if line.is_empty() {
-                    return vec![(true, 0)];
-                }
-                #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
-                let chunks = line.chars().chunks(chars_per_line() as usize);
-                let count = chunks.clone().into_iter().count();
-                chunks
-                    .into_iter()
-                    .enumerate()
-                    .map(|(index, line)| (index == count - 1, line.count()))
-                    .collect_vec()
-            })
-            .collect_vec()
+    let row_of = move |offset: usize| {
+        let offset = hidden.with(|hidden| to_shown(hidden, offset));
+        shown.with(|shown| layout().position(shown, offset).0)
+    };
+    create_effect(move |_| {
+        let range = matches.with_untracked(|matches| matches.get(current_match()?).cloned())?;
+        let line = untrack(|| row_of(range.start));
+        let text_area = text_area.get_untracked()?;
+        let line_height = char.get_untracked()?.get_bounding_client_rect().height();
+        #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+        text_area.set_scroll_top(
+            (line as f64).mul_add(line_height, f64::from(text_area.client_height()) / -2.) as i32,
+        );
+        overlay
+            .get_untracked()?
+            .set_scroll_top(text_area.scroll_top());
+        Some(())
+    });
+    create_effect(move |_| {
+        let offset = reveal()?;
+        let line = untrack(|| row_of(offset));
+        let text_area = text_area.get_untracked()?;
+        let line_height = char.get_untracked()?.get_bounding_client_rect().height();
+        #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+        text_area.set_scroll_top((line as f64 * line_height) as i32);
+        overlay
+            .get_untracked()?
+            .set_scroll_top(text_area.scroll_top());
+        reveal.set(None);
+        Some(())
+    });
+    create_effect(move |_| {
+        let offset = cursor();
+        shown.track();
+        typewriter_padding()?;
+        let line = untrack(|| row_of(offset));
+        let text_area = text_area.get_untracked()?;
+        let line_height = char.get_untracked()?.get_bounding_client_rect().height();
+        #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+        text_area.set_scroll_top((line as f64 * line_height) as i32);
+        overlay
+            .get_untracked()?
+            .set_scroll_top(text_area.scroll_top());
+        Some(())
+    });
+    let update_top = move |_| {
+        let (Some(overlay), Some(char)) = (overlay.get_untracked(), char.get_untracked()) else {
+            return;
+        };
+        let line_height = char.get_bounding_client_rect().height();
+        #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+        let line = (f64::from(overlay.scroll_top()) / line_height) as usize;
+        let offset = untrack(|| {
+            let layout = layout();
+            let start = layout
+                .rows()
+                .get(line)
+                .or_else(|| layout.rows().last())
+                .map_or(0, |row| row.range.start);
+            hidden.with(|hidden| to_text(hidden, start, false))
+        });
+        if offset != top.get_untracked() {
+            top.set(offset);
+        }
     };
     view! {
-        <div class="invisible inline text-sm" ref=char>
+        <div class="invisible inline-block text-editor" ref=char>
             "h"
         </div>
         <div
-            class="absolute top-0 left-0 pt-20 overflow-y-auto [&_*]:text-sm break-all whitespace-pre-wrap px-80 size-full"
+            class="absolute top-0 left-0 pt-20 overflow-y-auto text-editor [&_*]:text-editor break-words whitespace-pre-wrap px-column size-full"
+            style:padding-top=move || typewriter_padding().map(|(top, _)| format!("{top}px"))
             ref=overlay
+            on:scroll=update_top
         >
             <div class="relative size-full" ref=width_measure>
                 <div class="absolute top-0 size-full">
                     {move || {
-                        let char_to_position = move |char: usize| {
-                            let mut first = 0;
-                            let mut last = 0;
-                            for (index, (last_in_chunk, length)) in wrapped_lengths()
-                                .into_iter()
-                                .enumerate()
-                            {
-                                last += length;
-                                if !last_in_chunk {
-                                    last -= 1;
-                                }
-                                if last == char {
-                                    return Some((index, last - first))
-                                } else if char.max(last) == char {
-                                    last += 1;
-                                    first = last;
+                        let layout = layout();
+                        let shown = shown();
+                        let hidden = hidden();
+                        let current_match = current_match();
+                        let position = |offset| layout.position(&shown, to_shown(&hidden, offset));
+                        let ranges = selection()
+                            .map(|range| (range, "bg-highlight"))
+                            .into_iter()
+                            .chain(
+                                matches()
+                                    .into_iter()
+                                    .enumerate()
+                                    .map(|(index, range)| (
+                                        range,
+                                        if Some(index) == current_match {
+                                            "bg-fade"
+                                        } else {
+                                            "bg-fade/40"
+                                        },
+                                    )),
+                            )
+                            .chain(
+                                lints()
+                                    .into_iter()
+                                    .map(|lint| (lint.range, "border-b-2 border-dashed border-accent rounded-none")),
+                            )
+                            .chain(
+                                misspelled()
+                                    .into_iter()
+                                    .map(|range| (range, "border-b-2 border-dotted border-error rounded-none")),
+                            )
+                            .chain(
+                                diff()
+                                    .into_iter()
+                                    .map(|hunk| {
+                                        if hunk.range.is_empty() {
+                                            (hunk.range, "border-l-2 border-error rounded-none")
+                                        } else {
+                                            (hunk.range, "bg-accent/30")
+                                        }
+                                    }),
+                            )
+                            .map(|(range, class)| (position(range.start), position(range.end), class))
+                            .collect_vec();
+                        let headings = text
+                            .with(|text| headings(text))
+                            .into_iter()
+                            .filter(|heading| !hidden.iter().any(|range| range.contains(&heading.offset)))
+                            .map(|heading| {
+                                let folded = folds.with(|folds| folds.is_folded(heading.offset));
+                                (position(heading.offset), heading.offset, folded)
+                            })
+                            .collect_vec();
+                        let fold_points = hidden
+                            .iter()
+                            .map(|range| position(range.start))
+                            .collect_vec();
+                        let hunks = diff();
+                        let hunk_rows = hunks
+                            .iter()
+                            .map(|hunk| position(hunk.range.start).0)
+                            .collect_vec();
+                        let line_changes = git_changes()
+                            .iter()
+                            .map(|hunk| {
+                                let end = if hunk.range.is_empty() {
+                                    hunk.range.end
                                 } else {
-                                    return Some((index, char - first))
-                                }
-                            }
-                            None
-                        };
-                        let (start, end) = selection()
-                            .and_then(|(start, end)| Some((
-                                char_to_position(start)?,
-                                char_to_position(end)?,
-                            )))?;
-                        Some(
-                            wrapped_lengths()
-                                .into_iter()
-                                .enumerate()
-                                .map(|(index, (_, length))| {
-                                    view! {
-                                        <div
-                                            class="h-5"
-                                            style:padding-left=move || {
-                                                format!("{}ch", if start.0 == index { start.1 } else { 0 })
-                                            }
-                                        >
+                                    hunk.range.end - 1
+                                };
+                                (position(hunk.range.start).0..=position(end).0, LineChange::of(hunk))
+                            })
+                            .collect_vec();
+                        layout
+                            .rows()
+                            .iter()
+                            .enumerate()
+                            .map(|(index, row)| {
+                                let toggle = headings
+                                    .iter()
+                                    .find(|((row, column), ..)| *row == index && *column == 0)
+                                    .map(|&(_, offset, folded)| {
+                                        view! {
                                             <div
-                                                class="h-full rounded bg-highlight"
-                                                style:width=move || {
-                                                    format!(
-                                                        "{}ch",
-                                                        if start.0 == end.0 {
-                                                            if start.0 == index { end.1 - start.1 } else { 0 }
-                                                        } else if index > start.0 && index < end.0 {
-                                                            #[allow(
-                                                                clippy::cast_sign_loss,
-                                                                clippy::cast_possible_truncation
-                                                            )]
-                                                            {
-                                                                chars_per_line() as usize
-                                                                    - if start.0 == index { start.1 } else { 0 }
-                                                            }
-                                                        } else if index == start.0 {
-                                                            length - start.1
-                                                        } else if index == end.0 {
-                                                            end.1
-                                                        } else {
-                                                            0
-                                                        },
-                                                    )
+                                                class="absolute top-0 z-30 w-12 h-full cursor-pointer -left-16"
+                                                title=if folded { "Unfold" } else { "Fold" }
+                                                on:mousedown=|event| event.prevent_default()
+                                                on:click=move |_| {
+                                                    context.update_folds(|folds, text| folds.toggle(text, offset));
                                                 }
                                             ></div>
-                                        </div>
-                                    }
-                                })
-                                .collect_view(),
-                        )
+                                        }
+                                    });
+                                let ellipsis = fold_points
+                                    .iter()
+                                    .find(|(row, _)| *row == index)
+                                    .map(|(_, column)| {
+                                        view! {
+                                            <div
+                                                class="absolute h-full text-fade"
+                                                style:left=format!("{column}ch")
+                                            >
+                                                " …"
+                                            </div>
+                                        }
+                                    });
+                                let changes = hunks
+                                    .iter()
+                                    .zip(&hunk_rows)
+                                    .filter(|(_, row)| **row == index)
+                                    .map(|(hunk, _)| hunk.clone())
+                                    .collect_vec();
+                                let revert_marker = (!changes.is_empty())
+                                    .then(|| {
+                                        let title = changes
+                                            .iter()
+                                            .map(|hunk| {
+                                                let old = hunk.old.trim();
+                                                if old.is_empty() {
+                                                    "Revert: remove the added text".to_string()
+                                                } else {
+                                                    format!("Revert: restore \u{201c}{old}\u{201d}")
+                                                }
+                                            })
+                                            .join("\n");
+                                        view! {
+                                            <div
+                                                class="absolute top-0 z-30 w-1 h-full rounded cursor-pointer -left-3 bg-accent"
+                                                title=title
+                                                on:mousedown=|event| event.prevent_default()
+                                                on:click=move |_| context.revert_hunks(&changes)
+                                            ></div>
+                                        }
+                                    });
+                                let line_change = line_changes
+                                    .iter()
+                                    .find(|(rows, _)| rows.contains(&index))
+                                    .map(|&(_, change)| {
+                                        view! {
+                                            <div
+                                                class=format!("absolute top-0 -left-1.5 {}", change.class())
+                                                title=change.title()
+                                            ></div>
+                                        }
+                                    });
+                                view! {
+                                    <div class="relative h-line">
+                                        {ranges
+                                            .iter()
+                                            .filter(|(start, end, _)| {
+                                                (start.0..=end.0).contains(&index)
+                                            })
+                                            .map(|&(start, end, class)| {
+                                                let from = if start.0 == index { start.1 } else { 0 };
+                                                let to = if end.0 == index { end.1 } else { row.width };
+                                                view! {
+                                                    <div
+                                                        class=format!("absolute h-full rounded {class}")
+                                                        style:left=format!("{from}ch")
+                                                        style:width=format!("{}ch", to.saturating_sub(from))
+                                                    ></div>
+                                                }
+                                            })
+                                            .collect_view()}
+                                        {toggle}
+                                        {ellipsis}
+                                        {revert_marker}
+                                        {line_change}
+                                    </div>
+                                }
+                            })
+                            .collect_view()
                     }}
                 </div>
                 <div class="absolute top-0 z-10 size-full">
                     {move || {
-                        let document = match Document::parse(&(text() + " ")) {
+                        let document = match Document::parse(&(shown() + " ")) {
                             Ok((remaining, mut document)) => {
                                 document.push(Segment::Text(remaining.to_string()));
-                                document.into_view()
+                                document_view(document)
                             }
                             Err(_) => {
-                                text()
+                                shown()
                                     .lines()
                                     .map(|line| {
                                         view! {
@@ -406,202 +1386,589 @@ fn Overlay(overlay: NodeRef<Div>) -> impl IntoView {
                         };
                         document
                     }}
+                    {move || {
+                        typewriter_padding()
+                            .map(|(_, bottom)| {
+                                view! { <div style:height=format!("{bottom}px")></div> }
+                            })
+                    }}
                 </div>
+                <Show when=move || settings.with(|settings| settings.focus)>
+                    <div class="absolute top-0 z-20 pointer-events-none size-full">
+                        {move || {
+                            let layout = layout();
+                            let (start, end) = text
+                                .with(|text| {
+                                    let focused = focused_range(
+                                        text,
+                                        cursor(),
+                                        settings.with(|settings| settings.focus_granularity),
+                                    );
+                                    let position = |offset| {
+                                        shown.with(|shown| layout.position(shown, hidden.with(|hidden| to_shown(hidden, offset))))
+                                    };
+                                    (position(focused.start), position(focused.end))
+                                });
+                            layout
+                                .rows()
+                                .iter()
+                                .enumerate()
+                                .map(|(index, _)| {
+                                    let mut dimmed = Vec::new();
+                                    if index < start.0 || index > end.0 {
+                                        dimmed.push((0, None));
+                                    }
+                                    if index == start.0 && start.1 > 0 {
+                                        dimmed.push((0, Some(start.1)));
+                                    }
+                                    if index == end.0 {
+                                        dimmed.push((end.1, None));
+                                    }
+                                    view! {
+                                        <div class="relative h-line">
+                                            {dimmed
+                                                .into_iter()
+                                                .map(|(from, to): (usize, Option<usize>)| {
+                                                    view! {
+                                                        <div
+                                                            class="absolute h-full bg-background/70"
+                                                            class=("right-0", to.is_none())
+                                                            style:left=format!("{from}ch")
+                                                            style:width=to.map(|to| format!("{}ch", to - from))
+                                                        ></div>
+                                                    }
+                                                })
+                                                .collect_view()}
+                                        </div>
+                                    }
+                                })
+                                .collect_view()
+                        }}
+                    </div>
+                </Show>
             </div>
         </div>
     }
 }
+
 #[component]
 #[allow(clippy::too_many_lines)]
 fn StatusBar() -> impl IntoView {
-    macro_rules! shortcut {
-        (c-sh-$char:expr; $name:literal => $action:block) => {
-            Shortcut {
-                shift: true,
-                char: $char,
+    macro_rules! command {
+        ($id:ident: $binding:literal; $name:literal => $action:block) => {
+            Command {
+                id: stringify!($id),
+                binding: $binding,
                 name: $name,
                 action: Callback::new(move |()| $action),
             }
         };
-        (c-$char:expr; $name:literal => $action:block) => {
-            Shortcut {
-                shift: false,
-                char: $char,
-                name: $name,
-                action: Callback::new(move |()| $action),
-            }
-        };
-    }
-    #[derive(Clone, Copy)]
-    struct Shortcut {
-        shift: bool,
-        char: char,
-        name: &'static str,
-        action: Callback<()>,
     }
+    let context = use_context::<Context>().unwrap();
     let Context {
         save_path: (read_save_path, write_save_path),
         save,
         text,
         unsaved,
         selection,
-    } = use_context().unwrap();
+        matches,
+        current_match,
+        text_area,
+        cursor,
+        lints,
+        ..
+    } = context;
     let command_pressed = RwSignal::new(false);
 
-    let find_text = create_rw_signal(String::new());
-    let matches = create_rw_signal(Vec::new());
-    let current_match_index = create_rw_signal(0);
+    let query = create_rw_signal(Query::default());
+    let replacement = create_rw_signal(String::new());
+    let invalid_query = create_rw_signal(false);
     let show_find_input = create_rw_signal(false);
+    let find_input: NodeRef<Input> = create_node_ref();
+    let show_folder_search = create_rw_signal(false);
+    let show_index_search = create_rw_signal(false);
+    let show_palette = create_rw_signal(false);
+    let show_outline = create_rw_signal(false);
+    let show_settings = create_rw_signal(false);
+    let show_spelling = create_rw_signal(false);
+    let show_statistics = create_rw_signal(false);
+    let show_writing_history = create_rw_signal(false);
+    let show_sprint = create_rw_signal(false);
+    let show_snapshots = create_rw_signal(false);
+    let show_git_commit = create_rw_signal(false);
+    let show_git_log = create_rw_signal(false);
+    let show_manuscript = create_rw_signal(false);
+    let show_backlinks = create_rw_signal(false);
+    let show_problems = create_rw_signal(false);
+    let keymap = create_rw_signal(Keymap::default());
 
     create_effect(move |_| {
         spawn_local({
             async move {
-                let (Some(data), _) = Inter::load_file(read_save_path.get_untracked()).await else {
+                let Some(path) = read_save_path.get_untracked() else {
                     return;
                 };
-                text.set(data);
+                let Some(data) = Inter::load_file(path).await else {
+                    return;
+                };
+                context.load(data);
             }
         });
     });
     window_event_listener(keydown, move |event| {
-        if event.meta_key() {
+        if command_held(&event) {
             command_pressed.set(true);
-            show_find_input.set(false);
-            find_text.set(String::new());
-            matches.set(Vec::new());
-            current_match_index.set(0);
         }
     });
     window_event_listener(keyup, move |_| {
         command_pressed.set(false);
     });
 
-    let shortcuts = [
-        shortcut!(
-            c-'f';
+    let load_keymap = move || {
+        spawn_local(async move {
+            let (overrides, error) = Inter::load_keymap().await;
+            let problems = keymap.try_update(|keymap| keymap.set_overrides(&overrides));
+            for problem in error.into_iter().chain(problems.into_iter().flatten()) {
+                report(context.problems, "keymap", &problem);
+            }
+        });
+    };
+
+    // Closing the window asks to quit the same way, so no words written are lost.
+    let quit = move || {
+        spawn_local(async move {
+            log_writing(context.writing, context.problems).await;
+            Inter::quit().await;
+        });
+    };
+    spawn_local(async move {
+        Inter::listen("close-requested", move |()| quit()).await;
+    });
+
+    let commands = [
+        command!(
+            command_palette: "c-sh-P";
+            "Command palette" => {
+                show_palette.set(true);
+            }
+        ),
+        command!(
+            find: "c-F";
             "Find" => {
                 show_find_input.set(true);
-                find_text.set(String::new());
-                matches.set(Vec::new());
-                current_match_index.set(0);
+                let find_input = find_input.get_untracked().unwrap();
+                find_input.focus().unwrap();
+                find_input.select();
+            }
+        ),
+        command!(
+            search_folder: "c-sh-F";
+            "Search folder" => {
+                show_folder_search.set(true);
+            }
+        ),
+        command!(
+            search_project: "c-K c-S";
+            "Search project" => {
+                show_index_search.set(true);
+            }
+        ),
+        command!(
+            outline: "c-sh-O";
+            "Outline" => {
+                show_outline.set(true);
+            }
+        ),
+        command!(
+            settings: "c-,";
+            "Settings" => {
+                show_settings.set(true);
+            }
+        ),
+        command!(
+            spelling: "c-.";
+            "Spelling suggestions" => {
+                show_spelling.set(true);
+            }
+        ),
+        command!(
+            statistics: "c-K c-I";
+            "Statistics" => {
+                show_statistics.set(true);
+            }
+        ),
+        command!(
+            writing_history: "c-K c-H";
+            "Writing history" => {
+                show_writing_history.set(true);
+            }
+        ),
+        command!(
+            sprint: "c-K c-W";
+            "Writing sprint" => {
+                show_sprint.set(true);
+            }
+        ),
+        command!(
+            snapshots: "c-K c-V";
+            "Snapshot history" => {
+                show_snapshots.set(true);
+            }
+        ),
+        command!(
+            compare_with_saved: "c-K c-D";
+            "Toggle comparing with the saved file" => {
+                context
+                    .diff_base
+                    .update(|base| {
+                        *base = match base {
+                            Some(DiffBase::Saved) => None,
+                            _ => Some(DiffBase::Saved),
+                        };
+                    });
+            }
+        ),
+        command!(
+            revert_change: "";
+            "Revert the change at the caret" => {
+                let cursor = cursor.get_untracked();
+                let hunk = context
+                    .diff
+                    .with_untracked(|diff| {
+                        diff.iter().find(|hunk| hunk.range.start <= cursor && cursor <= hunk.range.end).cloned()
+                    });
+                if let Some(hunk) = hunk {
+                    context.revert_hunks(&[hunk]);
+                }
+            }
+        ),
+        command!(
+            git_commit: "c-K c-G";
+            "Commit the file" => {
+                show_git_commit.set(true);
+            }
+        ),
+        command!(
+            git_log: "c-K c-sh-G";
+            "Git log of the file" => {
+                show_git_log.set(true);
+            }
+        ),
+        command!(
+            toggle_spell_check: "";
+            "Toggle spell checking" => {
+                context.settings.update(|settings| settings.spell_check = !settings.spell_check);
+            }
+        ),
+        command!(
+            toggle_fold: "c-K c-L";
+            "Fold or unfold section" => {
+                let cursor = context.selected_range().start;
+                context
+                    .update_folds(|folds, text| {
+                        let headings = headings(text);
+                        if let Some(index) = current_section(&headings, cursor) {
+                            folds.toggle(text, headings[index].offset);
+                        }
+                    });
+            }
+        ),
+        command!(
+            fold_all: "c-K c-0";
+            "Fold all sections" => {
+                context.update_folds(Folds::fold_all);
+            }
+        ),
+        command!(
+            unfold_all: "c-K c-J";
+            "Unfold all sections" => {
+                context.update_folds(|folds, _| *folds = Folds::default());
+            }
+        ),
+        command!(
+            toggle_focus: "c-K c-F";
+            "Toggle focus mode" => {
+                context.settings.update(|settings| settings.focus = !settings.focus);
+            }
+        ),
+        command!(
+            switch_focus_granularity: "";
+            "Switch focus between sentence and paragraph" => {
+                context
+                    .settings
+                    .update(|settings| settings.focus_granularity = settings.focus_granularity.other());
+            }
+        ),
+        command!(
+            toggle_typewriter: "c-K c-T";
+            "Toggle typewriter scrolling" => {
+                context.settings.update(|settings| settings.typewriter = !settings.typewriter);
+            }
+        ),
+        command!(
+            raise_typewriter: "";
+            "Keep the caret higher with typewriter scrolling" => {
+                context
+                    .settings
+                    .update(|settings| {
+                        settings.typewriter_position = (settings.typewriter_position - 0.1).max(0.1);
+                    });
+            }
+        ),
+        command!(
+            lower_typewriter: "";
+            "Keep the caret lower with typewriter scrolling" => {
+                context
+                    .settings
+                    .update(|settings| {
+                        settings.typewriter_position = (settings.typewriter_position + 0.1).min(0.9);
+                    });
             }
         ),
-        shortcut!(
-            c-'n';
+        command!(
+            new: "c-N";
             "New" => {
-                text.set(String::new());
+                context.load(String::new());
                 write_save_path(None);
             }
         ),
-        shortcut!(
-            c-'o';
-            "Open" => {
+        command!(
+            open: "c-O";
+            "Open project folder" => {
                 spawn_local(async move {
-                    let (Some(data), Some(path)) = Inter::load_file(None).await else {
+                    let Some(root) = Inter::pick_project().await else {
                         return;
                     };
-                    text.set(data);
-                    write_save_path(Some(path));
+                    (context.project.1)(Some(root));
+                    context.sidebar.set(true);
                     command_pressed.set(false);
                 });
             }
         ),
-        shortcut!(
-            c-'s';
+        command!(
+            toggle_sidebar: "c-K c-B";
+            "Toggle the project sidebar" => {
+                context.sidebar.update(|sidebar| *sidebar = !*sidebar);
+            }
+        ),
+        command!(
+            follow_link: "";
+            "Follow the link at the caret" => {
+                let cursor = cursor.get_untracked();
+                if let Some(name) = text.with_untracked(|text| link_at(text, cursor)) {
+                    follow_link(&context, &name);
+                }
+            }
+        ),
+        command!(
+            backlinks: "c-K c-sh-B";
+            "Backlinks" => {
+                show_backlinks.set(true);
+            }
+        ),
+        command!(
+            compile_manuscript: "c-K c-M";
+            "Compile the project into a manuscript" => {
+                show_manuscript.set(true);
+            }
+        ),
+        command!(
+            close_project: "";
+            "Close the project folder" => {
+                (context.project.1)(None);
+            }
+        ),
+        command!(
+            undo: "c-Z";
+            "Undo" => {
+                context.undo();
+            }
+        ),
+        command!(
+            redo: "c-sh-Z";
+            "Redo" => {
+                context.redo();
+            }
+        ),
+        command!(
+            save: "c-S";
             "Save" => {
                 save.dispatch(false);
             }
         ),
-        shortcut!(
-            c-sh-'s';
+        command!(
+            save_as: "c-sh-S";
             "Save as" => {
                 save.dispatch(true);
             }
         ),
-        shortcut!(
-            c-'q';
+        command!(
+            reload_keymap: "";
+            "Reload keymap" => {
+                load_keymap();
+            }
+        ),
+        command!(
+            quit: "c-Q";
             "Quit" => {
-                spawn_local(Inter::quit());
+                quit();
             }
         ),
     ];
+    keymap.set(Keymap::new(
+        commands.map(|Command { id, binding, .. }| (id, binding)),
+    ));
+    load_keymap();
     window_event_listener(keydown, move |event| {
-        for Shortcut {
-            shift,
-            char,
-            action,
-            ..
-        } in shortcuts
-        {
-            if !command_pressed() {
-                continue;
-            }
-            if shift && !event.shift_key() {
-                continue;
-            }
-            if event.key() != char.to_string() {
-                continue;
+        let Some(keystroke) = Keystroke::from_event(&event) else {
+            return;
+        };
+        match keymap.try_update(|keymap| keymap.press(keystroke)).unwrap() {
+            Press::Action(id) => {
+                event.prevent_default();
+                if let Some(Command { action, .. }) =
+                    commands.into_iter().find(|command| command.id == id)
+                {
+                    action(());
+                }
             }
-            event.prevent_default();
-            action(());
+            Press::Pending => event.prevent_default(),
+            Press::Unbound => {}
         }
     });
 
-    let find_matches = move || {
-        let mut new_matches = Vec::new();
-        let mut start_index = 0;
-
-        while let Some(index) = text.get_untracked()[start_index..].find(&find_text.get_untracked())
-        {
-            new_matches.push(start_index + index);
-            start_index += index + find_text.get_untracked().len();
+    create_effect(move |_| {
+        if !show_find_input() {
+            matches.set(Vec::new());
+            current_match.set(None);
+            return;
+        }
+        let Ok(found) = query.with(|query| text.with(|text| query.find(text))) else {
+            invalid_query.set(true);
+            matches.set(Vec::new());
+            current_match.set(None);
+            return;
+        };
+        invalid_query.set(false);
+        let clamped = found
+            .len()
+            .checked_sub(1)
+            .map(|last| current_match.get_untracked().unwrap_or_default().min(last));
+        matches.set(found);
+        if clamped != current_match.get_untracked() {
+            current_match.set(clamped);
         }
+    });
 
-        matches.set(new_matches);
-        current_match_index.set(0);
+    let select_match = move |index: usize| {
+        let Some(range) = matches.with_untracked(|matches| matches.get(index).cloned()) else {
+            return;
+        };
+        current_match.set(Some(index));
+        context.select_range(range);
     };
-
-    let move_to_next_match = move || {
-        if matches.get_untracked().is_empty() {
+    let move_to_match = move |offset: isize| {
+        let count = matches.with_untracked(Vec::len);
+        if count == 0 {
+            return;
+        }
+        #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+        select_match(current_match.get_untracked().map_or(0, |index| {
+            (index as isize + offset).rem_euclid(count as isize) as usize
+        }));
+    };
+    let replace_match = move || {
+        let Some(range) = current_match
+            .get_untracked()
+            .and_then(|index| matches.with_untracked(|matches| matches.get(index).cloned()))
+        else {
+            return;
+        };
+        let Some(replaced) = query.with_untracked(|query| {
+            query.replace(
+                &text.get_untracked(),
+                range.clone(),
+                &replacement.get_untracked(),
+            )
+        }) else {
+            return;
+        };
+        let end = range.end + replaced.len() - text.with_untracked(String::len);
+        let Some(edit) = text.with_untracked(|text| Edit::diff(text, &replaced)) else {
+            return;
+        };
+        context.edit(edit, end..end);
+    };
+    let replace_all_matches = move || {
+        let Ok((replaced, count)) = query.with_untracked(|query| {
+            query.replace_all(&text.get_untracked(), &replacement.get_untracked())
+        }) else {
             return;
+        };
+        if count > 0 {
+            context.replace_text(&replaced);
         }
-        let next_index = (current_match_index.get_untracked() + 1) % matches.get_untracked().len();
-        current_match_index.set(next_index);
     };
 
     window_event_listener(keydown, move |event| {
         if event.key() == "Escape" && show_find_input() {
             show_find_input.set(false);
-            find_text.set(String::new());
-            matches.set(Vec::new());
-            current_match_index.set(0);
+            text_area.get_untracked().unwrap().focus().unwrap();
         }
     });
 
     view! {
-        <div class="text-xs text-right cursor-default select-none px-80 text-fade">
+        <FolderSearch show=show_folder_search />
+        <IndexSearch show=show_index_search />
+        <CommandPalette show=show_palette commands=commands.to_vec() keymap=keymap />
+        <Outline show=show_outline />
+        <SettingsPanel show=show_settings />
+        <SpellingPopup show=show_spelling />
+        <StatisticsPanel show=show_statistics />
+        <WritingHistory show=show_writing_history />
+        <SprintPopup show=show_sprint />
+        <SnapshotHistory show=show_snapshots />
+        <GitCommitPopup show=show_git_commit />
+        <GitLog show=show_git_log />
+        <ManuscriptPopup show=show_manuscript />
+        <Backlinks show=show_backlinks />
+        <UnsavedChanges />
+        <ProblemsPopup show=show_problems />
+        <div class="text-xs text-right cursor-default select-none px-column text-fade">
             <Horizontal class="justify-between">
                 <div class="h-6">
                     <Match cases=[
                         (
-                            (move |()| command_pressed()).into(),
+                            (move |()| {
+                                command_pressed() || keymap.with(|keymap| !keymap.pending().is_empty())
+                            })
+                                .into(),
                             (move || {
                                 view! {
                                     <Horizontal gap=2>
-                                        {shortcuts
-                                            .into_iter()
-                                            .map(|Shortcut { shift, char, name, .. }| {
-                                                let char = char.to_ascii_uppercase();
-                                                view! {
-                                                    <Horizontal gap=2>
-                                                        <div>
-                                                            {format!("c-{}{char}", if shift { "sh-" } else { "" })}
-                                                        </div>
-                                                        <div class="text-accent">{name}</div>
-                                                    </Horizontal>
-                                                }
-                                            })
-                                            .collect_view()}
+                                        {move || {
+                                            keymap
+                                                .with(|keymap| {
+                                                    commands
+                                                        .into_iter()
+                                                        .filter(|Command { id, .. }| keymap.continues_pending(id))
+                                                        .map(|Command { id, name, .. }| (
+                                                            keymap.binding(id).unwrap().to_string(),
+                                                            name,
+                                                        ))
+                                                        .collect_vec()
+                                                })
+                                                .into_iter()
+                                                .map(|(binding, name)| {
+                                                    view! {
+                                                        <Horizontal gap=2>
+                                                            <div>{binding}</div>
+                                                            <div class="text-accent">{name}</div>
+                                                        </Horizontal>
+                                                    }
+                                                })
+                                                .collect_view()
+                                        }}
                                     </Horizontal>
                                 }
                             })
@@ -611,22 +1978,79 @@ fn StatusBar() -> impl IntoView {
                             (move |()| show_find_input()).into(),
                             (move || {
                                 view! {
-                                    <Horizontal gap=1>
-                                        <div class="text-text">"find:"</div>
-                                        <input
-                                            type="text"
-                                            class="outline-none select-text text-text bg-background cursor-text selection:bg-highlight"
-                                            autocorrect="off"
-                                        />
-                                    // prop:value=find_text
-                                    // on:input=move |_| {
-                                    // find_matches();
-                                    // }
-                                    // on:keydown=move |event| {
-                                    // if event.key() == "Enter" {
-                                    // move_to_next_match();
-                                    // }
-                                    // }
+                                    <Horizontal gap=3>
+                                        <Horizontal gap=1>
+                                            <div class="text-text">"find:"</div>
+                                            <input
+                                                type="text"
+                                                class="w-32 outline-none select-text text-text bg-background cursor-text selection:bg-highlight"
+                                                autocorrect="off"
+                                                ref=find_input
+                                                prop:value=move || query.with(|query| query.pattern.clone())
+                                                on:input=move |event| {
+                                                    query.update(|query| query.pattern = event_target_value(&event));
+                                                }
+                                                on:keydown=move |event| {
+                                                    toggle_keydown(query, &event);
+                                                    if event.key() == "Enter" {
+                                                        event.prevent_default();
+                                                        move_to_match(if event.shift_key() { -1 } else { 1 });
+                                                    }
+                                                }
+                                            />
+                                        </Horizontal>
+                                        <Horizontal gap=2>
+                                            <Toggles query=query />
+                                        </Horizontal>
+                                        <div class="text-accent">
+                                            {move || {
+                                                if invalid_query() {
+                                                    return "invalid".to_string();
+                                                }
+                                                format!(
+                                                    "{}/{}",
+                                                    current_match().map_or(0, |index| index + 1),
+                                                    matches.with(Vec::len),
+                                                )
+                                            }}
+                                        </div>
+                                        <Horizontal gap=1>
+                                            <div class="text-text">"replace:"</div>
+                                            <input
+                                                type="text"
+                                                class="w-32 outline-none select-text text-text bg-background cursor-text selection:bg-highlight"
+                                                autocorrect="off"
+                                                prop:value=replacement
+                                                on:input=move |event| {
+                                                    replacement.set(event_target_value(&event));
+                                                }
+                                                on:keydown=move |event| {
+                                                    toggle_keydown(query, &event);
+                                                    if event.key() == "Enter" {
+                                                        event.prevent_default();
+                                                        if event.alt_key() {
+                                                            replace_all_matches();
+                                                        } else {
+                                                            replace_match();
+                                                        }
+                                                    }
+                                                }
+                                            />
+                                        </Horizontal>
+                                        <div
+                                            class="cursor-pointer text-accent"
+                                            title="Replace (Enter)"
+                                            on:click=move |_| replace_match()
+                                        >
+                                            "replace"
+                                        </div>
+                                        <div
+                                            class="cursor-pointer text-accent"
+                                            title="Replace all (a-Enter)"
+                                            on:click=move |_| replace_all_matches()
+                                        >
+                                            "all"
+                                        </div>
                                     </Horizontal>
                                 }
                             })
@@ -663,22 +2087,71 @@ fn StatusBar() -> impl IntoView {
                         ),
                     ] />
                 </div>
-                <Show when=move || { !text().is_empty() } fallback=|| view! { <div /> }>
-                    {move || {
-                        let text = Cow::from(text());
-                        let text = if let Some((start, end)) = selection() {
-                            text.get(start..end).unwrap_or(&text).into()
-                        } else {
-                            text
-                        };
-                        format!(
-                            "{lines}L {words}W {chars}C",
-                            lines = text.lines().count(),
-                            words = text.split_whitespace().count(),
-                            chars = text.graphemes(true).count(),
-                        )
-                    }}
-                </Show>
+                <Horizontal gap=2>
+                    <ProblemsIndicator show=show_problems />
+                    <Show when=move || { !text().is_empty() }>
+                        <Horizontal gap=2>
+                            <GitIndicator show_log=show_git_log />
+                            {move || {
+                                let base = context.diff_base.with(|base| match base.as_ref()? {
+                                    DiffBase::Saved => Some("the saved file"),
+                                    DiffBase::Snapshot(_) => Some("the snapshot"),
+                                })?;
+                                let changes = context.diff.with(Vec::len);
+                                Some(
+                                    view! {
+                                        <div
+                                            class="cursor-pointer text-accent"
+                                            title=format!("Changes from {base}, click to stop comparing")
+                                            on:click=move |_| context.diff_base.set(None)
+                                        >
+                                            {format!("\u{b1}{changes}")}
+                                        </div>
+                                    },
+                                )
+                            }}
+                            {move || {
+                                let cursor = cursor();
+                                lints
+                                    .with(|lints| {
+                                        match lints
+                                            .iter()
+                                            .find(|lint| lint.range.start <= cursor && cursor <= lint.range.end)
+                                        {
+                                            Some(lint) => {
+                                                view! { <div class="text-accent">{lint.message.clone()}</div> }
+                                                    .into_view()
+                                            }
+                                            None if lints.is_empty() => ().into_view(),
+                                            None => format!("{}S", lints.len()).into_view(),
+                                        }
+                                    })
+                            }}
+                            <SprintProgress show=show_sprint />
+                            <WritingProgress show_history=show_writing_history />
+                            <div
+                                class="cursor-pointer"
+                                title="Statistics (c-K c-I)"
+                                on:click=move |_| show_statistics.set(true)
+                            >
+                                {move || {
+                                    let text = Cow::from(text());
+                                    let text = if let Some(range) = selection() {
+                                        text.get(range).unwrap_or(&text).into()
+                                    } else {
+                                        text
+                                    };
+                                    format!(
+                                        "{lines}L {words}W {chars}C",
+                                        lines = text.lines().count(),
+                                        words = text.split_whitespace().count(),
+                                        chars = text.graphemes(true).count(),
+                                    )
+                                }}
+                            </div>
+                        </Horizontal>
+                    </Show>
+                </Horizontal>
             </Horizontal>
         </div>
     }
@@ -712,6 +2185,20 @@ pub fn Vertical(
     }
 }
 
+#[component]
+pub fn Popup(children: Children, #[prop(into)] show: Signal<bool>) -> impl IntoView {
+    view! {
+        <div
+            class="fixed inset-x-0 z-40 flex justify-center text-xs text-left transition top-16"
+            class=(["opacity-0", "pointer-events-none"], move || !show())
+        >
+            <div class="w-[40rem] max-h-[60vh] overflow-y-auto rounded p-3 bg-highlight text-fade">
+                {children()}
+            </div>
+        </div>
+    }
+}
+
 pub fn class_to_string(class: Option<AttributeValue>) -> String {
     class
         .map(|class| {
@@ -0,0 +1,484 @@
+//! Compiling the files of a project, in the order its manifest lists them, into one manuscript.
+
+use std::path::Path;
+
+use leptos::ev::keydown;
+use leptos::{
+    component, create_effect, create_rw_signal, event_target_value, spawn_local, use_context, view,
+    window_event_listener, CollectView, IntoView, RwSignal, SignalGetUntracked, SignalSet,
+    SignalUpdate, SignalWith,
+};
+use serde::{Deserialize, Serialize};
+use typewriter_core::document::Document;
+
+use crate::problems::report;
+use crate::project::{open_file, Entry};
+use crate::{Context, Horizontal, Inter, Popup, Vertical};
+
+/// How the files of a project are compiled into one manuscript, as stored in its manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Manuscript {
+    /// The files compiled, in order, relative to the project's folder and separated by `/`.
+    pub files: Vec<String>,
+    /// How many levels the headings of every file are moved down, or up if it's negative.
+    pub heading_shift: i8,
+    /// What's put between files, if anything.
+    pub separator: String,
+    /// The file the manuscript is written to, relative to the project's folder.
+    pub output: String,
+}
+
+impl Default for Manuscript {
+    fn default() -> Self {
+        Self {
+            files: Vec::new(),
+            heading_shift: 0,
+            separator: String::new(),
+            output: "manuscript.md".to_string(),
+        }
+    }
+}
+
+impl Manuscript {
+    /// The files compiled, leaving out the output so a manuscript is never compiled into itself.
+    pub fn sources(&self) -> impl Iterator<Item = &String> {
+        self.files.iter().filter(|file| **file != self.output)
+    }
+
+    /// Concatenates the texts of the files into one Markdown text, shifting their headings and
+    /// putting the separator between them.
+    pub fn compile(&self, texts: &[String]) -> String {
+        let separator = if self.separator.trim().is_empty() {
+            "\n\n".to_string()
+        } else {
+            format!("\n\n{}\n\n", self.separator.trim())
+        };
+        let mut compiled = texts
+            .iter()
+            .map(|text| {
+                let (remaining, mut document) = Document::parse(text).unwrap();
+                document.shift_headings(self.heading_shift.into());
+                // Anything the parser stops at is kept as it is.
+                let text = document.to_string() + remaining;
+                text.trim_matches('\n').to_string()
+            })
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join(&separator);
+        compiled.push('\n');
+        compiled
+    }
+}
+
+/// Lists the paths of the files in a project relative to its folder, separated by `/`, in the
+/// order the sidebar shows them.
+pub fn project_files(entries: &[Entry], root: &Path) -> Vec<String> {
+    let mut files = Vec::new();
+    for entry in entries {
+        if let Some(children) = &entry.children {
+            files.extend(project_files(children, root));
+            continue;
+        }
+        let Ok(relative) = entry.path.strip_prefix(root) else {
+            continue;
+        };
+        let parts = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>();
+        files.push(parts.join("/"));
+    }
+    files
+}
+
+#[component]
+#[allow(clippy::too_many_lines)]
+pub fn ManuscriptPopup(show: RwSignal<bool>) -> impl IntoView {
+    let context = use_context::<Context>().unwrap();
+    let Context {
+        text,
+        text_area,
+        save_path: (read_save_path, _),
+        project: (read_project, _),
+        ..
+    } = context;
+    let manuscript = create_rw_signal(Manuscript::default());
+    let loaded = create_rw_signal(false);
+    // The files in the project, which can be added to the manuscript.
+    let available = create_rw_signal(Vec::<String>::new());
+    let message = create_rw_signal(None::<String>);
+    let compiled = create_rw_signal(None);
+
+    create_effect(move |_| {
+        if !show() {
+            return;
+        }
+        text_area.get_untracked().unwrap().blur().unwrap();
+        loaded.set(false);
+        message.set(None);
+        compiled.set(None);
+        let Some(root) = read_project.get_untracked() else {
+            return;
+        };
+        spawn_local(async move {
+            let (entries, problems) = Inter::list_project(root.clone()).await;
+            let (stored, error) = Inter::get_manuscript(root.clone()).await;
+            for problem in problems.into_iter().chain(error) {
+                report(context.problems, "manuscript", &problem);
+            }
+            let files = project_files(&entries, &root);
+            let stored = stored.unwrap_or_else(|| {
+                let mut manuscript = Manuscript::default();
+                manuscript.files = files
+                    .iter()
+                    .filter(|file| **file != manuscript.output)
+                    .cloned()
+                    .collect();
+                manuscript
+            });
+            available.set(files);
+            manuscript.set(stored);
+            loaded.set(true);
+        });
+    });
+    create_effect(move |_| {
+        let manuscript = manuscript();
+        if !loaded.get_untracked() {
+            return;
+        }
+        let Some(root) = read_project.get_untracked() else {
+            return;
+        };
+        spawn_local(async move {
+            if let Some(error) = Inter::set_manuscript(root, manuscript).await {
+                report(context.problems, "manuscript", &error);
+            }
+        });
+    });
+
+    let compile = move || {
+        let Some(root) = read_project.get_untracked() else {
+            return;
+        };
+        let manuscript = manuscript.get_untracked();
+        spawn_local(async move {
+            let mut texts = Vec::new();
+            for file in manuscript.sources() {
+                let path = root.join(file);
+                // The document is compiled as it's being edited, even if it isn't saved.
+                let data = if read_save_path.get_untracked().as_ref() == Some(&path) {
+                    Some(text.get_untracked())
+                } else {
+                    Inter::load_file(path).await
+                };
+                let Some(data) = data else {
+                    message.set(Some(format!("{file} couldn't be read")));
+                    return;
+                };
+                texts.push(data);
+            }
+            let output = manuscript.output.clone();
+            let data = manuscript.compile(&texts);
+            let (path, error) = Inter::write_manuscript(root, output.clone(), data).await;
+            if let Some(error) = error {
+                report(context.problems, "manuscript", &error);
+                message.set(Some(error));
+            }
+            if path.is_some() {
+                message.set(Some(format!(
+                    "compiled {} file{} into {output}",
+                    texts.len(),
+                    if texts.len() == 1 { "" } else { "s" },
+                )));
+            }
+            compiled.set(path);
+        });
+    };
+    let close = move || {
+        show.set(false);
+        text_area.get_untracked().unwrap().focus().unwrap();
+    };
+    let open_compiled = move || {
+        let Some(path) = compiled.get_untracked() else {
+            return;
+        };
+        close();
+        open_file(&context, path, None);
+    };
+    let move_file = move |index: usize, down: bool| {
+        manuscript.update(|manuscript| {
+            let other = if down {
+                index + 1
+            } else {
+                index.wrapping_sub(1)
+            };
+            if other < manuscript.files.len() {
+                manuscript.files.swap(index, other);
+            }
+        });
+    };
+
+    window_event_listener(keydown, move |event| {
+        if event.key() == "Escape" && show.get_untracked() {
+            close();
+        }
+    });
+
+    let text_input = move |label: &'static str,
+                           get: fn(&Manuscript) -> String,
+                           set: fn(&mut Manuscript, String)| {
+        view! {
+            <Horizontal gap=1>
+                <div class="w-32 text-text">{label}</div>
+                <input
+                    type="text"
+                    class="px-1 rounded outline-none grow text-text bg-background"
+                    prop:value=move || manuscript.with(get)
+                    on:change=move |event| {
+                        manuscript.update(|manuscript| set(manuscript, event_target_value(&event)));
+                    }
+                />
+            </Horizontal>
+        }
+    };
+
+    view! {
+        <Popup show=show>
+            {move || {
+                if read_project().is_none() {
+                    return view! {
+                        <div class="px-1">"open a project folder to compile its files"</div>
+                    }
+                        .into_view();
+                }
+                if !loaded() {
+                    return ().into_view();
+                }
+                let (files, excluded) = manuscript
+                    .with(|manuscript| {
+                        let excluded = available
+                            .with(|available| {
+                                available
+                                    .iter()
+                                    .filter(|file| {
+                                        !manuscript.files.contains(file) && **file != manuscript.output
+                                    })
+                                    .cloned()
+                                    .collect::<Vec<_>>()
+                            });
+                        (manuscript.files.clone(), excluded)
+                    });
+                let count = files.len();
+                view! {
+                    <Vertical gap=2>
+                        <Vertical class="overflow-y-auto max-h-48">
+                            {files
+                                .into_iter()
+                                .enumerate()
+                                .map(|(index, file)| {
+                                    view! {
+                                        <Horizontal gap=2 class="justify-between px-1 rounded text-text">
+                                            <div class="truncate">{file}</div>
+                                            <Horizontal gap=1>
+                                                <div
+                                                    class="cursor-pointer text-accent"
+                                                    class=("invisible", index == 0)
+                                                    title="Move up"
+                                                    on:click=move |_| move_file(index, false)
+                                                >
+                                                    "\u{2191}"
+                                                </div>
+                                                <div
+                                                    class="cursor-pointer text-accent"
+                                                    class=("invisible", index + 1 == count)
+                                                    title="Move down"
+                                                    on:click=move |_| move_file(index, true)
+                                                >
+                                                    "\u{2193}"
+                                                </div>
+                                                <div
+                                                    class="cursor-pointer text-accent"
+                                                    title="Leave out"
+                                                    on:click=move |_| {
+                                                        manuscript.update(|manuscript| {
+                                                            manuscript.files.remove(index);
+                                                        });
+                                                    }
+                                                >
+                                                    "\u{00d7}"
+                                                </div>
+                                            </Horizontal>
+                                        </Horizontal>
+                                    }
+                                })
+                                .collect_view()}
+                            {excluded
+                                .into_iter()
+                                .map(|file| {
+                                    let added = file.clone();
+                                    view! {
+                                        <Horizontal gap=2 class="justify-between px-1">
+                                            <div class="truncate">{file}</div>
+                                            <div
+                                                class="cursor-pointer text-accent"
+                                                title="Add to the manuscript"
+                                                on:click=move |_| {
+                                                    manuscript.update(|manuscript| {
+                                                        manuscript.files.push(added.clone());
+                                                    });
+                                                }
+                                            >
+                                                "+"
+                                            </div>
+                                        </Horizontal>
+                                    }
+                                })
+                                .collect_view()}
+                        </Vertical>
+                        <Horizontal gap=1>
+                            <div class="w-32 text-text">"heading shift"</div>
+                            <input
+                                type="number"
+                                class="px-1 rounded outline-none grow text-text bg-background"
+                                min=-5
+                                max=5
+                                prop:value=move || manuscript.with(|manuscript| manuscript.heading_shift)
+                                on:change=move |event| {
+                                    if let Ok(shift) = event_target_value(&event).parse::<i8>() {
+                                        manuscript.update(|manuscript| {
+                                            manuscript.heading_shift = shift.clamp(-5, 5);
+                                        });
+                                    }
+                                }
+                            />
+                        </Horizontal>
+                        {text_input(
+                            "separator",
+                            |manuscript| manuscript.separator.clone(),
+                            |manuscript, separator| manuscript.separator = separator,
+                        )}
+                        {text_input(
+                            "output",
+                            |manuscript| manuscript.output.clone(),
+                            |manuscript, output| manuscript.output = output,
+                        )}
+                        <Horizontal class="justify-between px-1">
+                            <Horizontal gap=2>
+                                <div>{message}</div>
+                                {move || {
+                                    compiled
+                                        .with(Option::is_some)
+                                        .then(|| {
+                                            view! {
+                                                <div
+                                                    class="cursor-pointer text-accent"
+                                                    on:click=move |_| open_compiled()
+                                                >
+                                                    "open"
+                                                </div>
+                                            }
+                                        })
+                                }}
+                            </Horizontal>
+                            <div class="cursor-pointer text-accent" on:click=move |_| compile()>
+                                "compile"
+                            </div>
+                        </Horizontal>
+                    </Vertical>
+                }
+                    .into_view()
+            }}
+        </Popup>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn compiling_works() {
+        let texts = [
+            "# Chapter One\nIt was *dark*.\n\n".to_string(),
+            String::new(),
+            "# Chapter Two\n## Later\nIt was light.".to_string(),
+        ];
+        let manuscript = Manuscript {
+            heading_shift: 1,
+            separator: "* * *".to_string(),
+            ..Manuscript::default()
+        };
+        assert_eq!(
+            manuscript.compile(&texts),
+            "## Chapter One\nIt was *dark*.\n\n* * *\n\n## Chapter Two\n### Later\nIt was light.\n"
+        );
+        let manuscript = Manuscript::default();
+        assert_eq!(
+            manuscript.compile(&texts[..1]),
+            "# Chapter One\nIt was *dark*.\n"
+        );
+    }
+
+    #[test]
+    fn compiling_shifts_only_headings() {
+        let texts = [
+            "# Learning C# basics\n#hashtag and a # b\n## Escapes \\* here *\n# \n\
+                      ```sh\n# a comment\n```\n# After"
+                .to_string(),
+        ];
+        let manuscript = Manuscript {
+            heading_shift: 1,
+            ..Manuscript::default()
+        };
+        assert_eq!(
+            manuscript.compile(&texts),
+            "## Learning C# basics\n#hashtag and a # b\n### Escapes \\* here *\n# \n\
+             ```sh\n# a comment\n```\n## After\n"
+        );
+    }
+
+    #[test]
+    fn compiling_keeps_heading_text() {
+        let texts = ["# Learning C# basics\nSome *text.\n## Escapes \\* here".to_string()];
+        let manuscript = Manuscript {
+            heading_shift: 1,
+            ..Manuscript::default()
+        };
+        assert_eq!(
+            manuscript.compile(&texts),
+            "## Learning C# basics\nSome *text.\n### Escapes \\* here\n"
+        );
+    }
+
+    #[test]
+    fn leaving_out_the_output_works() {
+        let manuscript = Manuscript {
+            files: vec!["one.md".to_string(), "manuscript.md".to_string()],
+            ..Manuscript::default()
+        };
+        assert_eq!(manuscript.sources().collect::<Vec<_>>(), ["one.md"]);
+    }
+
+    #[test]
+    fn listing_project_files_works() {
+        let entry = |path: &str, children| Entry {
+            path: PathBuf::from(path),
+            name: String::new(),
+            children,
+        };
+        let entries = [
+            entry(
+                "/book/part one",
+                Some(vec![entry("/book/part one/one.md", None)]),
+            ),
+            entry("/book/notes.md", None),
+        ];
+        assert_eq!(
+            project_files(&entries, Path::new("/book")),
+            ["part one/one.md", "notes.md"]
+        );
+    }
+}
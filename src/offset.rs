@@ -0,0 +1,156 @@
+//! Conversions between the offsets used to index into the text.
+//!
+//! The DOM counts UTF-16 code units, Rust strings are indexed by byte and people count grapheme
+//! clusters. Everything stored in the editor is a byte offset, converted at the edges with these.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Converts a UTF-16 code unit offset into a byte offset, rounding down to the start of the
+/// character it falls in. Offsets past the end of the text are clamped to its length.
+pub fn utf16_to_byte(text: &str, utf16: usize) -> usize {
+    let mut units = 0;
+    for (offset, char) in text.char_indices() {
+        units += char.len_utf16();
+        if units > utf16 {
+            return offset;
+        }
+    }
+    text.len()
+}
+
+/// Converts a byte offset into a UTF-16 code unit offset, rounding down to the start of the
+/// character it falls in.
+pub fn byte_to_utf16(text: &str, byte: usize) -> usize {
+    text[..floor_char_boundary(text, byte)]
+        .encode_utf16()
+        .count()
+}
+
+/// Converts a byte offset into the number of grapheme clusters before it, counting a cluster the
+/// offset falls inside of as before it.
+pub fn byte_to_grapheme(text: &str, byte: usize) -> usize {
+    text.grapheme_indices(true)
+        .take_while(|(offset, _)| *offset < byte)
+        .count()
+}
+
+/// Converts a number of grapheme clusters into the byte offset after them, clamped to the length
+/// of the text.
+pub fn grapheme_to_byte(text: &str, grapheme: usize) -> usize {
+    text.grapheme_indices(true)
+        .nth(grapheme)
+        .map_or(text.len(), |(offset, _)| offset)
+}
+
+/// Rounds a byte offset down to the start of the grapheme cluster it falls in.
+pub fn floor_grapheme_boundary(text: &str, byte: usize) -> usize {
+    text.grapheme_indices(true)
+        .map(|(offset, _)| offset)
+        .chain([text.len()])
+        .take_while(|offset| *offset <= byte)
+        .last()
+        .unwrap_or_default()
+}
+
+/// Rounds a byte offset up to the end of the grapheme cluster it falls in.
+pub fn ceil_grapheme_boundary(text: &str, byte: usize) -> usize {
+    text.grapheme_indices(true)
+        .map(|(offset, _)| offset)
+        .find(|offset| *offset >= byte)
+        .unwrap_or(text.len())
+}
+
+/// Rounds a byte offset down to the start of the character it falls in, clamped to the length of
+/// the text.
+pub const fn floor_char_boundary(text: &str, byte: usize) -> usize {
+    if byte >= text.len() {
+        return text.len();
+    }
+    let mut byte = byte;
+    while !text.is_char_boundary(byte) {
+        byte -= 1;
+    }
+    byte
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// "e" with a combining acute accent, a flag made of two regional indicators, and a family
+    /// joined with zero-width joiners: single graphemes made of several code points.
+    const CLUSTERS: &str = "e\u{301}🇳🇿👨‍👩‍👧";
+
+    #[test]
+    fn converting_utf16_works() {
+        let text = "aé😀中b";
+        let offsets = [
+            (0, 0),
+            (1, 1),
+            (2, 3),
+            (3, 3),
+            (4, 7),
+            (5, 10),
+            (6, 11),
+            (100, 11),
+        ];
+        for (utf16, byte) in offsets {
+            assert_eq!(utf16_to_byte(text, utf16), byte, "utf16 {utf16}");
+        }
+        let offsets = [(0, 0), (1, 1), (3, 2), (5, 2), (7, 4), (10, 5), (11, 6)];
+        for (byte, utf16) in offsets {
+            assert_eq!(byte_to_utf16(text, byte), utf16, "byte {byte}");
+        }
+        for byte in [0, 1, 3, 7, 10, 11] {
+            assert_eq!(utf16_to_byte(text, byte_to_utf16(text, byte)), byte);
+        }
+    }
+
+    #[test]
+    fn converting_graphemes_works() {
+        let text = format!("a{CLUSTERS}b");
+        let starts = [0, 1, 4, 12, 30, 31];
+        for (grapheme, &byte) in starts.iter().enumerate() {
+            assert_eq!(grapheme_to_byte(&text, grapheme), byte);
+            assert_eq!(byte_to_grapheme(&text, byte), grapheme);
+        }
+        assert_eq!(grapheme_to_byte(&text, 100), text.len());
+        assert_eq!(byte_to_grapheme(&text, 2), 2);
+        assert_eq!(byte_to_grapheme(&text, 20), 4);
+    }
+
+    #[test]
+    fn rounding_to_boundaries_works() {
+        let text = format!("a{CLUSTERS}");
+        assert_eq!(floor_char_boundary(&text, 2), 2);
+        assert_eq!(floor_char_boundary(&text, 5), 4);
+        assert_eq!(floor_char_boundary(&text, 100), text.len());
+        assert_eq!(floor_grapheme_boundary(&text, 2), 1);
+        assert_eq!(floor_grapheme_boundary(&text, 11), 4);
+        assert_eq!(floor_grapheme_boundary(&text, 20), 12);
+        assert_eq!(floor_grapheme_boundary(&text, text.len()), text.len());
+        assert_eq!(floor_grapheme_boundary("", 0), 0);
+        assert_eq!(ceil_grapheme_boundary(&text, 1), 1);
+        assert_eq!(ceil_grapheme_boundary(&text, 2), 4);
+        assert_eq!(ceil_grapheme_boundary(&text, 20), text.len());
+        assert_eq!(ceil_grapheme_boundary("", 0), 0);
+    }
+
+    #[test]
+    fn dom_offsets_inside_clusters_work() {
+        // The DOM can put the caret between the code points of a cluster, e.g. after the "e"
+        // but before its accent, or between the two halves of a surrogate pair.
+        let text = format!("x{CLUSTERS}");
+        assert_eq!(utf16_to_byte(&text, 2), 2);
+        assert_eq!(utf16_to_byte(&text, 3), 4);
+        assert_eq!(utf16_to_byte(&text, 4), 4);
+        assert_eq!(utf16_to_byte(&text, 5), 8);
+        let byte = utf16_to_byte(&text, 5);
+        assert_eq!(floor_grapheme_boundary(&text, byte), 4);
+        assert_eq!(ceil_grapheme_boundary(&text, byte), 12);
+        assert_eq!(
+            byte_to_utf16(&text, text.len()),
+            text.encode_utf16().count()
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use std::ops::Range;
+
+use leptos::ev::keydown;
+use leptos::html::Input;
+use leptos::{
+    component, create_effect, create_node_ref, create_rw_signal, event_target_value, untrack,
+    use_context, view, window_event_listener, CollectView, IntoView, NodeRef, RwSignal,
+    SignalGetUntracked, SignalSet, SignalUpdate, SignalWith, SignalWithUntracked,
+};
+use typewriter_core::document::{headings, Heading};
+
+use crate::palette::fuzzy_match;
+use crate::{Context, Horizontal, Popup, Vertical};
+
+/// Finds the byte ranges of the paragraphs in some text: runs of lines between blank lines and
+/// headings, without their last newline.
+pub fn paragraphs(text: &str) -> Vec<Range<usize>> {
+    let mut headings = headings(text)
+        .into_iter()
+        .map(|heading| heading.offset)
+        .peekable();
+    let mut paragraphs = Vec::new();
+    let mut start = None;
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let is_heading = headings.next_if_eq(&offset).is_some();
+        if is_heading || line.trim().is_empty() {
+            if let Some(start) = start.take() {
+                paragraphs.push(start..offset - 1);
+            }
+        } else {
+            start.get_or_insert(offset);
+        }
+        offset += line.len();
+    }
+    if let Some(start) = start {
+        paragraphs.push(start..text.trim_end_matches(['\n', '\r']).len());
+    }
+    paragraphs
+}
+
+/// Finds the index of the heading of the section containing a byte offset, if any.
+pub fn current_section(headings: &[Heading], offset: usize) -> Option<usize> {
+    headings
+        .partition_point(|heading| heading.offset <= offset)
+        .checked_sub(1)
+}
+
+#[component]
+#[allow(clippy::too_many_lines)]
+pub fn Outline(show: RwSignal<bool>) -> impl IntoView {
+    let context = use_context::<Context>().unwrap();
+    let Context {
+        text,
+        text_area,
+        top,
+        reveal,
+        ..
+    } = context;
+    let query = create_rw_signal(String::new());
+    let selected = create_rw_signal(0);
+    let input: NodeRef<Input> = create_node_ref();
+
+    let entries = move || {
+        let headings = text.with(|text| headings(text));
+        let current = current_section(&headings, top());
+        query.with(|query| {
+            headings
+                .into_iter()
+                .enumerate()
+                .filter_map(|(index, heading)| {
+                    let (_, matched) = fuzzy_match(query, &heading.title)?;
+                    Some((heading, matched, Some(index) == current))
+                })
+                .collect::<Vec<_>>()
+        })
+    };
+    create_effect(move |_| {
+        if show() {
+            query.set(String::new());
+            selected.set(untrack(|| {
+                entries()
+                    .iter()
+                    .position(|(.., current)| *current)
+                    .unwrap_or_default()
+            }));
+            input.get_untracked().unwrap().focus().unwrap();
+        }
+    });
+    let jump = move |heading: &Heading| {
+        show.set(false);
+        text_area.get_untracked().unwrap().focus().unwrap();
+        context.select_range(heading.offset..heading.offset);
+        reveal.set(Some(heading.offset));
+    };
+
+    window_event_listener(keydown, move |event| {
+        if event.key() == "Escape" && show.get_untracked() {
+            show.set(false);
+            text_area.get_untracked().unwrap().focus().unwrap();
+        }
+    });
+
+    view! {
+        <Popup show=show>
+            <Vertical gap=2>
+                <Horizontal gap=1>
+                    <div class="text-text">"heading:"</div>
+                    <input
+                        type="text"
+                        class="outline-none grow select-text text-text bg-highlight cursor-text selection:bg-fade"
+                        autocorrect="off"
+                        ref=input
+                        prop:value=query
+                        on:input=move |event| {
+                            query.set(event_target_value(&event));
+                            selected.set(0);
+                        }
+                        on:keydown=move |event| {
+                            let entries = entries();
+                            match event.key().as_str() {
+                                "Enter" => {
+                                    if let Some((heading, ..)) = entries.get(selected.get_untracked()) {
+                                        jump(heading);
+                                    }
+                                }
+                                "ArrowDown" => {
+                                    selected
+                                        .update(|selected| {
+                                            *selected = (*selected + 1).min(entries.len().saturating_sub(1));
+                                        });
+                                }
+                                "ArrowUp" => {
+                                    selected.update(|selected| *selected = selected.saturating_sub(1));
+                                }
+                                _ => return,
+                            }
+                            event.prevent_default();
+                        }
+                    />
+                </Horizontal>
+                <Vertical class="overflow-y-auto max-h-96">
+                    {move || {
+                        let entries = entries();
+                        if entries.is_empty() {
+                            let empty = if text.with_untracked(|text| headings(text).is_empty()) {
+                                "no headings"
+                            } else {
+                                "no matches"
+                            };
+                            return view! { <div class="px-1">{empty}</div> }.into_view();
+                        }
+                        let min_depth = entries
+                            .iter()
+                            .map(|(heading, ..)| heading.depth)
+                            .min()
+                            .unwrap_or_default();
+                        entries
+                            .into_iter()
+                            .enumerate()
+                            .map(|(index, (heading, matched, current))| {
+                                let indent = format!("{}rem", heading.depth - min_depth);
+                                let title = heading
+                                    .title
+                                    .chars()
+                                    .enumerate()
+                                    .map(|(index, char)| {
+                                        view! {
+                                            <span class=("text-text", matched.contains(&index))>
+                                                {char}
+                                            </span>
+                                        }
+                                    })
+                                    .collect_view();
+                                view! {
+                                    <div
+                                        class="px-1 truncate rounded cursor-pointer"
+                                        class=("bg-background", move || selected() == index)
+                                        class=("text-accent", current)
+                                        style:margin-left=indent
+                                        on:click=move |_| jump(&heading)
+                                    >
+                                        {title}
+                                    </div>
+                                }
+                            })
+                            .collect_view()
+                    }}
+                </Vertical>
+            </Vertical>
+        </Popup>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finding_paragraphs_works() {
+        let text = "# Title\nOne\nline.\n\n\nTwo.\n## Sub\nThree.\n#hashtag\n";
+        let found = paragraphs(text)
+            .into_iter()
+            .map(|range| &text[range])
+            .collect::<Vec<_>>();
+        assert_eq!(found, ["One\nline.", "Two.", "Three.\n#hashtag"]);
+        assert!(paragraphs("\n\n# Only\n").is_empty());
+    }
+
+    #[test]
+    fn finding_current_section_works() {
+        let headings = headings("intro\n# One\ntext\n## Two\n");
+        assert_eq!(current_section(&headings, 0), None);
+        assert_eq!(current_section(&headings, 6), Some(0));
+        assert_eq!(current_section(&headings, 14), Some(0));
+        assert_eq!(current_section(&headings, 17), Some(1));
+        assert_eq!(current_section(&headings, 100), Some(1));
+    }
+}
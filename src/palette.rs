@@ -0,0 +1,214 @@
+use leptos::ev::keydown;
+use leptos::html::Input;
+use leptos::{
+    component, create_effect, create_node_ref, create_rw_signal, event_target_value, store_value,
+    use_context, view, window_event_listener, Callback, CollectView, IntoView, NodeRef, RwSignal,
+    SignalGetUntracked, SignalSet, SignalUpdate, SignalWith,
+};
+
+use crate::keymap::Keymap;
+use crate::{Context, Horizontal, Popup, Vertical};
+
+/// An action the editor can perform, run from the command palette or its binding.
+#[derive(Clone, Copy)]
+pub struct Command {
+    /// The id the keymap refers to the command by.
+    pub id: &'static str,
+    /// The default binding, or an empty string if it only runs from the palette by default.
+    pub binding: &'static str,
+    pub name: &'static str,
+    pub action: Callback<()>,
+}
+
+/// Scores how well a query fuzzily matches some text, where every character of the query has to
+/// appear in the text in order, ignoring case.
+///
+/// Matches at the start of words and runs of consecutive characters score higher, gaps score
+/// lower. Returns the score and the indices of the matched characters of the text, or [`None`]
+/// if the query doesn't match.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<(i32, Vec<usize>)> {
+    let mut query = query
+        .chars()
+        .filter(|char| !char.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .peekable();
+    let mut score = 0;
+    let mut matched: Vec<usize> = Vec::new();
+    let mut previous = None;
+    for (index, char) in text.chars().enumerate() {
+        let Some(&wanted) = query.peek() else {
+            break;
+        };
+        let word_start = previous.is_none_or(|previous: char| {
+            !previous.is_alphanumeric() || (previous.is_lowercase() && char.is_uppercase())
+        });
+        previous = Some(char);
+        if !char.to_lowercase().eq([wanted]) {
+            continue;
+        }
+        query.next();
+        score += 1;
+        if word_start {
+            score += 8;
+        }
+        match matched.last() {
+            Some(&last) if last + 1 == index => score += 4,
+            Some(&last) => score -= i32::try_from(index - last).unwrap_or(i32::MAX).min(4),
+            None => score -= i32::try_from(index).unwrap_or(i32::MAX).min(4),
+        }
+        matched.push(index);
+    }
+    query.peek().is_none().then_some((score, matched))
+}
+
+#[component]
+#[allow(clippy::too_many_lines)]
+pub fn CommandPalette(
+    show: RwSignal<bool>,
+    commands: Vec<Command>,
+    keymap: RwSignal<Keymap>,
+) -> impl IntoView {
+    let Context { text_area, .. } = use_context().unwrap();
+    let query = create_rw_signal(String::new());
+    let selected = create_rw_signal(0);
+    let input: NodeRef<Input> = create_node_ref();
+    let commands = store_value(commands);
+
+    create_effect(move |_| {
+        if show() {
+            query.set(String::new());
+            selected.set(0);
+            input.get_untracked().unwrap().focus().unwrap();
+        }
+    });
+
+    let matches = move || {
+        let mut matches = query.with(|query| {
+            commands.with_value(|commands| {
+                commands
+                    .iter()
+                    .filter_map(|command| {
+                        let (score, matched) = fuzzy_match(query, command.name)?;
+                        Some((score, matched, *command))
+                    })
+                    .collect::<Vec<_>>()
+            })
+        });
+        matches.sort_by_key(|(score, ..)| -score);
+        matches
+    };
+    let run = move |command: Command| {
+        show.set(false);
+        text_area.get_untracked().unwrap().focus().unwrap();
+        (command.action)(());
+    };
+
+    window_event_listener(keydown, move |event| {
+        if event.key() == "Escape" && show.get_untracked() {
+            show.set(false);
+            text_area.get_untracked().unwrap().focus().unwrap();
+        }
+    });
+
+    view! {
+        <Popup show=show>
+            <Vertical gap=2>
+                <Horizontal gap=1>
+                    <div class="text-text">"command:"</div>
+                    <input
+                        type="text"
+                        class="outline-none grow select-text text-text bg-highlight cursor-text selection:bg-fade"
+                        autocorrect="off"
+                        ref=input
+                        prop:value=query
+                        on:input=move |event| {
+                            query.set(event_target_value(&event));
+                            selected.set(0);
+                        }
+                        on:keydown=move |event| {
+                            let matches = matches();
+                            match event.key().as_str() {
+                                "Enter" => {
+                                    if let Some((.., command)) = matches.get(selected.get_untracked()) {
+                                        run(*command);
+                                    }
+                                }
+                                "ArrowDown" => {
+                                    selected
+                                        .update(|selected| {
+                                            *selected = (*selected + 1).min(matches.len().saturating_sub(1));
+                                        });
+                                }
+                                "ArrowUp" => {
+                                    selected.update(|selected| *selected = selected.saturating_sub(1));
+                                }
+                                _ => return,
+                            }
+                            event.prevent_default();
+                        }
+                    />
+                </Horizontal>
+                <Vertical>
+                    {move || {
+                        matches()
+                            .into_iter()
+                            .enumerate()
+                            .map(|(index, (_, matched, command))| {
+                                let binding = keymap
+                                    .with(|keymap| keymap.binding(command.id).map(ToString::to_string));
+                                view! {
+                                    <div
+                                        class="flex justify-between gap-2 px-1 rounded cursor-pointer"
+                                        class=("bg-background", move || selected() == index)
+                                        on:click=move |_| run(command)
+                                    >
+                                        <div>
+                                            {command
+                                                .name
+                                                .chars()
+                                                .enumerate()
+                                                .map(|(index, char)| {
+                                                    view! {
+                                                        <span class=("text-text", matched.contains(&index))>
+                                                            {char}
+                                                        </span>
+                                                    }
+                                                })
+                                                .collect_view()}
+                                        </div>
+                                        <div class="text-accent">{binding}</div>
+                                    </div>
+                                }
+                            })
+                            .collect_view()
+                    }}
+                </Vertical>
+            </Vertical>
+        </Popup>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_matching_works() {
+        assert_eq!(fuzzy_match("", "Save"), Some((0, Vec::new())));
+        assert_eq!(fuzzy_match("sa", "Save as").unwrap().1, [0, 1]);
+        assert_eq!(fuzzy_match("sva", "Save as").unwrap().1, [0, 2, 5]);
+        assert_eq!(fuzzy_match("s as", "Save as").unwrap().1, [0, 1, 6]);
+        assert_eq!(fuzzy_match("SAVE", "save").unwrap().1, [0, 1, 2, 3]);
+        assert_eq!(fuzzy_match("xyz", "Save as"), None);
+        assert_eq!(fuzzy_match("sa", "as"), None);
+    }
+
+    #[test]
+    fn fuzzy_scoring_works() {
+        let score = |query, text| fuzzy_match(query, text).unwrap().0;
+        assert!(score("sf", "Search folder") > score("sf", "Save as draft"));
+        assert!(score("save", "Save") > score("save", "Search and evaluate"));
+        assert!(score("op", "Open") > score("op", "Command palette"));
+        assert!(score("cp", "CommandPalette") > score("cp", "Compile"));
+    }
+}
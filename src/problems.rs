@@ -0,0 +1,128 @@
+//! The problems the backend ran into doing what the user asked, like settings that couldn't be
+//! read or files that couldn't be moved, kept so the status bar can show them.
+
+use leptos::ev::keydown;
+use leptos::logging::warn;
+use leptos::{
+    component, use_context, view, window_event_listener, CollectView, IntoView, RwSignal,
+    SignalGetUntracked, SignalSet, SignalUpdate, SignalWith,
+};
+
+use crate::{Context, Horizontal, Popup, Vertical};
+
+/// The most problems kept, dropping the oldest past it.
+const MAX_PROBLEMS: usize = 50;
+
+/// Adds a problem to the list, unless it's already the latest one.
+pub fn add_problem(problems: &mut Vec<String>, problem: String) {
+    if problems.last() == Some(&problem) {
+        return;
+    }
+    problems.push(problem);
+    if problems.len() > MAX_PROBLEMS {
+        problems.remove(0);
+    }
+}
+
+/// Logs a problem with some area of the app and shows it in the status bar.
+pub fn report(problems: RwSignal<Vec<String>>, area: &str, problem: &str) {
+    warn!("{area}: {problem}");
+    problems.update(|problems| add_problem(problems, format!("{area}: {problem}")));
+}
+
+/// The number of problems reported, if there are any.
+#[component]
+pub fn ProblemsIndicator(show: RwSignal<bool>) -> impl IntoView {
+    let Context { problems, .. } = use_context::<Context>().unwrap();
+
+    move || {
+        let count = problems.with(Vec::len);
+        (count > 0).then(|| {
+            view! {
+                <div
+                    class="cursor-pointer text-error"
+                    title="Problems"
+                    on:click=move |_| show.set(true)
+                >
+                    {format!("{count}!")}
+                </div>
+            }
+        })
+    }
+}
+
+#[component]
+pub fn ProblemsPopup(show: RwSignal<bool>) -> impl IntoView {
+    let Context {
+        problems,
+        text_area,
+        ..
+    } = use_context::<Context>().unwrap();
+
+    let close = move || {
+        show.set(false);
+        text_area.get_untracked().unwrap().focus().unwrap();
+    };
+
+    window_event_listener(keydown, move |event| {
+        if event.key() == "Escape" && show.get_untracked() {
+            close();
+        }
+    });
+
+    view! {
+        <Popup show=show>
+            <Vertical gap=2>
+                <Horizontal class="justify-between">
+                    <div class="text-text">"problems"</div>
+                    <div
+                        class="cursor-pointer text-accent"
+                        on:click=move |_| {
+                            problems.set(Vec::new());
+                            close();
+                        }
+                    >
+                        "clear"
+                    </div>
+                </Horizontal>
+                <Vertical class="overflow-y-auto max-h-80">
+                    {move || {
+                        if problems.with(Vec::is_empty) {
+                            return view! { <div>"nothing went wrong"</div> }.into_view();
+                        }
+                        problems
+                            .with(|problems| {
+                                problems
+                                    .iter()
+                                    .rev()
+                                    .map(|problem| {
+                                        view! { <div class="select-text">{problem.clone()}</div> }
+                                    })
+                                    .collect_view()
+                            })
+                    }}
+                </Vertical>
+            </Vertical>
+        </Popup>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adding_problems_works() {
+        let mut problems = Vec::new();
+        add_problem(&mut problems, "a".to_string());
+        add_problem(&mut problems, "a".to_string());
+        add_problem(&mut problems, "b".to_string());
+        add_problem(&mut problems, "a".to_string());
+        assert_eq!(problems, ["a", "b", "a"]);
+        for index in 0..MAX_PROBLEMS {
+            add_problem(&mut problems, index.to_string());
+        }
+        assert_eq!(problems.len(), MAX_PROBLEMS);
+        assert_eq!(problems[0], "0");
+    }
+}
@@ -0,0 +1,719 @@
+//! The project folder the documents being written are in, and the sidebar listing its files.
+
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use leptos::ev::keydown;
+use leptos::html::Input;
+use leptos::{
+    component, create_effect, create_node_ref, create_rw_signal, event_target_value,
+    request_animation_frame, spawn_local, use_context, view, window_event_listener, CollectView,
+    IntoView, NodeRef, RwSignal, Signal, SignalGetUntracked, SignalSet, SignalUpdate, SignalWith,
+    SignalWithUntracked, View,
+};
+use serde::Deserialize;
+
+use crate::links::index_links;
+use crate::problems::report;
+use crate::{Context, Inter, Popup};
+
+/// The extension of the documents in a project, which new files are given.
+const MARKDOWN_EXTENSION: &str = ".md";
+
+/// A Markdown file or a folder in the project.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Entry {
+    pub path: PathBuf,
+    pub name: String,
+    /// The entries in the folder, or [`None`] if this is a file.
+    pub children: Option<Vec<Entry>>,
+}
+
+/// What the name being typed in the sidebar is for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Naming {
+    /// A new file in a folder.
+    File(PathBuf),
+    /// A new folder in a folder.
+    Folder(PathBuf),
+    /// Renaming a file or a folder.
+    Rename(PathBuf),
+}
+
+/// Checks a name typed for a file or a folder, giving files the Markdown extension if they don't
+/// have it.
+pub fn entry_name(name: &str, folder: bool) -> Option<String> {
+    let name = name.trim();
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        return None;
+    }
+    if folder || name.ends_with(MARKDOWN_EXTENSION) {
+        Some(name.to_string())
+    } else {
+        Some(format!("{name}{MARKDOWN_EXTENSION}"))
+    }
+}
+
+/// Finds where a path is after a file or a folder is moved, if it's the one moved or in it.
+pub fn moved_path(path: &Path, from: &Path, to: &Path) -> Option<PathBuf> {
+    Some(to.join(path.strip_prefix(from).ok()?))
+}
+
+/// Whether a file or a folder can be moved into a folder, which it isn't already in and isn't in
+/// it.
+pub fn can_move(path: &Path, folder: &Path) -> bool {
+    path.parent() != Some(folder) && !folder.starts_with(path)
+}
+
+/// Finds the byte range of a line of a text, without its line break.
+pub fn line_range(text: &str, line: usize) -> Range<usize> {
+    let start = text
+        .split_inclusive('\n')
+        .take(line)
+        .map(str::len)
+        .sum::<usize>()
+        .min(text.len());
+    let end = text[start..]
+        .find('\n')
+        .map_or(text.len(), |length| start + length);
+    start..end
+}
+
+/// A place in a file to select once it's opened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Place {
+    pub line: usize,
+    /// The byte range in the line, or [`None`] for the whole line.
+    pub columns: Option<Range<usize>>,
+}
+
+impl Place {
+    /// Finds the byte range of the place in a text, keeping it in its line.
+    pub fn range(&self, text: &str) -> Range<usize> {
+        let line = line_range(text, self.line);
+        match &self.columns {
+            Some(columns) => {
+                (line.start + columns.start).min(line.end)..(line.start + columns.end).min(line.end)
+            }
+            None => line,
+        }
+    }
+}
+
+/// A file asked to be opened while the document has unsaved changes, which needs confirming.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Opening {
+    path: PathBuf,
+    place: Option<Place>,
+}
+
+/// Opens a file in the editor, selecting a place in it if there is one. If the document has
+/// unsaved changes, asks whether to save or discard them first.
+pub fn open_file(context: &Context, path: PathBuf, place: Option<Place>) {
+    let opening = Opening { path, place };
+    if context.unsaved.get_untracked() {
+        context.opening.set(Some(opening));
+        return;
+    }
+    load(context, opening);
+}
+
+/// Opens a file in the editor, discarding any unsaved changes.
+fn load(context: &Context, Opening { path, place }: Opening) {
+    let context = *context;
+    spawn_local(async move {
+        let Some(data) = Inter::load_file(path.clone()).await else {
+            let problem = format!("{} couldn't be read", path.display());
+            report(context.problems, "project", &problem);
+            return;
+        };
+        let range = place.map(|place| place.range(&data));
+        context.load(data);
+        (context.save_path.1)(Some(path));
+        if let Some(range) = range {
+            context.text_area.get_untracked().unwrap().focus().unwrap();
+            context.select_range(range);
+        }
+    });
+}
+
+/// Asks whether to save or discard the unsaved changes of the document before opening another
+/// file, opening it once the document is saved.
+#[component]
+pub fn UnsavedChanges() -> impl IntoView {
+    let context = use_context::<Context>().unwrap();
+    let Context {
+        opening,
+        unsaved,
+        save,
+        text_area,
+        save_path: (read_save_path, _),
+        ..
+    } = context;
+
+    create_effect(move |_| {
+        if unsaved() {
+            return;
+        }
+        if let Some(file) = opening.get_untracked() {
+            opening.set(None);
+            load(&context, file);
+        }
+    });
+
+    let cancel = move || {
+        opening.set(None);
+        text_area.get_untracked().unwrap().focus().unwrap();
+    };
+    let discard = move || {
+        if let Some(file) = opening.get_untracked() {
+            opening.set(None);
+            load(&context, file);
+        }
+    };
+
+    window_event_listener(keydown, move |event| {
+        if event.key() == "Escape" && opening.with_untracked(Option::is_some) {
+            cancel();
+        }
+    });
+
+    view! {
+        <Popup show=Signal::derive(move || opening.with(Option::is_some))>
+            <div class="flex justify-between gap-2 px-1">
+                <div>
+                    {move || {
+                        let name = read_save_path()
+                            .and_then(|path| Some(path.file_name()?.to_string_lossy().to_string()))
+                            .unwrap_or_else(|| "the document".to_string());
+                        format!("{name} has unsaved changes")
+                    }}
+                </div>
+                <div class="flex gap-2">
+                    <div class="cursor-pointer text-accent" on:click=move |_| save.dispatch(false)>
+                        "save"
+                    </div>
+                    <div class="cursor-pointer text-accent" on:click=move |_| discard()>
+                        "discard"
+                    </div>
+                    <div class="cursor-pointer text-accent" on:click=move |_| cancel()>
+                        "cancel"
+                    </div>
+                </div>
+            </div>
+        </Popup>
+    }
+}
+
+/// The state of the sidebar shared by every entry in it.
+#[derive(Clone, Copy)]
+struct Tree {
+    context: Context,
+    entries: RwSignal<Vec<Entry>>,
+    /// The folders whose entries aren't shown.
+    collapsed: RwSignal<HashSet<PathBuf>>,
+    naming: RwSignal<Option<Naming>>,
+    /// The entry asked to be deleted, which needs confirming.
+    deleting: RwSignal<Option<PathBuf>>,
+    dragged: RwSignal<Option<PathBuf>>,
+}
+
+impl Tree {
+    fn refresh(self) {
+        let Some(root) = self.context.project.0.get_untracked() else {
+            self.entries.set(Vec::new());
+            return;
+        };
+        spawn_local(async move {
+            let (entries, problems) = Inter::list_project(root).await;
+            for problem in problems {
+                report(self.context.problems, "project", &problem);
+            }
+            self.entries.set(entries);
+        });
+    }
+
+    /// Reports what went wrong with an operation on the project, if anything, then lists it
+    /// again.
+    fn finish(self, error: Option<String>) {
+        if let Some(error) = error {
+            report(self.context.problems, "project", &error);
+        }
+        self.refresh();
+        index_links(&self.context);
+    }
+
+    fn create(self, folder: &Path, name: &str, is_folder: bool) {
+        let Some(name) = entry_name(name, is_folder) else {
+            return;
+        };
+        let path = folder.join(name);
+        spawn_local(async move {
+            if is_folder {
+                self.finish(Inter::create_folder(path).await);
+                return;
+            }
+            let error = Inter::create_file(path.clone()).await;
+            if error.is_none() {
+                open_file(&self.context, path, None);
+            }
+            self.finish(error);
+        });
+    }
+
+    /// Renames or moves a file or a folder, following the document if it's the one moved or in
+    /// it.
+    fn rename(self, path: PathBuf, to: PathBuf) {
+        if path == to {
+            return;
+        }
+        let (read_save_path, write_save_path) = self.context.save_path;
+        spawn_local(async move {
+            let error = Inter::rename_path(path.clone(), to.clone()).await;
+            if error.is_none() {
+                if let Some(moved) = read_save_path
+                    .get_untracked()
+                    .and_then(|current| moved_path(&current, &path, &to))
+                {
+                    write_save_path(Some(moved));
+                }
+            }
+            self.finish(error);
+        });
+    }
+
+    /// Deletes a file or an empty folder. The document stays open as an unsaved one if it's
+    /// deleted.
+    fn delete(self, path: PathBuf) {
+        let (read_save_path, write_save_path) = self.context.save_path;
+        spawn_local(async move {
+            let error = Inter::delete_path(path.clone()).await;
+            if error.is_none()
+                && read_save_path
+                    .get_untracked()
+                    .is_some_and(|current| current.starts_with(&path))
+            {
+                write_save_path(None);
+            }
+            self.finish(error);
+        });
+    }
+
+    /// Applies the name typed in the sidebar.
+    fn name(self, name: &str) {
+        let Some(naming) = self.naming.get_untracked() else {
+            return;
+        };
+        self.naming.set(None);
+        match naming {
+            Naming::File(folder) => self.create(&folder, name, false),
+            Naming::Folder(folder) => self.create(&folder, name, true),
+            Naming::Rename(path) => {
+                let is_folder = self.is_folder(&path);
+                let (Some(folder), Some(name)) = (path.parent(), entry_name(name, is_folder))
+                else {
+                    return;
+                };
+                let to = folder.join(name);
+                self.rename(path, to);
+            }
+        }
+    }
+
+    fn is_folder(self, path: &Path) -> bool {
+        fn find(entries: &[Entry], path: &Path) -> Option<bool> {
+            entries.iter().find_map(|entry| {
+                if entry.path == path {
+                    return Some(entry.children.is_some());
+                }
+                find(entry.children.as_deref()?, path)
+            })
+        }
+        self.entries
+            .with_untracked(|entries| find(entries, path))
+            .unwrap_or_default()
+    }
+
+    /// Moves the entry being dragged into a folder.
+    fn drop_into(self, folder: &Path) {
+        let Some(path) = self.dragged.get_untracked() else {
+            return;
+        };
+        self.dragged.set(None);
+        if !can_move(&path, folder) {
+            return;
+        }
+        let Some(name) = path.file_name() else {
+            return;
+        };
+        let to = folder.join(name);
+        self.rename(path, to);
+    }
+
+    /// Shows the input for a name, which is focused as soon as it's shown.
+    fn name_input(self, initial: String, depth: usize) -> View {
+        let input: NodeRef<Input> = create_node_ref();
+        input.on_load(|input| {
+            request_animation_frame(move || {
+                input.focus().unwrap();
+                input.select();
+            });
+        });
+        view! {
+            <input
+                type="text"
+                class="mx-1 outline-none select-text text-text bg-background cursor-text selection:bg-fade"
+                style:margin-left=indent(depth)
+                autocorrect="off"
+                ref=input
+                prop:value=initial
+                on:keydown=move |event| {
+                    match event.key().as_str() {
+                        "Enter" => self.name(&event_target_value(&event)),
+                        "Escape" => self.naming.set(None),
+                        _ => return,
+                    }
+                    event.prevent_default();
+                    event.stop_propagation();
+                }
+                on:blur=move |_| self.naming.set(None)
+            />
+        }
+        .into_view()
+    }
+
+    /// Shows the entries in a folder, nested some folders deep.
+    fn view(self, folder: &Path, entries: &[Entry], depth: usize) -> View {
+        let mut rows = Vec::new();
+        for kind in [
+            Naming::Folder(folder.to_path_buf()),
+            Naming::File(folder.to_path_buf()),
+        ] {
+            if self.naming.with(|naming| naming.as_ref() == Some(&kind)) {
+                rows.push(self.name_input(String::new(), depth));
+            }
+        }
+        for entry in entries {
+            let renaming = Naming::Rename(entry.path.clone());
+            if self
+                .naming
+                .with(|naming| naming.as_ref() == Some(&renaming))
+            {
+                rows.push(self.name_input(entry.name.clone(), depth));
+            } else {
+                rows.push(self.row(entry, depth));
+            }
+            if let Some(children) = &entry.children {
+                if !self
+                    .collapsed
+                    .with(|collapsed| collapsed.contains(&entry.path))
+                {
+                    rows.push(self.view(&entry.path, children, depth + 1));
+                }
+            }
+        }
+        rows.into_iter().collect_view()
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn row(self, entry: &Entry, depth: usize) -> View {
+        let Self {
+            context,
+            collapsed,
+            naming,
+            deleting,
+            dragged,
+            ..
+        } = self;
+        let read_save_path = context.save_path.0;
+        let is_folder = entry.children.is_some();
+        let label = if is_folder {
+            let open = !collapsed.with(|collapsed| collapsed.contains(&entry.path));
+            format!(
+                "{} {}",
+                if open { "\u{25be}" } else { "\u{25b8}" },
+                entry.name
+            )
+        } else {
+            entry.name.trim_end_matches(MARKDOWN_EXTENSION).to_string()
+        };
+        let path = entry.path.clone();
+        let current = {
+            let path = path.clone();
+            move || read_save_path.with(|current| current.as_ref() == Some(&path))
+        };
+        let click = {
+            let path = path.clone();
+            move || {
+                if is_folder {
+                    collapsed.update(|collapsed| {
+                        if !collapsed.remove(&path) {
+                            collapsed.insert(path.clone());
+                        }
+                    });
+                } else if !read_save_path.with_untracked(|current| current.as_ref() == Some(&path))
+                {
+                    open_file(&context, path.clone(), None);
+                }
+            }
+        };
+        let drop_folder = if is_folder {
+            path.clone()
+        } else {
+            path.parent().unwrap_or(&path).to_path_buf()
+        };
+        let drag_path = path.clone();
+        let actions = if deleting.with(|deleting| deleting.as_ref() == Some(&path)) {
+            let path = path.clone();
+            view! {
+                <div class="flex gap-1">
+                    <div class="text-error">"delete?"</div>
+                    <div
+                        class="cursor-pointer text-accent"
+                        on:click=move |event| {
+                            event.stop_propagation();
+                            deleting.set(None);
+                            self.delete(path.clone());
+                        }
+                    >
+                        "yes"
+                    </div>
+                    <div
+                        class="cursor-pointer text-accent"
+                        on:click=move |event| {
+                            event.stop_propagation();
+                            deleting.set(None);
+                        }
+                    >
+                        "no"
+                    </div>
+                </div>
+            }
+            .into_view()
+        } else {
+            let adding = is_folder.then(|| {
+                let (file_folder, folder_folder) = (path.clone(), path.clone());
+                view! {
+                    <div
+                        class="cursor-pointer text-accent"
+                        title="New file"
+                        on:click=move |event| {
+                            event.stop_propagation();
+                            collapsed.update(|collapsed| {
+                                collapsed.remove(&file_folder);
+                            });
+                            naming.set(Some(Naming::File(file_folder.clone())));
+                        }
+                    >
+                        "+"
+                    </div>
+                    <div
+                        class="cursor-pointer text-accent"
+                        title="New folder"
+                        on:click=move |event| {
+                            event.stop_propagation();
+                            collapsed.update(|collapsed| {
+                                collapsed.remove(&folder_folder);
+                            });
+                            naming.set(Some(Naming::Folder(folder_folder.clone())));
+                        }
+                    >
+                        "+/"
+                    </div>
+                }
+            });
+            let (rename_path, delete_path) = (path.clone(), path.clone());
+            view! {
+                <div class="hidden gap-1 group-hover:flex">
+                    {adding}
+                    <div
+                        class="cursor-pointer text-accent"
+                        title="Rename"
+                        on:click=move |event| {
+                            event.stop_propagation();
+                            naming.set(Some(Naming::Rename(rename_path.clone())));
+                        }
+                    >
+                        "\u{270e}"
+                    </div>
+                    <div
+                        class="cursor-pointer text-accent"
+                        title="Delete"
+                        on:click=move |event| {
+                            event.stop_propagation();
+                            deleting.set(Some(delete_path.clone()));
+                        }
+                    >
+                        "\u{00d7}"
+                    </div>
+                </div>
+            }
+            .into_view()
+        };
+        view! {
+            <div
+                class="flex justify-between gap-1 px-1 rounded cursor-pointer group"
+                class=("bg-highlight", current.clone())
+                class=("text-text", current)
+                style:padding-left=indent(depth)
+                title=path.display().to_string()
+                draggable="true"
+                on:click=move |_| click()
+                on:dragstart=move |_| dragged.set(Some(drag_path.clone()))
+                on:dragend=move |_| dragged.set(None)
+                on:dragover=|event| event.prevent_default()
+                on:drop=move |event| {
+                    event.prevent_default();
+                    event.stop_propagation();
+                    self.drop_into(&drop_folder);
+                }
+            >
+                <div class="truncate">{label}</div>
+                {actions}
+            </div>
+        }
+        .into_view()
+    }
+}
+
+/// Gets the indentation of an entry nested some folders deep.
+#[allow(clippy::cast_precision_loss)]
+fn indent(depth: usize) -> String {
+    format!("{}rem", (depth as f64).mul_add(0.75, 0.25))
+}
+
+/// Lists the files and folders in the project, and lets them be opened, created, renamed, moved
+/// by dragging them onto a folder, and deleted.
+#[component]
+pub fn Sidebar() -> impl IntoView {
+    let context = use_context::<Context>().unwrap();
+    let Context {
+        project: (read_project, _),
+        sidebar,
+        ..
+    } = context;
+    let tree = Tree {
+        context,
+        entries: create_rw_signal(Vec::new()),
+        collapsed: create_rw_signal(HashSet::new()),
+        naming: create_rw_signal(None),
+        deleting: create_rw_signal(None),
+        dragged: create_rw_signal(None),
+    };
+
+    create_effect(move |_| {
+        read_project.track();
+        tree.refresh();
+    });
+
+    move || {
+        let root = read_project()?;
+        if !sidebar() {
+            return None;
+        }
+        let name = root.file_name().map_or_else(
+            || root.display().to_string(),
+            |name| name.to_string_lossy().to_string(),
+        );
+        let (file_root, folder_root, drop_root) = (root.clone(), root.clone(), root.clone());
+        Some(view! {
+            <div
+                class="flex flex-col w-56 gap-1 pt-20 pb-4 pl-4 overflow-y-auto text-xs shrink-0 text-fade select-none"
+                on:dragover=|event| event.prevent_default()
+                on:drop=move |event| {
+                    event.prevent_default();
+                    tree.drop_into(&drop_root);
+                }
+            >
+                <div class="flex justify-between gap-1 px-1 group">
+                    <div class="truncate text-text" title=root.display().to_string()>
+                        {name}
+                    </div>
+                    <div class="flex gap-1">
+                        <div
+                            class="cursor-pointer text-accent"
+                            title="New file"
+                            on:click=move |_| tree.naming.set(Some(Naming::File(file_root.clone())))
+                        >
+                            "+"
+                        </div>
+                        <div
+                            class="cursor-pointer text-accent"
+                            title="New folder"
+                            on:click=move |_| {
+                                tree.naming.set(Some(Naming::Folder(folder_root.clone())));
+                            }
+                        >
+                            "+/"
+                        </div>
+                    </div>
+                </div>
+                {move || {
+                    let root = read_project.get_untracked()?;
+                    Some(tree.entries.with(|entries| tree.view(&root, entries, 0)))
+                }}
+            </div>
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finding_lines_works() {
+        let text = "# Title\nfirst line\n\nlast";
+        assert_eq!(&text[line_range(text, 0)], "# Title");
+        assert_eq!(&text[line_range(text, 1)], "first line");
+        assert_eq!(&text[line_range(text, 2)], "");
+        assert_eq!(&text[line_range(text, 3)], "last");
+        assert_eq!(line_range(text, 9), text.len()..text.len());
+    }
+
+    #[test]
+    fn finding_places_works() {
+        let text = "one\ntwo three\n";
+        let place = |line, columns| Place { line, columns };
+        assert_eq!(&text[place(1, None).range(text)], "two three");
+        assert_eq!(&text[place(1, Some(4..9)).range(text)], "three");
+        assert_eq!(&text[place(0, Some(2..8)).range(text)], "e");
+        assert_eq!(place(5, None).range(text), text.len()..text.len());
+    }
+
+    #[test]
+    fn checking_entry_names_works() {
+        assert_eq!(
+            entry_name(" Chapter 1 ", false).as_deref(),
+            Some("Chapter 1.md")
+        );
+        assert_eq!(entry_name("notes.md", false).as_deref(), Some("notes.md"));
+        assert_eq!(entry_name("Part One", true).as_deref(), Some("Part One"));
+        assert_eq!(entry_name("", false), None);
+        assert_eq!(entry_name("..", true), None);
+        assert_eq!(entry_name("a/b", false), None);
+    }
+
+    #[test]
+    fn moving_paths_works() {
+        let (from, to) = (
+            Path::new("/book/drafts"),
+            Path::new("/book/part one/drafts"),
+        );
+        assert_eq!(
+            moved_path(Path::new("/book/drafts/one.md"), from, to),
+            Some(PathBuf::from("/book/part one/drafts/one.md"))
+        );
+        assert_eq!(moved_path(from, from, to), Some(to.to_path_buf()));
+        assert_eq!(moved_path(Path::new("/book/one.md"), from, to), None);
+
+        assert!(can_move(
+            Path::new("/book/one.md"),
+            Path::new("/book/drafts")
+        ));
+        assert!(!can_move(Path::new("/book/one.md"), Path::new("/book")));
+        assert!(!can_move(from, Path::new("/book/drafts/old")));
+        assert!(!can_move(from, from));
+    }
+}
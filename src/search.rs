@@ -0,0 +1,224 @@
+use std::path::PathBuf;
+
+use leptos::ev::keydown;
+use leptos::html::Input;
+use leptos::{
+    component, create_effect, create_node_ref, create_rw_signal, event_target_value, spawn_local,
+    use_context, view, window_event_listener, CollectView, IntoView, NodeRef, RwSignal,
+    SignalGetUntracked, SignalSet, SignalUpdate, SignalWith, SignalWithUntracked,
+};
+use serde::Deserialize;
+use typewriter_core::find::Query;
+
+use crate::find::{toggle_keydown, Toggles};
+use crate::offset::{byte_to_grapheme, grapheme_to_byte};
+use crate::project::{open_file, Place};
+use crate::{Context, Horizontal, Inter, Popup, Vertical};
+
+#[derive(Debug, Clone, Deserialize)]
+struct SearchResult {
+    id: u32,
+    path: PathBuf,
+    line: usize,
+    start: usize,
+    end: usize,
+    text: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SearchFinished {
+    id: u32,
+    count: usize,
+    error: Option<String>,
+}
+
+/// How many graphemes of a result's line to show before the match.
+const CONTEXT: usize = 40;
+
+#[component]
+#[allow(clippy::too_many_lines)]
+pub fn FolderSearch(show: RwSignal<bool>) -> impl IntoView {
+    let context = use_context::<Context>().unwrap();
+    let root = create_rw_signal(None::<PathBuf>);
+    let query = create_rw_signal(Query::default());
+    let search = create_rw_signal(None);
+    let searched = create_rw_signal(None);
+    let results = create_rw_signal(Vec::<SearchResult>::new());
+    let finished = create_rw_signal(None::<SearchFinished>);
+    let selected = create_rw_signal(0);
+    let input: NodeRef<Input> = create_node_ref();
+
+    spawn_local(async move {
+        Inter::listen("search-result", move |result: SearchResult| {
+            results.update(|results| results.push(result));
+        })
+        .await;
+        Inter::listen("search-finished", move |search: SearchFinished| {
+            finished.set(Some(search));
+        })
+        .await;
+    });
+    create_effect(move |_| {
+        if show() {
+            input.get_untracked().unwrap().focus().unwrap();
+        }
+    });
+
+    let run = move |pick_root: bool| {
+        if query.with_untracked(|query| query.pattern.is_empty()) {
+            return;
+        }
+        spawn_local(async move {
+            let Some((new_root, id)) = Inter::search_folder(
+                root.get_untracked().filter(|_| !pick_root),
+                query.get_untracked(),
+            )
+            .await
+            else {
+                return;
+            };
+            root.set(Some(new_root));
+            search.set(Some(id));
+            searched.set(Some(query.get_untracked()));
+            selected.set(0);
+            results.update(|results| results.retain(|result| result.id == id));
+        });
+    };
+    let current_results = move || {
+        results.with(|results| {
+            results
+                .iter()
+                .filter(|result| Some(result.id) == search())
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+    };
+    let open = move |result: SearchResult| {
+        show.set(false);
+        let place = Place {
+            line: result.line,
+            columns: Some(result.start..result.end),
+        };
+        open_file(&context, result.path, Some(place));
+    };
+
+    window_event_listener(keydown, move |event| {
+        if event.key() == "Escape" && show.get_untracked() {
+            show.set(false);
+        }
+    });
+
+    view! {
+        <Popup show=show>
+            <Vertical gap=2>
+                <Horizontal gap=3>
+                    <Horizontal gap=1 class="grow">
+                        <div class="text-text">"search:"</div>
+                        <input
+                            type="text"
+                            class="outline-none grow select-text text-text bg-highlight cursor-text selection:bg-fade"
+                            autocorrect="off"
+                            ref=input
+                            prop:value=move || query.with(|query| query.pattern.clone())
+                            on:input=move |event| {
+                                query.update(|query| query.pattern = event_target_value(&event));
+                            }
+                            on:keydown=move |event| {
+                                toggle_keydown(query, &event);
+                                let results = current_results();
+                                match event.key().as_str() {
+                                    "Enter" if event.shift_key() => run(true),
+                                    "Enter" => {
+                                        let fresh = searched.get_untracked() == Some(query.get_untracked());
+                                        match results.get(selected.get_untracked()) {
+                                            Some(result) if fresh => open(result.clone()),
+                                            _ => run(false),
+                                        }
+                                    }
+                                    "ArrowDown" => {
+                                        selected
+                                            .update(|selected| {
+                                                *selected = (*selected + 1).min(results.len().saturating_sub(1));
+                                            });
+                                    }
+                                    "ArrowUp" => {
+                                        selected.update(|selected| *selected = selected.saturating_sub(1));
+                                    }
+                                    _ => return,
+                                }
+                                event.prevent_default();
+                            }
+                        />
+                    </Horizontal>
+                    <Horizontal gap=2>
+                        <Toggles query=query />
+                    </Horizontal>
+                    <div
+                        class="cursor-pointer text-accent"
+                        title="Choose folder (sh-Enter)"
+                        on:click=move |_| run(true)
+                    >
+                        {move || {
+                            root()
+                                .and_then(|root| Some(root.file_name()?.to_string_lossy().to_string()))
+                                .unwrap_or_else(|| "folder…".to_string())
+                        }}
+                    </div>
+                </Horizontal>
+                <div class="text-accent">
+                    {move || {
+                        let id = search()?;
+                        let count = results
+                            .with(|results| results.iter().filter(|result| result.id == id).count());
+                        Some(
+                            match finished().filter(|finished| finished.id == id) {
+                                None => format!("{count} results so far…"),
+                                Some(SearchFinished { error: Some(error), .. }) => error,
+                                Some(SearchFinished { count, .. }) => format!("{count} results"),
+                            },
+                        )
+                    }}
+                </div>
+                <Vertical>
+                    {move || {
+                        let root = root().unwrap_or_default();
+                        current_results()
+                            .into_iter()
+                            .enumerate()
+                            .map(|(index, result)| {
+                                let path = result
+                                    .path
+                                    .strip_prefix(&root)
+                                    .unwrap_or(&result.path)
+                                    .to_string_lossy()
+                                    .to_string();
+                                let line = format!("{path}:{}", result.line + 1);
+                                let before = &result.text[..result.start];
+                                let skipped = byte_to_grapheme(before, before.len()).saturating_sub(CONTEXT);
+                                let before = before[grapheme_to_byte(before, skipped)..]
+                                    .trim_start()
+                                    .to_string();
+                                let found = result.text[result.start..result.end].to_string();
+                                let after = result.text[result.end..].to_string();
+                                view! {
+                                    <div
+                                        class="flex gap-2 px-1 overflow-hidden rounded cursor-pointer whitespace-nowrap"
+                                        class=("bg-background", move || selected() == index)
+                                        on:click=move |_| open(result.clone())
+                                    >
+                                        <div class="shrink-0">{line}</div>
+                                        <div class="truncate">
+                                            {before}
+                                            <span class="rounded text-text bg-fade">{found}</span>
+                                            {after}
+                                        </div>
+                                    </div>
+                                }
+                            })
+                            .collect_view()
+                    }}
+                </Vertical>
+            </Vertical>
+        </Popup>
+    }
+}
@@ -0,0 +1,231 @@
+//! Searching the open project through the full-text index the backend keeps of its Markdown
+//! files.
+
+use std::path::{Path, PathBuf};
+
+use leptos::ev::keydown;
+use leptos::html::Input;
+use leptos::{
+    component, create_effect, create_node_ref, create_rw_signal, event_target_value, spawn_local,
+    use_context, view, window_event_listener, CollectView, IntoView, NodeRef, RwSignal,
+    SignalGetUntracked, SignalSet, SignalUpdate, SignalWith, SignalWithUntracked,
+};
+use serde::Deserialize;
+
+use crate::problems::report;
+use crate::project::{open_file, Place};
+use crate::{Context, Inter, Popup, Vertical};
+
+/// A section of a file in the project that matches a query.
+#[derive(Debug, Clone, Deserialize)]
+pub struct IndexResult {
+    pub path: PathBuf,
+    /// The heading of the section, which is empty before the first heading.
+    pub heading: String,
+    /// The line of the first match in the section.
+    pub line: usize,
+    /// The text of that line.
+    pub text: String,
+}
+
+/// Describes where a result is, by the section's heading if it has one and the file's path in the
+/// project.
+pub fn location(result: &IndexResult, root: &Path) -> String {
+    let path = result
+        .path
+        .strip_prefix(root)
+        .unwrap_or(&result.path)
+        .display()
+        .to_string();
+    if result.heading.is_empty() {
+        path
+    } else {
+        format!("{path} \u{203a} {}", result.heading)
+    }
+}
+
+/// Keeps the backend indexing the open project, and reports what goes wrong doing it.
+pub fn track_search_index(context: &Context) {
+    let Context {
+        project: (read_project, _),
+        problems,
+        ..
+    } = *context;
+    spawn_local(async move {
+        Inter::listen("search-index-error", move |error: String| {
+            report(problems, "search index", &error);
+        })
+        .await;
+    });
+    create_effect(move |_| {
+        if let Some(root) = read_project() {
+            spawn_local(Inter::open_search_index(root));
+        }
+    });
+}
+
+#[component]
+#[allow(clippy::too_many_lines)]
+pub fn IndexSearch(show: RwSignal<bool>) -> impl IntoView {
+    let context = use_context::<Context>().unwrap();
+    let Context {
+        project: (read_project, _),
+        text_area,
+        ..
+    } = context;
+    let query = create_rw_signal(String::new());
+    let results = create_rw_signal(Vec::<IndexResult>::new());
+    let message = create_rw_signal(None::<String>);
+    let selected = create_rw_signal(0);
+    // The number of queries sent, so results of older ones that arrive late are dropped.
+    let sent = create_rw_signal(0_u32);
+    let input: NodeRef<Input> = create_node_ref();
+
+    create_effect(move |_| {
+        if show() {
+            let input = input.get_untracked().unwrap();
+            input.focus().unwrap();
+            input.select();
+        }
+    });
+
+    let run = move || {
+        sent.update(|sent| *sent += 1);
+        let id = sent.get_untracked();
+        let query = query.get_untracked();
+        spawn_local(async move {
+            let (found, error) = Inter::query_search_index(query.clone()).await;
+            if sent.get_untracked() != id {
+                return;
+            }
+            message.set(error.or_else(|| {
+                (!query.trim().is_empty()).then(|| {
+                    format!(
+                        "{} result{}",
+                        found.len(),
+                        if found.len() == 1 { "" } else { "s" }
+                    )
+                })
+            }));
+            selected.set(0);
+            results.set(found);
+        });
+    };
+    let close = move || {
+        show.set(false);
+        text_area.get_untracked().unwrap().focus().unwrap();
+    };
+    let open = move |index: usize| {
+        let Some(result) = results.with_untracked(|results| results.get(index).cloned()) else {
+            return;
+        };
+        close();
+        let place = Place {
+            line: result.line,
+            columns: None,
+        };
+        open_file(&context, result.path, Some(place));
+    };
+
+    window_event_listener(keydown, move |event| {
+        if event.key() == "Escape" && show.get_untracked() {
+            close();
+        }
+    });
+
+    view! {
+        <Popup show=show>
+            <Vertical gap=2>
+                <div class="flex gap-1">
+                    <div class="text-text">"search project:"</div>
+                    <input
+                        type="text"
+                        class="outline-none grow select-text text-text bg-highlight cursor-text selection:bg-fade"
+                        autocorrect="off"
+                        placeholder="words and \"phrases\""
+                        ref=input
+                        prop:value=query
+                        on:input=move |event| {
+                            query.set(event_target_value(&event));
+                            run();
+                        }
+                        on:keydown=move |event| {
+                            let count = results.with_untracked(Vec::len);
+                            match event.key().as_str() {
+                                "Enter" => open(selected.get_untracked()),
+                                "ArrowDown" => {
+                                    selected
+                                        .update(|selected| {
+                                            *selected = (*selected + 1).min(count.saturating_sub(1));
+                                        });
+                                }
+                                "ArrowUp" => {
+                                    selected.update(|selected| *selected = selected.saturating_sub(1));
+                                }
+                                _ => return,
+                            }
+                            event.prevent_default();
+                        }
+                    />
+                </div>
+                {move || {
+                    read_project()
+                        .is_none()
+                        .then(|| view! { <div>"open a project folder to search its files"</div> })
+                }}
+                <div class="text-accent">{message}</div>
+                <Vertical class="overflow-y-auto max-h-80">
+                    {move || {
+                        let root = read_project().unwrap_or_default();
+                        results
+                            .with(|results| {
+                                results
+                                    .iter()
+                                    .enumerate()
+                                    .map(|(index, result)| {
+                                        let location = format!(
+                                            "{}:{}",
+                                            location(result, &root),
+                                            result.line + 1,
+                                        );
+                                        let text = result.text.clone();
+                                        view! {
+                                            <div
+                                                class="px-1 overflow-hidden rounded cursor-pointer whitespace-nowrap"
+                                                class=("bg-background", move || selected() == index)
+                                                on:click=move |_| open(index)
+                                            >
+                                                <div class="truncate text-text">{location}</div>
+                                                <div class="truncate">{text}</div>
+                                            </div>
+                                        }
+                                    })
+                                    .collect_view()
+                            })
+                    }}
+                </Vertical>
+            </Vertical>
+        </Popup>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describing_locations_works() {
+        let mut result = IndexResult {
+            path: PathBuf::from("/notes/ideas/plot.md"),
+            heading: "Act Two".to_string(),
+            line: 4,
+            text: String::new(),
+        };
+        assert_eq!(
+            location(&result, Path::new("/notes")),
+            "ideas/plot.md \u{203a} Act Two"
+        );
+        result.heading.clear();
+        assert_eq!(location(&result, Path::new("/notes")), "ideas/plot.md");
+    }
+}
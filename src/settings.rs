@@ -0,0 +1,479 @@
+//! Everything the user can configure besides the keymap, stored by the backend in
+//! `settings.json` in the config directory.
+//!
+//! The appearance settings are applied as CSS variables on the root element, which the tailwind
+//! colours and the `text-editor`, `h-line` and `px-column` utilities refer to.
+
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use leptos::ev::keydown;
+use leptos::{
+    component, event_target_checked, event_target_value, use_context, view, window_event_listener,
+    IntoView, RwSignal, Show, SignalGetUntracked, SignalSet, SignalUpdate, SignalWith,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::focus::Granularity;
+use crate::lint::{Rule, Rules};
+use crate::{Context, Horizontal, Popup, Vertical};
+
+/// An opaque colour, written as `#rrggbb`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Color(pub [u8; 3]);
+
+impl FromStr for Color {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("`{string}` isn't a colour like `#rrggbb`");
+        let hex = string.strip_prefix('#').ok_or_else(invalid)?;
+        if hex.len() != 6 || !hex.is_ascii() {
+            return Err(invalid());
+        }
+        let mut channels = [0; 3];
+        for (index, channel) in channels.iter_mut().enumerate() {
+            *channel =
+                u8::from_str_radix(&hex[index * 2..index * 2 + 2], 16).map_err(|_| invalid())?;
+        }
+        Ok(Self(channels))
+    }
+}
+
+impl TryFrom<String> for Color {
+    type Error = String;
+
+    fn try_from(string: String) -> Result<Self, Self::Error> {
+        string.parse()
+    }
+}
+
+impl From<Color> for String {
+    fn from(color: Color) -> Self {
+        color.to_string()
+    }
+}
+
+impl Display for Color {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        let [red, green, blue] = self.0;
+        write!(formatter, "#{red:02x}{green:02x}{blue:02x}")
+    }
+}
+
+/// The colours of the editor, named like the tailwind colours they back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Palette {
+    pub background: Color,
+    pub highlight: Color,
+    pub accent: Color,
+    pub fade: Color,
+    pub text: Color,
+    pub caret: Color,
+    /// The underline of misspelled words.
+    pub error: Color,
+}
+
+impl Palette {
+    pub const DARK: Self = Self {
+        background: Color([0x22, 0x22, 0x22]),
+        highlight: Color([0x08, 0x07, 0x07]),
+        accent: Color([0x88, 0x88, 0x88]),
+        fade: Color([0x54, 0x53, 0x54]),
+        text: Color([0xee, 0xee, 0xee]),
+        caret: Color([0xee, 0xee, 0xee]),
+        error: Color([0xe0, 0x6c, 0x5f]),
+    };
+    pub const LIGHT: Self = Self {
+        background: Color([0xf7, 0xf5, 0xf0]),
+        highlight: Color([0xe4, 0xe0, 0xd8]),
+        accent: Color([0x6f, 0x6b, 0x66]),
+        fade: Color([0xb5, 0xb0, 0xa8]),
+        text: Color([0x22, 0x22, 0x22]),
+        caret: Color([0x22, 0x22, 0x22]),
+        error: Color([0xc0, 0x39, 0x2b]),
+    };
+
+    const fn colors(&self) -> [(&'static str, Color); 7] {
+        [
+            ("background", self.background),
+            ("highlight", self.highlight),
+            ("accent", self.accent),
+            ("fade", self.fade),
+            ("text", self.text),
+            ("caret", self.caret),
+            ("error", self.error),
+        ]
+    }
+
+    const fn colors_mut(&mut self) -> [(&'static str, &mut Color); 7] {
+        [
+            ("background", &mut self.background),
+            ("highlight", &mut self.highlight),
+            ("accent", &mut self.accent),
+            ("fade", &mut self.fade),
+            ("text", &mut self.text),
+            ("caret", &mut self.caret),
+            ("error", &mut self.error),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+    Custom(Palette),
+}
+
+impl Theme {
+    pub const fn palette(&self) -> &Palette {
+        match self {
+            Self::Dark => &Palette::DARK,
+            Self::Light => &Palette::LIGHT,
+            Self::Custom(palette) => palette,
+        }
+    }
+
+    const fn name(&self) -> &'static str {
+        match self {
+            Self::Dark => "dark",
+            Self::Light => "light",
+            Self::Custom(_) => "custom",
+        }
+    }
+}
+
+/// The font family bundled in `fonts/`.
+pub const BUNDLED_FONT: &str = "DejaVu";
+
+/// Everything the user can configure besides the keymap, mirroring `Settings` in the backend,
+/// which validates and stores them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Settings {
+    pub theme: Theme,
+    /// The name of the font family, which has to be monospace for the overlay to line up with
+    /// the text area.
+    pub font_family: String,
+    /// The font size in pixels.
+    pub font_size: f64,
+    /// The height of a row relative to the font size.
+    pub line_height: f64,
+    /// The most characters a row of the text column holds. Narrower windows make the column
+    /// narrower.
+    pub column_width: usize,
+    /// Whether everything but the sentence or paragraph at the caret is dimmed.
+    pub focus: bool,
+    pub focus_granularity: Granularity,
+    /// Whether the view scrolls to keep the caret's row at a fixed height as it moves.
+    pub typewriter: bool,
+    /// How far down the view typewriter scrolling keeps the caret's row, from 0 to 1.
+    pub typewriter_position: f64,
+    /// Whether misspelled words are underlined.
+    pub spell_check: bool,
+    /// The Hunspell dictionary spelling is checked with, like `en_US`.
+    pub spelling_language: String,
+    /// Which style rules flag the text.
+    pub style: Rules,
+    /// The words to write each day, or 0 for no goal.
+    pub daily_goal: usize,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            theme: Theme::default(),
+            font_family: BUNDLED_FONT.to_string(),
+            font_size: 14.,
+            line_height: 1.5,
+            column_width: 96,
+            focus: false,
+            focus_granularity: Granularity::default(),
+            typewriter: false,
+            typewriter_position: 0.5,
+            spell_check: true,
+            spelling_language: "en_US".to_string(),
+            style: Rules::default(),
+            daily_goal: 0,
+        }
+    }
+}
+
+impl Settings {
+    /// Declares the CSS variables the settings are applied with.
+    pub fn css(&self) -> String {
+        let colors = self
+            .theme
+            .palette()
+            .colors()
+            .map(|(name, Color([red, green, blue]))| {
+                format!("--color-{name}: {red} {green} {blue}; ")
+            })
+            .concat();
+        format!(
+            "{colors}--font-family: \"{}\", monospace; --font-size: {}px; --line-height: {}px; --column-width: {}ch;",
+            self.font_family.replace(['"', '\\'], ""),
+            self.font_size,
+            (self.font_size * self.line_height).round(),
+            self.column_width,
+        )
+    }
+}
+
+#[component]
+#[allow(clippy::too_many_lines)]
+pub fn SettingsPanel(show: RwSignal<bool>) -> impl IntoView {
+    let Context {
+        settings,
+        text_area,
+        ..
+    } = use_context().unwrap();
+
+    window_event_listener(keydown, move |event| {
+        if event.key() == "Escape" && show.get_untracked() {
+            show.set(false);
+            text_area.get_untracked().unwrap().focus().unwrap();
+        }
+    });
+
+    let number = move |label: &'static str,
+                       get: fn(&Settings) -> f64,
+                       set: fn(&mut Settings, f64),
+                       range: (f64, f64),
+                       step: f64| {
+        view! {
+            <Horizontal gap=1>
+                <div class="w-32 text-text">{label}</div>
+                <input
+                    type="number"
+                    class="px-1 rounded outline-none grow text-text bg-background"
+                    min=range.0
+                    max=range.1
+                    step=step
+                    prop:value=move || settings.with(get)
+                    on:change=move |event| {
+                        if let Ok(value) = event_target_value(&event).parse::<f64>() {
+                            settings.update(|settings| set(settings, value.clamp(range.0, range.1)));
+                        }
+                    }
+                />
+            </Horizontal>
+        }
+    };
+
+    view! {
+        <Popup show=show>
+            <Vertical gap=2>
+                <Horizontal gap=1>
+                    <div class="w-32 text-text">"theme"</div>
+                    <select
+                        class="px-1 rounded outline-none grow text-text bg-background"
+                        prop:value=move || settings.with(|settings| settings.theme.name())
+                        on:change=move |event| {
+                            settings
+                                .update(|settings| {
+                                    settings.theme = match event_target_value(&event).as_str() {
+                                        "light" => Theme::Light,
+                                        "custom" => Theme::Custom(*settings.theme.palette()),
+                                        _ => Theme::Dark,
+                                    };
+                                });
+                        }
+                    >
+                        <option value="dark">"dark"</option>
+                        <option value="light">"light"</option>
+                        <option value="custom">"custom"</option>
+                    </select>
+                </Horizontal>
+                <Show when=move || settings.with(|settings| matches!(settings.theme, Theme::Custom(_)))>
+                    <Horizontal gap=2 class="flex-wrap">
+                        {["background", "highlight", "accent", "fade", "text", "caret", "error"]
+                            .into_iter()
+                            .map(|name| {
+                                let color = move || {
+                                    settings
+                                        .with(|settings| {
+                                            settings
+                                                .theme
+                                                .palette()
+                                                .colors()
+                                                .into_iter()
+                                                .find(|(other, _)| *other == name)
+                                                .map(|(_, color)| color.to_string())
+                                        })
+                                };
+                                view! {
+                                    <Horizontal gap=1>
+                                        <input
+                                            type="color"
+                                            class="bg-transparent cursor-pointer size-5"
+                                            prop:value=color
+                                            on:input=move |event| {
+                                                let Ok(new) = event_target_value(&event).parse::<Color>() else {
+                                                    return;
+                                                };
+                                                settings
+                                                    .update(|settings| {
+                                                        let Theme::Custom(palette) = &mut settings.theme else {
+                                                            return;
+                                                        };
+                                                        for (other, color) in palette.colors_mut() {
+                                                            if other == name {
+                                                                *color = new;
+                                                            }
+                                                        }
+                                                    });
+                                            }
+                                        />
+                                        <div>{name}</div>
+                                    </Horizontal>
+                                }
+                            })
+                            .collect::<Vec<_>>()}
+                    </Horizontal>
+                </Show>
+                <Horizontal gap=1>
+                    <div class="w-32 text-text">"font"</div>
+                    <input
+                        type="text"
+                        class="px-1 rounded outline-none grow select-text text-text bg-background cursor-text selection:bg-fade"
+                        list="fonts"
+                        autocorrect="off"
+                        prop:value=move || settings.with(|settings| settings.font_family.clone())
+                        on:change=move |event| {
+                            let family = event_target_value(&event).trim().to_string();
+                            settings
+                                .update(|settings| {
+                                    settings.font_family = if family.is_empty() {
+                                        BUNDLED_FONT.to_string()
+                                    } else {
+                                        family
+                                    };
+                                });
+                        }
+                    />
+                    <datalist id="fonts">
+                        <option value=BUNDLED_FONT></option>
+                        <option value="monospace"></option>
+                        <option value="Menlo"></option>
+                        <option value="Consolas"></option>
+                        <option value="Courier New"></option>
+                    </datalist>
+                </Horizontal>
+                <Horizontal gap=1>
+                    <div class="w-32 text-text">"spelling"</div>
+                    <input
+                        type="checkbox"
+                        class="cursor-pointer accent-fade"
+                        prop:checked=move || settings.with(|settings| settings.spell_check)
+                        on:change=move |event| {
+                            let checked = event_target_checked(&event);
+                            settings.update(|settings| settings.spell_check = checked);
+                        }
+                    />
+                    <input
+                        type="text"
+                        class="px-1 rounded outline-none grow select-text text-text bg-background cursor-text selection:bg-fade"
+                        autocorrect="off"
+                        placeholder="en_US"
+                        prop:value=move || settings.with(|settings| settings.spelling_language.clone())
+                        on:change=move |event| {
+                            let language = event_target_value(&event).trim().to_string();
+                            if !language.is_empty() {
+                                settings.update(|settings| settings.spelling_language = language);
+                            }
+                        }
+                    />
+                </Horizontal>
+                <Horizontal gap=1 class="flex-wrap">
+                    <div class="w-32 text-text">"style"</div>
+                    {Rule::ALL
+                        .into_iter()
+                        .map(|rule| {
+                            view! {
+                                <label class="flex items-center gap-1 cursor-pointer">
+                                    <input
+                                        type="checkbox"
+                                        class="cursor-pointer accent-fade"
+                                        prop:checked=move || settings.with(|settings| settings.style.enabled(rule))
+                                        on:change=move |event| {
+                                            let checked = event_target_checked(&event);
+                                            settings.update(|settings| *settings.style.enabled_mut(rule) = checked);
+                                        }
+                                    />
+                                    {rule.name()}
+                                </label>
+                            }
+                        })
+                        .collect::<Vec<_>>()}
+                </Horizontal>
+                {number(
+                    "font size",
+                    |settings| settings.font_size,
+                    |settings, size| settings.font_size = size,
+                    (8., 48.),
+                    1.,
+                )}
+                {number(
+                    "line height",
+                    |settings| settings.line_height,
+                    |settings, height| settings.line_height = height,
+                    (1., 3.),
+                    0.1,
+                )}
+                {number(
+                    "column width",
+                    #[allow(clippy::cast_precision_loss)]
+                    |settings| settings.column_width as f64,
+                    #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+                    |settings, width| settings.column_width = width as usize,
+                    (20., 400.),
+                    1.,
+                )}
+                {number(
+                    "daily goal",
+                    #[allow(clippy::cast_precision_loss)]
+                    |settings| settings.daily_goal as f64,
+                    #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+                    |settings, goal| settings.daily_goal = goal as usize,
+                    (0., 100_000.),
+                    50.,
+                )}
+            </Vertical>
+        </Popup>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing_colors_works() {
+        let color: Color = "#08a0Ff".parse().unwrap();
+        assert_eq!(color, Color([0x08, 0xa0, 0xff]));
+        assert_eq!(color.to_string(), "#08a0ff");
+        assert!("08a0ff".parse::<Color>().is_err());
+        assert!("#08a0f".parse::<Color>().is_err());
+        assert!("#08a0fg".parse::<Color>().is_err());
+        assert!("#08a0fé".parse::<Color>().is_err());
+    }
+
+    #[test]
+    fn declaring_css_works() {
+        let settings = Settings {
+            theme: Theme::Light,
+            font_family: "Fira \"Mono\"".to_string(),
+            ..Settings::default()
+        };
+        let css = settings.css();
+        assert!(css.starts_with("--color-background: 247 245 240; "));
+        assert!(css.contains("--color-caret: 34 34 34; "));
+        assert!(css.ends_with(
+            "--font-family: \"Fira Mono\", monospace; --font-size: 14px; --line-height: 21px; --column-width: 96ch;"
+        ));
+    }
+}
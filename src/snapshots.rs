@@ -0,0 +1,263 @@
+//! Browsing the snapshots the backend takes of the document each time it's saved, and restoring
+//! them.
+
+use js_sys::Date;
+use leptos::ev::keydown;
+use leptos::{
+    component, create_effect, create_memo, create_rw_signal, spawn_local, untrack, use_context,
+    view, window_event_listener, CollectView, IntoView, RwSignal, SignalGetUntracked, SignalSet,
+    SignalUpdate, SignalWith, SignalWithUntracked,
+};
+use serde::Deserialize;
+use wasm_bindgen::JsValue;
+
+use crate::diff::{hunks, DiffBase, Granularity};
+use crate::problems::report;
+use crate::{Context, Horizontal, Inter, Popup, Vertical};
+
+/// The contents of the document as it was saved at some time.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Snapshot {
+    /// When the document was saved, in milliseconds since the epoch.
+    pub time: u64,
+    /// The hash of the contents, which they're loaded by.
+    pub hash: String,
+    /// The size of the contents in bytes.
+    pub size: usize,
+}
+
+/// Formats a time in milliseconds since the epoch as a local date and time.
+pub fn time_label(time: u64) -> String {
+    #[allow(clippy::cast_precision_loss)]
+    let date = Date::new(&JsValue::from_f64(time as f64));
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        date.get_full_year(),
+        date.get_month() + 1,
+        date.get_date(),
+        date.get_hours(),
+        date.get_minutes()
+    )
+}
+
+#[component]
+#[allow(clippy::too_many_lines)]
+pub fn SnapshotHistory(show: RwSignal<bool>) -> impl IntoView {
+    let context = use_context::<Context>().unwrap();
+    let Context {
+        text,
+        text_area,
+        save_path: (read_save_path, _),
+        diff_base,
+        problems,
+        ..
+    } = context;
+    let snapshots = create_rw_signal(Vec::<Snapshot>::new());
+    let selected = create_rw_signal(0);
+    // The hash and contents of the selected snapshot once it's loaded.
+    let loaded = create_rw_signal(None::<(String, String)>);
+
+    create_effect(move |_| {
+        if !show() {
+            return;
+        }
+        // The popup takes the keys for choosing a snapshot instead of the text area.
+        text_area.get_untracked().unwrap().blur().unwrap();
+        selected.set(0);
+        snapshots.set(Vec::new());
+        let Some(path) = read_save_path.get_untracked() else {
+            return;
+        };
+        spawn_local(async move {
+            let (listed, error) = Inter::list_snapshots(path).await;
+            if let Some(error) = error {
+                report(problems, "snapshots", &error);
+            }
+            snapshots.set(listed);
+        });
+    });
+    create_effect(move |_| {
+        let Some(hash) = snapshots.with(|snapshots| Some(snapshots.get(selected())?.hash.clone()))
+        else {
+            loaded.set(None);
+            return;
+        };
+        if untrack(|| loaded.with(|loaded| loaded.as_ref().is_some_and(|(at, _)| *at == hash))) {
+            return;
+        }
+        let Some(path) = read_save_path.get_untracked() else {
+            return;
+        };
+        spawn_local(async move {
+            let (contents, error) = Inter::load_snapshot(path, hash.clone()).await;
+            if let Some(error) = error {
+                report(problems, "snapshots", &error);
+            }
+            loaded.set(contents.map(|contents| (hash, contents)));
+        });
+    });
+    // The hunks of the text that differ from the snapshot, which restoring reverts.
+    let hunks = create_memo(move |_| {
+        loaded.with(|loaded| {
+            let (_, snapshot) = loaded.as_ref()?;
+            Some(text.with(|text| hunks(snapshot, text, Granularity::Lines)))
+        })
+    });
+
+    let close = move || {
+        show.set(false);
+        text_area.get_untracked().unwrap().focus().unwrap();
+    };
+    let restore_all = move || {
+        let Some((_, snapshot)) = loaded.get_untracked() else {
+            return;
+        };
+        close();
+        context.replace_text(&snapshot);
+    };
+    let compare = move || {
+        let Some((_, snapshot)) = loaded.get_untracked() else {
+            return;
+        };
+        close();
+        diff_base.set(Some(DiffBase::Snapshot(snapshot)));
+    };
+    let restore = move |index: usize| {
+        let Some(hunk) = hunks.with_untracked(|hunks| hunks.as_ref()?.get(index).cloned()) else {
+            return;
+        };
+        let edit = text.with_untracked(|text| hunk.revert(text));
+        let end = edit.offset + edit.inserted.len();
+        context.edit(edit, end..end);
+        text_area.get_untracked().unwrap().blur().unwrap();
+    };
+
+    window_event_listener(keydown, move |event| {
+        if !show.get_untracked() {
+            return;
+        }
+        let count = snapshots.with_untracked(Vec::len);
+        match event.key().as_str() {
+            "Escape" => close(),
+            "ArrowDown" => {
+                selected
+                    .update(|selected| *selected = (*selected + 1).min(count.saturating_sub(1)));
+            }
+            "ArrowUp" => selected.update(|selected| *selected = selected.saturating_sub(1)),
+            _ => return,
+        }
+        event.prevent_default();
+    });
+
+    view! {
+        <Popup show=show>
+            <Vertical gap=2>
+                <Vertical class="overflow-y-auto max-h-32">
+                    {move || {
+                        if read_save_path().is_none() {
+                            return view! {
+                                <div class="px-1">"snapshots are taken when the document is saved"</div>
+                            }
+                                .into_view();
+                        }
+                        if snapshots.with(Vec::is_empty) {
+                            return view! { <div class="px-1">"no snapshots"</div> }.into_view();
+                        }
+                        snapshots
+                            .with(|snapshots| {
+                                snapshots
+                                    .iter()
+                                    .enumerate()
+                                    .map(|(index, snapshot)| {
+                                        let label = time_label(snapshot.time);
+                                        let size = format!("{}B", snapshot.size);
+                                        view! {
+                                            <div
+                                                class="flex justify-between px-1 rounded cursor-pointer"
+                                                class=("bg-background", move || selected() == index)
+                                                class=("text-text", move || selected() == index)
+                                                on:click=move |_| selected.set(index)
+                                            >
+                                                <div>{label}</div>
+                                                <div>{size}</div>
+                                            </div>
+                                        }
+                                    })
+                                    .collect_view()
+                            })
+                    }}
+                </Vertical>
+                {move || {
+                    let hunks = hunks()?;
+                    if hunks.is_empty() {
+                        return Some(
+                            view! { <div class="px-1">"the same as the document"</div> }.into_view(),
+                        );
+                    }
+                    let summary = format!(
+                        "{} change{} from the document",
+                        hunks.len(),
+                        if hunks.len() == 1 { "" } else { "s" },
+                    );
+                    // The text between the hunks is the same in both, so it's shown once.
+                    let pieces = text
+                        .with_untracked(|text| {
+                            let mut offset = 0;
+                            let mut pieces = Vec::new();
+                            for (index, hunk) in hunks.iter().enumerate() {
+                                pieces
+                                    .push(
+                                        view! { <span>{text[offset..hunk.range.start].to_string()}</span> }
+                                            .into_view(),
+                                    );
+                                pieces
+                                    .push(
+                                        view! {
+                                            <span
+                                                class="cursor-pointer"
+                                                title="Restore this part of the snapshot"
+                                                on:click=move |_| restore(index)
+                                            >
+                                                <span class="line-through text-error">
+                                                    {text[hunk.range.clone()].to_string()}
+                                                </span>
+                                                <span class="text-accent">{hunk.old.clone()}</span>
+                                            </span>
+                                        }
+                                            .into_view(),
+                                    );
+                                offset = hunk.range.end;
+                            }
+                            pieces.push(view! { <span>{text[offset..].to_string()}</span> }.into_view());
+                            pieces
+                        });
+                    Some(
+                        view! {
+                            <Vertical gap=2>
+                                <Horizontal class="justify-between px-1">
+                                    <div>{summary}</div>
+                                    <Horizontal gap=2>
+                                        <div
+                                            class="cursor-pointer text-accent"
+                                            title="Compare word by word in the editor"
+                                            on:click=move |_| compare()
+                                        >
+                                            "compare"
+                                        </div>
+                                        <div class="cursor-pointer text-accent" on:click=move |_| restore_all()>
+                                            "restore all"
+                                        </div>
+                                    </Horizontal>
+                                </Horizontal>
+                                <div class="px-1 overflow-y-auto break-words whitespace-pre-wrap max-h-80">
+                                    {pieces}
+                                </div>
+                            </Vertical>
+                        }
+                            .into_view(),
+                    )
+                }}
+            </Vertical>
+        </Popup>
+    }
+}
@@ -0,0 +1,281 @@
+//! Spell checking the plain text of the document with the backend's Hunspell dictionaries.
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use leptos::ev::keydown;
+use leptos::{
+    component, create_effect, create_rw_signal, spawn_local, untrack, use_context, view,
+    window_event_listener, CollectView, IntoView, RwSignal, SignalGetUntracked, SignalSet,
+    SignalUpdate, SignalWith, SignalWithUntracked,
+};
+use typewriter_core::document::text_ranges;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::history::Edit;
+use crate::problems::report;
+use crate::{Context, Inter, Popup, Vertical};
+
+/// The most suggestions offered for a misspelled word.
+const MAX_SUGGESTIONS: usize = 8;
+
+/// The words whose spelling the backend has checked, and whether each was spelled correctly.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Checked {
+    /// The language the words were checked in.
+    pub language: String,
+    pub words: HashMap<String, bool>,
+}
+
+/// Finds the byte ranges of the words in the plain text of some Markdown that can be spell
+/// checked, skipping numbers and anything without a letter in it.
+pub fn words(text: &str) -> Vec<Range<usize>> {
+    text_ranges(text)
+        .into_iter()
+        .flat_map(|range| {
+            text[range.clone()]
+                .split_word_bound_indices()
+                .map(move |(start, word)| {
+                    let trimmed = word.trim_start_matches(['\'', '’']);
+                    let start = range.start + start + word.len() - trimmed.len();
+                    start..start + trimmed.trim_end_matches(['\'', '’']).len()
+                })
+        })
+        .filter(|range| {
+            let word = &text[range.clone()];
+            word.chars().any(char::is_alphabetic) && !word.chars().any(char::is_numeric)
+        })
+        .collect()
+}
+
+/// Checks the spelling of every word in the text that hasn't been checked in the spelling
+/// language yet, whenever either changes.
+pub fn check_spelling(context: &Context) {
+    let Context {
+        text,
+        settings,
+        checked,
+        problems,
+        ..
+    } = *context;
+    create_effect(move |_| {
+        let (enabled, language) =
+            settings.with(|settings| (settings.spell_check, settings.spelling_language.clone()));
+        if !enabled {
+            return;
+        }
+        if checked.with_untracked(|checked| checked.language != language) {
+            checked.set(Checked {
+                language: language.clone(),
+                words: HashMap::new(),
+            });
+        }
+        let unchecked = text.with(|text| {
+            checked.with_untracked(|checked| {
+                words(text)
+                    .into_iter()
+                    .map(|range| &text[range])
+                    .filter(|word| !checked.words.contains_key(*word))
+                    .map(ToString::to_string)
+                    .collect::<HashSet<_>>()
+            })
+        });
+        if unchecked.is_empty() {
+            return;
+        }
+        spawn_local(async move {
+            let unchecked = unchecked.into_iter().collect::<Vec<_>>();
+            let (misspelled, error) = Inter::check_words(language.clone(), unchecked.clone()).await;
+            if let Some(error) = error {
+                report(problems, "spelling", &error);
+            }
+            let misspelled = misspelled.into_iter().collect::<HashSet<_>>();
+            checked.update(|checked| {
+                if checked.language == language {
+                    for word in unchecked {
+                        let correct = !misspelled.contains(&word);
+                        checked.words.insert(word, correct);
+                    }
+                }
+            });
+        });
+    });
+}
+
+/// Finds the byte ranges of the misspelled words in some text.
+pub fn misspelled(text: &str, checked: &Checked) -> Vec<Range<usize>> {
+    words(text)
+        .into_iter()
+        .filter(|range| checked.words.get(&text[range.clone()]) == Some(&false))
+        .collect()
+}
+
+#[component]
+#[allow(clippy::too_many_lines)]
+pub fn SpellingPopup(show: RwSignal<bool>) -> impl IntoView {
+    let context = use_context::<Context>().unwrap();
+    let Context {
+        text,
+        text_area,
+        cursor,
+        settings,
+        checked,
+        misspelled,
+        problems,
+        ..
+    } = context;
+    // The misspelled word at the caret, and the suggested corrections of it.
+    let word = create_rw_signal(None::<(Range<usize>, String)>);
+    let suggestions = create_rw_signal(Vec::<String>::new());
+    let selected = create_rw_signal(0);
+
+    create_effect(move |_| {
+        if !show() {
+            return;
+        }
+        selected.set(0);
+        suggestions.set(Vec::new());
+        // The popup takes the keys for choosing a suggestion instead of the text area.
+        text_area.get_untracked().unwrap().blur().unwrap();
+        let cursor = cursor.get_untracked();
+        let range = untrack(|| {
+            misspelled.with(|misspelled| {
+                misspelled
+                    .iter()
+                    .find(|range| range.start <= cursor && cursor <= range.end)
+                    .cloned()
+            })
+        });
+        let found = range.map(|range| {
+            let misspelling = text.with_untracked(|text| text[range.clone()].to_string());
+            (range, misspelling)
+        });
+        word.set(found.clone());
+        if let Some((_, misspelling)) = found {
+            let language = settings.with_untracked(|settings| settings.spelling_language.clone());
+            spawn_local(async move {
+                let mut found = Inter::suggest_words(language, misspelling).await;
+                found.truncate(MAX_SUGGESTIONS);
+                suggestions.set(found);
+            });
+        }
+    });
+
+    let close = move || {
+        show.set(false);
+        text_area.get_untracked().unwrap().focus().unwrap();
+    };
+    // Replaces the word with the suggestion at an index, or adds it to the personal dictionary
+    // if the index is past the suggestions.
+    let choose = move |index: usize| {
+        let Some((range, misspelling)) = word.get_untracked() else {
+            return;
+        };
+        close();
+        if let Some(suggestion) =
+            suggestions.with_untracked(|suggestions| suggestions.get(index).cloned())
+        {
+            let end = range.start + suggestion.len();
+            context.edit(
+                Edit {
+                    offset: range.start,
+                    removed: misspelling,
+                    inserted: suggestion,
+                },
+                end..end,
+            );
+            return;
+        }
+        spawn_local(async move {
+            if let Some(error) = Inter::add_to_dictionary(misspelling.clone()).await {
+                report(problems, "spelling", &error);
+                return;
+            }
+            checked.update(|checked| {
+                checked.words.insert(misspelling, true);
+            });
+        });
+    };
+
+    window_event_listener(keydown, move |event| {
+        if !show.get_untracked() {
+            return;
+        }
+        let entries = suggestions.with_untracked(Vec::len) + 1;
+        match event.key().as_str() {
+            "Escape" => close(),
+            "Enter" => choose(selected.get_untracked()),
+            "ArrowDown" => selected.update(|selected| *selected = (*selected + 1).min(entries - 1)),
+            "ArrowUp" => selected.update(|selected| *selected = selected.saturating_sub(1)),
+            _ => return,
+        }
+        event.prevent_default();
+    });
+
+    view! {
+        <Popup show=show>
+            <Vertical>
+                {move || {
+                    let Some((_, misspelling)) = word() else {
+                        return view! { <div class="px-1">"no misspelled word at the caret"</div> }
+                            .into_view();
+                    };
+                    let mut entries = suggestions();
+                    let add = format!("add \u{201c}{misspelling}\u{201d} to the dictionary");
+                    entries.push(add);
+                    let suggested = entries.len() - 1;
+                    entries
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, entry)| {
+                            view! {
+                                <div
+                                    class="px-1 rounded cursor-pointer"
+                                    class=("bg-background", move || selected() == index)
+                                    class=("text-text", index < suggested)
+                                    on:click=move |_| choose(index)
+                                >
+                                    {entry}
+                                </div>
+                            }
+                        })
+                        .collect_view()
+                }}
+            </Vertical>
+        </Popup>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words_of(text: &str) -> Vec<&str> {
+        words(text).into_iter().map(|range| &text[range]).collect()
+    }
+
+    #[test]
+    fn finding_words_works() {
+        assert_eq!(
+            words_of("# Héllo, *wörld*\n42 times\\*2 'quoted' don't"),
+            ["Héllo", "wörld", "times", "quoted", "don't"]
+        );
+        assert!(words_of("").is_empty());
+        assert!(words_of("## 1984").is_empty());
+        assert_eq!(words_of("# a \\* é\nééé abc"), ["a", "é", "ééé", "abc"]);
+        assert_eq!(
+            words_of("# Learning C# *now\nünder"),
+            ["Learning", "C", "now", "ünder"]
+        );
+    }
+
+    #[test]
+    fn finding_misspellings_works() {
+        let checked = Checked {
+            language: "en_US".to_string(),
+            words: [("teh".to_string(), false), ("the".to_string(), true)].into(),
+        };
+        let text = "the teh cat teh";
+        assert_eq!(misspelled(text, &checked), [4..7, 12..15]);
+    }
+}
@@ -0,0 +1,409 @@
+//! Timed writing sprints towards a duration or a word target, optionally without deleting.
+
+use std::time::Duration;
+
+use js_sys::Date;
+use leptos::ev::keydown;
+use leptos::{
+    component, create_rw_signal, event_target_checked, event_target_value, set_interval,
+    use_context, view, window_event_listener, IntoView, RwSignal, SignalGetUntracked, SignalSet,
+    SignalUpdate, SignalWith, SignalWithUntracked,
+};
+
+use crate::writing::Writing;
+use crate::{Context, Horizontal, Popup, Vertical};
+
+/// How often a running sprint's countdown is updated and checked for its end.
+const TICK: Duration = Duration::from_secs(1);
+
+/// What ends a sprint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Minutes(u32),
+    Words(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprint {
+    pub target: Target,
+    /// Whether deleting text is blocked until the sprint ends.
+    pub strict: bool,
+    /// When the sprint started, in milliseconds since the epoch.
+    started: f64,
+    /// The time as of the last tick, in milliseconds since the epoch.
+    now: f64,
+    /// The net words written in the session before the sprint started.
+    words_before: i64,
+}
+
+impl Sprint {
+    pub const fn new(target: Target, strict: bool, now: f64, session_words: i64) -> Self {
+        Self {
+            target,
+            strict,
+            started: now,
+            now,
+            words_before: session_words,
+        }
+    }
+
+    /// Gets the net words written this sprint.
+    pub const fn words(&self, session_words: i64) -> i64 {
+        session_words - self.words_before
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_secs_f64(((self.now - self.started) / 1000.).max(0.))
+    }
+
+    /// Gets the time left of a timed sprint.
+    pub fn remaining(&self) -> Option<Duration> {
+        let Target::Minutes(minutes) = self.target else {
+            return None;
+        };
+        Some(Duration::from_secs(u64::from(minutes) * 60).saturating_sub(self.elapsed()))
+    }
+
+    pub fn is_over(&self, session_words: i64) -> bool {
+        match self.target {
+            Target::Minutes(_) => self.remaining() == Some(Duration::ZERO),
+            Target::Words(words) => self.words(session_words) >= i64::from(words),
+        }
+    }
+
+    pub fn summary(&self, session_words: i64) -> Summary {
+        Summary {
+            words: self.words(session_words),
+            elapsed: self.elapsed(),
+        }
+    }
+}
+
+/// What a finished sprint achieved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Summary {
+    pub words: i64,
+    pub elapsed: Duration,
+}
+
+impl Summary {
+    /// Gets the net words written per minute.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn words_per_minute(&self) -> f64 {
+        let minutes = self.elapsed.as_secs_f64() / 60.;
+        if minutes > 0. {
+            self.words as f64 / minutes
+        } else {
+            0.
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SprintState {
+    #[default]
+    Idle,
+    Running(Sprint),
+    Finished(Summary),
+}
+
+impl SprintState {
+    /// Whether a strict sprint is running, so deleting text is blocked.
+    pub const fn is_strict(&self) -> bool {
+        matches!(self, Self::Running(Sprint { strict: true, .. }))
+    }
+}
+
+/// Whether pressing a key in the text area deletes text, given whether any is selected and any
+/// modifier other than shift is held.
+pub fn deletes(key: &str, selected: bool, modified: bool) -> bool {
+    match key {
+        "Backspace" | "Delete" => true,
+        // Typing replaces the selection.
+        "Enter" | "Tab" => selected,
+        key => selected && !modified && key.chars().count() == 1,
+    }
+}
+
+/// Whether an input to the text area deletes text, given its type and whether any text is
+/// selected.
+pub fn input_deletes(input_type: &str, selected: bool) -> bool {
+    match input_type {
+        "historyUndo" => true,
+        input_type if input_type.starts_with("delete") => true,
+        // Pasting, dropping and autocorrect replace the selection.
+        input_type => selected && input_type.starts_with("insert"),
+    }
+}
+
+/// Formats a duration as minutes and seconds.
+pub fn clock(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Shows the countdown or word target progress, and the words written, of the running sprint.
+#[component]
+pub fn SprintProgress(show: RwSignal<bool>) -> impl IntoView {
+    let Context {
+        sprint, writing, ..
+    } = use_context::<Context>().unwrap();
+
+    move || {
+        let SprintState::Running(running) = sprint() else {
+            return ().into_view();
+        };
+        let words = running.words(writing.with(Writing::session_words));
+        let progress = match (running.target, running.remaining()) {
+            (_, Some(remaining)) => format!("{} {words:+}W", clock(remaining)),
+            (Target::Words(target), _) => format!("{} {words}/{target}W", clock(running.elapsed())),
+            (Target::Minutes(_), None) => unreachable!(),
+        };
+        view! {
+            <div
+                class="cursor-pointer text-accent"
+                title=if running.strict { "Strict sprint" } else { "Sprint" }
+                on:click=move |_| show.set(true)
+            >
+                {progress}
+            </div>
+        }
+        .into_view()
+    }
+}
+
+#[component]
+#[allow(clippy::too_many_lines)]
+pub fn SprintPopup(show: RwSignal<bool>) -> impl IntoView {
+    let Context {
+        text_area,
+        sprint,
+        writing,
+        ..
+    } = use_context::<Context>().unwrap();
+    let by_words = create_rw_signal(false);
+    let minutes = create_rw_signal(25);
+    let words = create_rw_signal(500);
+    let strict = create_rw_signal(false);
+
+    set_interval(
+        move || {
+            if !matches!(sprint.get_untracked(), SprintState::Running(_)) {
+                return;
+            }
+            let session_words = writing.with_untracked(Writing::session_words);
+            sprint.update(|state| {
+                let SprintState::Running(running) = state else {
+                    return;
+                };
+                running.now = Date::now();
+                if running.is_over(session_words) {
+                    *state = SprintState::Finished(running.summary(session_words));
+                }
+            });
+            if matches!(sprint.get_untracked(), SprintState::Finished(_)) {
+                show.set(true);
+            }
+        },
+        TICK,
+    );
+
+    let close = move || {
+        show.set(false);
+        if matches!(sprint.get_untracked(), SprintState::Finished(_)) {
+            sprint.set(SprintState::Idle);
+        }
+        text_area.get_untracked().unwrap().focus().unwrap();
+    };
+    let start = move || {
+        let target = if by_words.get_untracked() {
+            Target::Words(words.get_untracked())
+        } else {
+            Target::Minutes(minutes.get_untracked())
+        };
+        let session_words = writing.with_untracked(Writing::session_words);
+        sprint.set(SprintState::Running(Sprint::new(
+            target,
+            strict.get_untracked(),
+            Date::now(),
+            session_words,
+        )));
+        close();
+    };
+    window_event_listener(keydown, move |event| {
+        if event.key() == "Escape" && show.get_untracked() {
+            close();
+        }
+    });
+
+    let number = move |label: &'static str, value: RwSignal<u32>, step: u32| {
+        view! {
+            <Horizontal gap=1>
+                <div class="w-32 text-text">{label}</div>
+                <input
+                    type="number"
+                    class="px-1 rounded outline-none grow text-text bg-background"
+                    min=1
+                    step=step
+                    prop:value=value
+                    on:change=move |event| {
+                        if let Ok(number) = event_target_value(&event).parse::<u32>() {
+                            value.set(number.max(1));
+                        }
+                    }
+                />
+            </Horizontal>
+        }
+    };
+
+    view! {
+        <Popup show=show>
+            {move || match sprint() {
+                SprintState::Idle => {
+                    view! {
+                        <Vertical gap=2>
+                            <Horizontal gap=1>
+                                <div class="w-32 text-text">"until"</div>
+                                <select
+                                    class="px-1 rounded outline-none grow text-text bg-background"
+                                    prop:value=move || if by_words() { "words" } else { "time" }
+                                    on:change=move |event| {
+                                        by_words.set(event_target_value(&event) == "words");
+                                    }
+                                >
+                                    <option value="time">"a time is up"</option>
+                                    <option value="words">"a word count is reached"</option>
+                                </select>
+                            </Horizontal>
+                            {move || {
+                                if by_words() {
+                                    number("words", words, 50).into_view()
+                                } else {
+                                    number("minutes", minutes, 5).into_view()
+                                }
+                            }}
+                            <Horizontal gap=1>
+                                <div class="w-32 text-text">"strict"</div>
+                                <label class="flex items-center gap-1 cursor-pointer">
+                                    <input
+                                        type="checkbox"
+                                        class="cursor-pointer accent-fade"
+                                        prop:checked=strict
+                                        on:change=move |event| strict.set(event_target_checked(&event))
+                                    />
+                                    "block deleting until the sprint ends"
+                                </label>
+                            </Horizontal>
+                            <div class="self-end cursor-pointer text-accent" on:click=move |_| start()>
+                                "start"
+                            </div>
+                        </Vertical>
+                    }
+                        .into_view()
+                }
+                SprintState::Running(running) => {
+                    let written = running.words(writing.with(Writing::session_words));
+                    view! {
+                        <Vertical gap=2 class="px-1">
+                            <div class="text-text">
+                                {format!(
+                                    "{written} words in {}{}",
+                                    clock(running.elapsed()),
+                                    if running.strict { ", deleting is blocked" } else { "" },
+                                )}
+                            </div>
+                            <div
+                                class="self-end cursor-pointer text-accent"
+                                on:click=move |_| {
+                                    let session_words = writing
+                                        .with_untracked(Writing::session_words);
+                                    sprint.set(SprintState::Finished(running.summary(session_words)));
+                                }
+                            >
+                                "stop"
+                            </div>
+                        </Vertical>
+                    }
+                        .into_view()
+                }
+                SprintState::Finished(summary) => {
+                    view! {
+                        <Vertical gap=2 class="px-1">
+                            <div class="text-text">"sprint over"</div>
+                            <Horizontal class="justify-between">
+                                <div>"words written"</div>
+                                <div class="text-text">{summary.words}</div>
+                            </Horizontal>
+                            <Horizontal class="justify-between">
+                                <div>"time"</div>
+                                <div class="text-text">{clock(summary.elapsed)}</div>
+                            </Horizontal>
+                            <Horizontal class="justify-between">
+                                <div>"words per minute"</div>
+                                <div class="text-text">
+                                    {format!("{:.1}", summary.words_per_minute())}
+                                </div>
+                            </Horizontal>
+                            <div class="self-end cursor-pointer text-accent" on:click=move |_| close()>
+                                "done"
+                            </div>
+                        </Vertical>
+                    }
+                        .into_view()
+                }
+            }}
+        </Popup>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ending_sprints_works() {
+        let mut sprint = Sprint::new(Target::Minutes(10), false, 1000., 40);
+        sprint.now = 541_000.;
+        assert_eq!(sprint.remaining(), Some(Duration::from_secs(60)));
+        assert!(!sprint.is_over(100));
+        sprint.now += 90_000.;
+        assert_eq!(sprint.remaining(), Some(Duration::ZERO));
+        assert!(sprint.is_over(40));
+        let summary = sprint.summary(265);
+        assert_eq!(summary.words, 225);
+        assert!((summary.words_per_minute() - 21.428).abs() < 0.01);
+
+        let sprint = Sprint::new(Target::Words(100), true, 0., -20);
+        assert_eq!(sprint.remaining(), None);
+        assert!(!sprint.is_over(79));
+        assert!(sprint.is_over(80));
+        assert!(SprintState::Running(sprint).is_strict());
+        assert!(!SprintState::Idle.is_strict());
+    }
+
+    #[test]
+    fn finding_deleting_keys_works() {
+        assert!(deletes("Backspace", false, false));
+        assert!(deletes("Delete", false, true));
+        assert!(!deletes("a", false, false));
+        assert!(deletes("a", true, false));
+        assert!(!deletes("c", true, true));
+        assert!(deletes("Enter", true, false));
+        assert!(!deletes("Enter", false, false));
+        assert!(!deletes("ArrowLeft", true, false));
+        assert!(input_deletes("historyUndo", false));
+        assert!(input_deletes("deleteByCut", true));
+        assert!(input_deletes("deleteByDrag", false));
+        assert!(input_deletes("insertFromPaste", true));
+        assert!(input_deletes("insertReplacementText", true));
+        assert!(!input_deletes("insertFromPaste", false));
+        assert!(!input_deletes("insertText", false));
+        assert!(!input_deletes("historyRedo", true));
+    }
+
+    #[test]
+    fn formatting_clocks_works() {
+        assert_eq!(clock(Duration::from_secs(0)), "0:00");
+        assert_eq!(clock(Duration::from_secs(754)), "12:34");
+    }
+}
@@ -0,0 +1,362 @@
+//! Readability and reading time statistics of the plain text of the document.
+
+use std::collections::HashMap;
+use std::ops::{Add, Range};
+
+use leptos::ev::keydown;
+use leptos::{
+    component, create_memo, store_value, use_context, view, window_event_listener, CollectView,
+    IntoView, RwSignal, SignalGetUntracked, SignalSet, SignalWith,
+};
+use typewriter_core::document::headings;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::fold::section_body;
+use crate::outline::paragraphs;
+use crate::spelling::words;
+use crate::{Context, Horizontal, Popup, Vertical};
+
+/// The words read silently per minute by an average adult.
+const READING_WPM: f64 = 238.;
+
+/// The words spoken per minute by an average speaker.
+const SPEAKING_WPM: f64 = 150.;
+
+/// What the readability scores are computed from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counts {
+    pub paragraphs: usize,
+    pub sentences: usize,
+    pub words: usize,
+    pub syllables: usize,
+    /// The letters and digits in the words.
+    pub letters: usize,
+}
+
+impl Add for Counts {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            paragraphs: self.paragraphs + other.paragraphs,
+            sentences: self.sentences + other.sentences,
+            words: self.words + other.words,
+            syllables: self.syllables + other.syllables,
+            letters: self.letters + other.letters,
+        }
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+impl Counts {
+    /// Counts the sentences, words, syllables and letters of a paragraph of Markdown.
+    pub fn of_paragraph(paragraph: &str) -> Self {
+        let words = words(paragraph);
+        if words.is_empty() {
+            return Self::default();
+        }
+        Self {
+            paragraphs: 1,
+            sentences: paragraph
+                .split_sentence_bounds()
+                .filter(|sentence| sentence.chars().any(char::is_alphabetic))
+                .count(),
+            words: words.len(),
+            syllables: words
+                .iter()
+                .map(|range| syllables(&paragraph[range.clone()]))
+                .sum(),
+            letters: words
+                .iter()
+                .flat_map(|range| paragraph[range.clone()].chars())
+                .filter(|char| char.is_alphanumeric())
+                .count(),
+        }
+    }
+
+    /// Averages, or nothing if there are no sentences to average over.
+    fn per_sentence(self, count: usize) -> Option<f64> {
+        (self.sentences > 0).then(|| count as f64 / self.sentences as f64)
+    }
+
+    fn per_word(self, count: usize) -> Option<f64> {
+        (self.words > 0).then(|| count as f64 / self.words as f64)
+    }
+
+    pub fn words_per_sentence(self) -> Option<f64> {
+        self.per_sentence(self.words)
+    }
+
+    /// The Flesch reading ease, from about 0 for academic papers to 100 for children's books.
+    pub fn reading_ease(self) -> Option<f64> {
+        Some(84.6f64.mul_add(
+            -self.per_word(self.syllables)?,
+            1.015f64.mul_add(-self.words_per_sentence()?, 206.835),
+        ))
+    }
+
+    /// The Flesch–Kincaid grade level, the US school grade the text is written for.
+    pub fn grade_level(self) -> Option<f64> {
+        Some(0.39f64.mul_add(
+            self.words_per_sentence()?,
+            11.8f64.mul_add(self.per_word(self.syllables)?, -15.59),
+        ))
+    }
+
+    /// The automated readability index, a grade level from letters rather than syllables.
+    pub fn automated_readability(self) -> Option<f64> {
+        Some(4.71f64.mul_add(
+            self.per_word(self.letters)?,
+            0.5f64.mul_add(self.words_per_sentence()?, -21.43),
+        ))
+    }
+
+    /// The Coleman–Liau index, a grade level from letters and sentences per hundred words.
+    pub fn coleman_liau(self) -> Option<f64> {
+        let letters = self.per_word(self.letters)? * 100.;
+        let sentences = self.per_word(self.sentences)? * 100.;
+        Some(0.0588f64.mul_add(letters, 0.296f64.mul_add(-sentences, -15.8)))
+    }
+}
+
+/// Estimates the syllables of a word by counting its groups of vowels, not counting a silent `e`
+/// at the end.
+pub fn syllables(word: &str) -> usize {
+    let word = word.to_lowercase();
+    let mut count = 0;
+    let mut after_vowel = false;
+    for char in word.chars() {
+        let vowel = "aeiouyàáâäèéêëìíîïòóôöùúûü".contains(char);
+        if vowel && !after_vowel {
+            count += 1;
+        }
+        after_vowel = vowel;
+    }
+    if count > 1 && word.ends_with('e') && !word.ends_with("le") && !word.ends_with("ee") {
+        count -= 1;
+    }
+    count.max(1)
+}
+
+/// Counts every paragraph of some text, reusing the counts of paragraphs that haven't changed
+/// since the last time so only edited ones are counted again.
+pub fn count_paragraphs(
+    text: &str,
+    cache: &mut HashMap<String, Counts>,
+) -> Vec<(Range<usize>, Counts)> {
+    let mut counted = HashMap::with_capacity(cache.len());
+    let counts = paragraphs(text)
+        .into_iter()
+        .map(|range| {
+            let paragraph = &text[range.clone()];
+            let counts = cache
+                .remove(paragraph)
+                .or_else(|| counted.get(paragraph).copied())
+                .unwrap_or_else(|| Counts::of_paragraph(paragraph));
+            counted.insert(paragraph.to_string(), counts);
+            (range, counts)
+        })
+        .collect();
+    *cache = counted;
+    counts
+}
+
+/// Describes a number of minutes as a time to the nearest minute.
+pub fn duration(minutes: f64) -> String {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let minutes = minutes.round() as usize;
+    match (minutes / 60, minutes % 60) {
+        (0, 0) => "under a minute".to_string(),
+        (0, minutes) => format!("{minutes} min"),
+        (hours, 0) => format!("{hours} h"),
+        (hours, minutes) => format!("{hours} h {minutes} min"),
+    }
+}
+
+#[component]
+#[allow(clippy::too_many_lines, clippy::cast_precision_loss)]
+pub fn StatisticsPanel(show: RwSignal<bool>) -> impl IntoView {
+    let context = use_context::<Context>().unwrap();
+    let Context {
+        text,
+        text_area,
+        reveal,
+        ..
+    } = context;
+    let cache = store_value(HashMap::new());
+
+    // Only counted while the panel is shown, since it's the only thing that needs the counts.
+    let statistics = create_memo(move |_| {
+        if !show() {
+            return None;
+        }
+        text.with(|text| {
+            let paragraphs = cache.try_update_value(|cache| count_paragraphs(text, cache))?;
+            let total = paragraphs
+                .iter()
+                .fold(Counts::default(), |total, (_, counts)| total + *counts);
+            let headings = headings(text);
+            let sections = (0..headings.len())
+                .map(|index| {
+                    let body = section_body(text, &headings, index);
+                    let words = paragraphs
+                        .iter()
+                        .filter(|(range, _)| body.start <= range.start && range.end <= body.end)
+                        .map(|(_, counts)| counts.words)
+                        .sum::<usize>();
+                    (headings[index].clone(), words)
+                })
+                .collect::<Vec<_>>();
+            Some((total, sections))
+        })
+    });
+
+    let close = move || {
+        show.set(false);
+        text_area.get_untracked().unwrap().focus().unwrap();
+    };
+    window_event_listener(keydown, move |event| {
+        if event.key() == "Escape" && show.get_untracked() {
+            close();
+        }
+    });
+
+    let score =
+        |score: Option<f64>| score.map_or_else(|| "-".to_string(), |score| format!("{score:.1}"));
+
+    view! {
+        <Popup show=show>
+            {move || {
+                let Some((total, sections)) = statistics() else {
+                    return ().into_view();
+                };
+                let rows = [
+                    ("reading time", duration(total.words as f64 / READING_WPM)),
+                    ("speaking time", duration(total.words as f64 / SPEAKING_WPM)),
+                    ("words", total.words.to_string()),
+                    ("sentences", total.sentences.to_string()),
+                    ("paragraphs", total.paragraphs.to_string()),
+                    ("words per sentence", score(total.words_per_sentence())),
+                    ("Flesch reading ease", score(total.reading_ease())),
+                    ("Flesch–Kincaid grade", score(total.grade_level())),
+                    ("automated readability index", score(total.automated_readability())),
+                    ("Coleman–Liau index", score(total.coleman_liau())),
+                ];
+                let min_depth = sections
+                    .iter()
+                    .map(|(heading, _)| heading.depth)
+                    .min()
+                    .unwrap_or_default();
+                view! {
+                    <Vertical gap=2>
+                        <Vertical>
+                            {rows
+                                .into_iter()
+                                .map(|(name, value)| {
+                                    view! {
+                                        <Horizontal class="justify-between px-1">
+                                            <div>{name}</div>
+                                            <div class="text-text">{value}</div>
+                                        </Horizontal>
+                                    }
+                                })
+                                .collect_view()}
+                        </Vertical>
+                        {(!sections.is_empty())
+                            .then(|| view! { <div class="px-1">"words per section"</div> })}
+                        <Vertical class="overflow-y-auto max-h-60">
+                            {sections
+                                .into_iter()
+                                .map(|(heading, words)| {
+                                    let indent = format!("{}rem", heading.depth - min_depth);
+                                    let offset = heading.offset;
+                                    view! {
+                                        <Horizontal
+                                            class="justify-between px-1 rounded cursor-pointer hover:bg-background"
+                                            on:click=move |_| {
+                                                close();
+                                                context.select_range(offset..offset);
+                                                reveal.set(Some(offset));
+                                            }
+                                        >
+                                            <div class="truncate" style:margin-left=indent>
+                                                {heading.title}
+                                            </div>
+                                            <div class="text-text">{words}</div>
+                                        </Horizontal>
+                                    }
+                                })
+                                .collect_view()}
+                        </Vertical>
+                    </Vertical>
+                }
+                    .into_view()
+            }}
+        </Popup>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counting_syllables_works() {
+        for (word, count) in [
+            ("the", 1),
+            ("cat", 1),
+            ("make", 1),
+            ("table", 2),
+            ("reading", 2),
+            ("beautiful", 3),
+            ("free", 1),
+            ("rhythm", 1),
+            ("Readability", 5),
+        ] {
+            assert_eq!(syllables(word), count, "{word}");
+        }
+    }
+
+    #[test]
+    fn counting_paragraphs_works() {
+        let counts = Counts::of_paragraph("The cat sat. It *was* happy!");
+        assert_eq!(
+            counts,
+            Counts {
+                paragraphs: 1,
+                sentences: 2,
+                words: 6,
+                syllables: 7,
+                letters: 19,
+            }
+        );
+        assert_eq!(counts.words_per_sentence(), Some(3.));
+        assert!(counts.reading_ease().unwrap() > 90.);
+        assert!(counts.grade_level().unwrap() < 2.);
+        assert_eq!(Counts::of_paragraph("42").reading_ease(), None);
+    }
+
+    #[test]
+    fn caching_counts_works() {
+        let mut cache = HashMap::new();
+        let counts = count_paragraphs("One two.\n\nThree.\n\nOne two.", &mut cache);
+        assert_eq!(
+            counts
+                .iter()
+                .map(|(_, counts)| counts.words)
+                .collect::<Vec<_>>(),
+            [2, 1, 2]
+        );
+        assert_eq!(cache.len(), 2);
+        count_paragraphs("Three.", &mut cache);
+        assert_eq!(cache.keys().collect::<Vec<_>>(), ["Three."]);
+    }
+
+    #[test]
+    fn describing_durations_works() {
+        assert_eq!(duration(0.2), "under a minute");
+        assert_eq!(duration(1.6), "2 min");
+        assert_eq!(duration(120.), "2 h");
+        assert_eq!(duration(135.), "2 h 15 min");
+    }
+}
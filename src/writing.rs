@@ -0,0 +1,518 @@
+//! Word goals, and the log of the words written each day in each file that writing sessions add
+//! to.
+
+use std::collections::BTreeMap;
+use std::mem::take;
+use std::path::Path;
+use std::time::Duration;
+
+use js_sys::Date;
+use leptos::ev::keydown;
+use leptos::{
+    component, event_target_value, set_interval, spawn_local, use_context, view,
+    window_event_listener, CollectView, IntoView, RwSignal, SignalGetUntracked, SignalSet,
+    SignalUpdate, SignalWith,
+};
+use serde::Deserialize;
+
+use crate::history::Edit;
+use crate::problems::report;
+use crate::{Context, Horizontal, Inter, Popup, Vertical};
+
+/// How often the words written are logged and the session timer is updated.
+const TICK: Duration = Duration::from_secs(30);
+
+/// How many weeks of the log the history shows.
+const HISTORY_WEEKS: i64 = 8;
+
+/// How many days with words written the history lists.
+const HISTORY_DAYS: usize = 14;
+
+/// The words written each day in each file, and the word goals of files, as stored by the
+/// backend.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct WritingLog {
+    /// The net words written in each file, by day as `yyyy-mm-dd` and then by path. Untitled
+    /// documents have an empty path.
+    pub days: BTreeMap<String, BTreeMap<String, i64>>,
+    /// The word goals of files, by path.
+    pub goals: BTreeMap<String, usize>,
+}
+
+impl WritingLog {
+    /// Gets the net words written in every file on a day.
+    pub fn written_on(&self, day: &str) -> i64 {
+        self.days
+            .get(day)
+            .map(|files| files.values().sum())
+            .unwrap_or_default()
+    }
+
+    /// Counts the days in a row up to today that met a daily goal, or that had any words written
+    /// if there is no goal. Today only breaks the streak once it's over.
+    pub fn streak(&self, today: &str, goal: usize) -> usize {
+        let Some(today) = day_number(today) else {
+            return 0;
+        };
+        let goal = i64::try_from(goal.max(1)).unwrap_or(i64::MAX);
+        let met = |day: i64| self.written_on(&day_string(day)) >= goal;
+        let last = if met(today) { today } else { today - 1 };
+        // A streak can't be longer than the days in the log.
+        (0..i64::try_from(self.days.len()).unwrap_or(i64::MAX))
+            .take_while(|&days| met(last - days))
+            .count()
+    }
+
+    fn add(&mut self, day: &str, path: &str, words: i64) {
+        *self
+            .days
+            .entry(day.to_string())
+            .or_default()
+            .entry(path.to_string())
+            .or_default() += words;
+    }
+}
+
+/// The writing log and the current writing session.
+#[derive(Debug, Clone, Default)]
+pub struct Writing {
+    pub log: WritingLog,
+    /// The net words written in each file since they were last logged.
+    pending: BTreeMap<String, i64>,
+    /// When the session started, in milliseconds since the epoch.
+    session_start: f64,
+    /// The net words written this session.
+    session_words: i64,
+    /// The time the session timer shows up to, in milliseconds since the epoch.
+    now: f64,
+}
+
+impl Writing {
+    pub fn new(now: f64) -> Self {
+        Self {
+            session_start: now,
+            now,
+            ..Self::default()
+        }
+    }
+
+    /// Records net words written in a file by an edit.
+    pub fn record(&mut self, path: &str, words: i64) {
+        *self.pending.entry(path.to_string()).or_default() += words;
+        self.session_words += words;
+    }
+
+    /// Gets the net words written this session.
+    pub const fn session_words(&self) -> i64 {
+        self.session_words
+    }
+
+    /// Gets the net words written in every file today, including those not logged yet.
+    pub fn written_today(&self) -> i64 {
+        self.log.written_on(&today()) + self.pending.values().sum::<i64>()
+    }
+}
+
+/// Counts the net words an edit adds to some text, as whitespace-separated words.
+pub fn words_added(text: &str, edit: &Edit) -> i64 {
+    // Only the words touching the edit can change, so only they're counted.
+    let end = edit.offset + edit.removed.len();
+    let start = text[..edit.offset]
+        .rfind(char::is_whitespace)
+        .unwrap_or_default();
+    let end = text[end..]
+        .find(char::is_whitespace)
+        .map_or(text.len(), |after| end + after);
+    let after = [
+        &text[start..edit.offset],
+        &edit.inserted,
+        &text[edit.offset + edit.removed.len()..end],
+    ]
+    .concat();
+    let count = |text: &str| i64::try_from(text.split_whitespace().count()).unwrap_or_default();
+    count(&after) - count(&text[start..end])
+}
+
+/// Gets the key of a file in the writing log, which is empty for untitled documents.
+pub fn path_key(path: Option<&Path>) -> String {
+    path.map(|path| path.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Converts a day like `2024-01-31` to the number of days since 1970-01-01.
+pub fn day_number(day: &str) -> Option<i64> {
+    let mut parts = day.splitn(3, '-').map(str::parse::<i64>);
+    let (year, month, day) = (
+        parts.next()?.ok()?,
+        parts.next()?.ok()?,
+        parts.next()?.ok()?,
+    );
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    // Counting from March puts the leap day at the end of the year.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Some(era * 146_097 + day_of_era - 719_468)
+}
+
+/// Converts a number of days since 1970-01-01 to a day like `2024-01-31`.
+pub fn day_string(number: i64) -> String {
+    let number = number + 719_468;
+    let era = number.div_euclid(146_097);
+    let day_of_era = number - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let march_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * march_month + 2) / 5 + 1;
+    let month = if march_month < 10 {
+        march_month + 3
+    } else {
+        march_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Gets the local day like `2024-01-31`.
+pub fn today() -> String {
+    let date = Date::new_0();
+    format!(
+        "{:04}-{:02}-{:02}",
+        date.get_full_year(),
+        date.get_month() + 1,
+        date.get_date()
+    )
+}
+
+/// Loads the writing log, then regularly logs the words written since and updates the session
+/// timer.
+pub fn track_writing(context: &Context) {
+    let Context {
+        writing, problems, ..
+    } = *context;
+    spawn_local(async move {
+        let (log, error) = Inter::get_writing_log().await;
+        if let Some(error) = error {
+            report(problems, "writing log", &error);
+        }
+        writing.update(|writing| writing.log = log);
+    });
+    set_interval(
+        move || {
+            writing.update(|writing| writing.now = Date::now());
+            spawn_local(log_writing(writing, problems));
+        },
+        TICK,
+    );
+}
+
+/// Logs the words written since they were last logged, reporting what went wrong saving them.
+#[allow(clippy::future_not_send)]
+pub async fn log_writing(writing: RwSignal<Writing>, problems: RwSignal<Vec<String>>) {
+    let day = today();
+    let pending = writing
+        .try_update(|writing| {
+            let pending = take(&mut writing.pending);
+            for (path, words) in &pending {
+                writing.log.add(&day, path, *words);
+            }
+            pending
+        })
+        .unwrap_or_default();
+    for (path, words) in pending.into_iter().filter(|(_, words)| *words != 0) {
+        if let Some(error) = Inter::log_words(day.clone(), path, words).await {
+            report(problems, "writing log", &error);
+        }
+    }
+}
+
+/// Shows a thin bar filled up to some fraction.
+fn progress_bar(fraction: f64, title: String) -> impl IntoView {
+    view! {
+        <div class="self-center w-12 h-1 overflow-hidden rounded bg-highlight" title=title>
+            <div
+                class="h-full bg-accent"
+                style:width=format!("{}%", (fraction * 100.).clamp(0., 100.))
+            />
+        </div>
+    }
+}
+
+/// Shows the progress towards the document and daily goals, and the session timer.
+#[component]
+#[allow(clippy::cast_precision_loss)]
+pub fn WritingProgress(show_history: RwSignal<bool>) -> impl IntoView {
+    let Context {
+        text,
+        save_path: (read_save_path, _),
+        settings,
+        writing,
+        ..
+    } = use_context::<Context>().unwrap();
+
+    view! {
+        <Horizontal gap=2 class="cursor-pointer" on:click=move |_| show_history.set(true)>
+            {move || {
+                let goal = writing
+                    .with(|writing| {
+                        writing.log.goals.get(&path_key(read_save_path().as_deref())).copied()
+                    })?;
+                let words = text.with(|text| text.split_whitespace().count());
+                let title = format!("{words} of {goal} words in this document");
+                Some(progress_bar(words as f64 / goal as f64, title))
+            }}
+            {move || {
+                let goal = settings.with(|settings| settings.daily_goal);
+                (goal > 0)
+                    .then(|| {
+                        let words = writing.with(Writing::written_today);
+                        let title = format!("{words} of {goal} words today");
+                        progress_bar(words as f64 / goal as f64, title)
+                    })
+            }}
+            {move || {
+                writing
+                    .with(|writing| {
+                        #[allow(clippy::cast_possible_truncation)]
+                        let minutes = ((writing.now - writing.session_start) / 60_000.) as i64;
+                        format!(
+                            "{}:{:02} {:+}W",
+                            minutes / 60,
+                            minutes % 60,
+                            writing.session_words,
+                        )
+                    })
+            }}
+        </Horizontal>
+    }
+}
+
+#[component]
+#[allow(clippy::too_many_lines)]
+pub fn WritingHistory(show: RwSignal<bool>) -> impl IntoView {
+    let Context {
+        text_area,
+        save_path: (read_save_path, _),
+        settings,
+        writing,
+        problems,
+        ..
+    } = use_context::<Context>().unwrap();
+
+    window_event_listener(keydown, move |event| {
+        if event.key() == "Escape" && show.get_untracked() {
+            show.set(false);
+            text_area.get_untracked().unwrap().focus().unwrap();
+        }
+    });
+
+    let set_document_goal = move |goal: usize| {
+        let Some(path) = read_save_path.get_untracked() else {
+            return;
+        };
+        let path = path_key(Some(&path));
+        writing.update(|writing| {
+            if goal == 0 {
+                writing.log.goals.remove(&path);
+            } else {
+                writing.log.goals.insert(path.clone(), goal);
+            }
+        });
+        spawn_local(async move {
+            if let Some(error) = Inter::set_document_goal(path, (goal > 0).then_some(goal)).await {
+                report(problems, "writing log", &error);
+            }
+        });
+    };
+
+    view! {
+        <Popup show=show>
+            {move || {
+                if !show() {
+                    return ().into_view();
+                }
+                let today = today();
+                let goal = settings.with(|settings| settings.daily_goal);
+                let Some(today_number) = day_number(&today) else {
+                    return ().into_view();
+                };
+                // Weeks start on Monday, and 1970-01-01 was a Thursday.
+                let first = today_number - (today_number + 3).rem_euclid(7)
+                    - (HISTORY_WEEKS - 1) * 7;
+                writing
+                    .with(|writing| {
+                        let streak = writing.log.streak(&today, goal);
+                        let weeks = (0..HISTORY_WEEKS)
+                            .map(|week| {
+                                let days = (0..7)
+                                    .map(|weekday| {
+                                        let number = first + week * 7 + weekday;
+                                        let day = day_string(number);
+                                        let words = if day == today {
+                                            writing.written_today()
+                                        } else {
+                                            writing.log.written_on(&day)
+                                        };
+                                        let met = words > 0
+                                            && usize::try_from(words).unwrap_or_default()
+                                                >= goal;
+                                        view! {
+                                            <div
+                                                class="rounded size-3"
+                                                class=("invisible", number > today_number)
+                                                class=("bg-accent", met)
+                                                class=("bg-fade", words > 0 && !met)
+                                                class=("bg-background", words <= 0)
+                                                title=format!("{day}: {words} words")
+                                            />
+                                        }
+                                    })
+                                    .collect_view();
+                                view! { <Vertical gap=1>{days}</Vertical> }
+                            })
+                            .collect_view();
+                        let days = writing
+                            .log
+                            .days
+                            .iter()
+                            .rev()
+                            .filter(|(_, files)| files.values().any(|words| *words != 0))
+                            .take(HISTORY_DAYS)
+                            .map(|(day, files)| {
+                                let files = files
+                                    .iter()
+                                    .filter(|(_, words)| **words != 0)
+                                    .map(|(path, words)| {
+                                        let name = Path::new(path)
+                                            .file_name()
+                                            .map_or_else(
+                                                || "untitled".to_string(),
+                                                |name| name.to_string_lossy().to_string(),
+                                            );
+                                        let path = path.clone();
+                                        let words = format!("{words:+}");
+                                        view! {
+                                            <Horizontal class="justify-between pl-4">
+                                                <div class="truncate" title=path>
+                                                    {name}
+                                                </div>
+                                                <div>{words}</div>
+                                            </Horizontal>
+                                        }
+                                    })
+                                    .collect_view();
+                                let total = writing.log.written_on(day);
+                                let day = day.clone();
+                                view! {
+                                    <Horizontal class="justify-between text-text">
+                                        <div>{day}</div>
+                                        <div>{total}</div>
+                                    </Horizontal>
+                                    {files}
+                                }
+                            })
+                            .collect_view();
+                        let document_goal = read_save_path()
+                            .and_then(|path| {
+                                writing.log.goals.get(&path_key(Some(&path))).copied()
+                            })
+                            .unwrap_or_default();
+                        view! {
+                            <Vertical gap=2 class="px-1">
+                                <Horizontal class="justify-between">
+                                    <div class="text-text">
+                                        {format!(
+                                            "{streak} day{} streak",
+                                            if streak == 1 { "" } else { "s" },
+                                        )}
+                                    </div>
+                                    <Horizontal gap=1>
+                                        <div class="text-text">"document goal"</div>
+                                        <input
+                                            type="number"
+                                            class="w-24 px-1 rounded outline-none text-text bg-background"
+                                            min=0
+                                            step=100
+                                            disabled=move || read_save_path().is_none()
+                                            prop:value=document_goal
+                                            on:change=move |event| {
+                                                if let Ok(goal) = event_target_value(&event).parse() {
+                                                    set_document_goal(goal);
+                                                }
+                                            }
+                                        />
+                                    </Horizontal>
+                                </Horizontal>
+                                <Horizontal gap=1 class="justify-center">
+                                    {weeks}
+                                </Horizontal>
+                                <Vertical class="overflow-y-auto max-h-60">{days}</Vertical>
+                            </Vertical>
+                        }
+                    })
+                    .into_view()
+            }}
+        </Popup>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn added(text: &str, offset: usize, removed: &str, inserted: &str) -> i64 {
+        words_added(
+            text,
+            &Edit {
+                offset,
+                removed: removed.to_string(),
+                inserted: inserted.to_string(),
+            },
+        )
+    }
+
+    #[test]
+    fn counting_words_added_works() {
+        assert_eq!(added("one two", 7, "", " three"), 1);
+        assert_eq!(added("one two", 3, "", "x"), 0);
+        assert_eq!(added("one two", 3, " ", ""), -1);
+        assert_eq!(added("onetwo", 3, "", " "), 1);
+        assert_eq!(added("one two three", 4, "two ", ""), -1);
+        assert_eq!(added("", 0, "", "a b c"), 3);
+    }
+
+    #[test]
+    fn converting_days_works() {
+        for (day, number) in [
+            ("1970-01-01", 0),
+            ("1969-12-31", -1),
+            ("2000-02-29", 11_016),
+            ("2024-03-01", 19_783),
+        ] {
+            assert_eq!(day_number(day), Some(number), "{day}");
+            assert_eq!(day_string(number), day);
+        }
+        assert_eq!(day_number("2024-13-01"), None);
+        assert_eq!(day_number("yesterday"), None);
+    }
+
+    #[test]
+    fn counting_streaks_works() {
+        let mut log = WritingLog::default();
+        log.add("2024-02-27", "a.md", 600);
+        log.add("2024-02-28", "a.md", 200);
+        log.add("2024-02-28", "b.md", 400);
+        log.add("2024-02-29", "a.md", 100);
+        assert_eq!(log.written_on("2024-02-28"), 600);
+        assert_eq!(log.streak("2024-02-29", 500), 2);
+        assert_eq!(log.streak("2024-02-29", 0), 3);
+        assert_eq!(log.streak("2024-03-01", 0), 3);
+        assert_eq!(log.streak("2024-03-02", 0), 0);
+    }
+}
@@ -6,16 +6,24 @@ module.exports = {
   ],
   theme: {
     extend: {
+      // These are set from the settings, see `src/settings.rs`.
       fontFamily: {
-        'sans': ["DejaVu"],
+        'sans': ["var(--font-family)"],
+      },
+      fontSize: {
+        'editor': ["var(--font-size)", "var(--line-height)"],
+      },
+      spacing: {
+        'line': "var(--line-height)",
       },
       colors: {
-        'background': '#222222',
-        'highlight': '#080707',
-        'accent': '#888888',
-        'fade': '#545354',
-        'text': "#EEEEEE",
-        'caret': "#EEEEEE",
+        'background': 'rgb(var(--color-background) / <alpha-value>)',
+        'highlight': 'rgb(var(--color-highlight) / <alpha-value>)',
+        'accent': 'rgb(var(--color-accent) / <alpha-value>)',
+        'fade': 'rgb(var(--color-fade) / <alpha-value>)',
+        'text': 'rgb(var(--color-text) / <alpha-value>)',
+        'caret': 'rgb(var(--color-caret) / <alpha-value>)',
+        'error': 'rgb(var(--color-error) / <alpha-value>)',
       },
     },
   },